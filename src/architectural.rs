@@ -0,0 +1,87 @@
+//! Perspectives of the architectural visualization, with control over the vanishing points of the verticals
+
+use crate::math::{self, Matrix, Vec3};
+use crate::ProjectionMatrixBuilder;
+
+/// Number of vanishing points of the perspective of a box aligned with the vertical axis
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VanishingPoints {
+    /// The verticals are kept parallel in the screen, shifting the lens instead of tilting the camera
+    Two,
+    /// The verticals converge in a third vanishing point when the camera looks up or down
+    Three,
+}
+
+impl ProjectionMatrixBuilder {
+    /// Returns the view and projection matrices of a camera placed in `eye` looking at `target`, with `up` as the
+    /// vertical axis. With [`VanishingPoints::Two`] the camera is kept level and the projection is shifted to bring
+    /// the target to the center of the screen, like the shift lenses of the architectural photography.
+    ///
+    /// # Panic
+    /// With two vanishing points, if the camera looks along the vertical axis
+    pub fn architectural_view(&self, eye: Vec3, target: Vec3, up: Vec3, vanishing_points: VanishingPoints)
+        -> (Matrix, Matrix) {
+        match vanishing_points {
+            VanishingPoints::Three => (math::look_at(eye, target, up), self.build().matrix()),
+            VanishingPoints::Two => {
+                let up = math::normalize(up);
+                let direction = math::sub(target, eye);
+                let level = math::sub(direction, math::scale(up, math::dot(direction, up)));
+                if math::length(level) < f32::EPSILON {
+                    panic!("The verticals can't be kept parallel looking along the vertical axis");
+                }
+                let forward = math::normalize(level);
+                let view = math::view_matrix(eye, math::cross(up, forward), up, forward);
+
+                let mut projection = self.build().matrix();
+                let [x, y, z, _] = math::transform_point(target, &view);
+                projection[2][0] -= (x * projection[0][0] + z * projection[2][0]) / z;
+                projection[2][1] -= (y * projection[1][1] + z * projection[2][1]) / z;
+                (view, projection)
+            }
+        }
+    }
+}
+
+/// Returns the position in normalized device coordinates where the lines with the given world space direction
+/// converge, or `None` if they are parallel in the screen or the point is behind the camera
+pub fn vanishing_point(direction: Vec3, view_projection: &Matrix) -> Option<(f32, f32)> {
+    let [x, y, _, w] = math::transform([direction[0], direction[1], direction[2], 0.0], view_projection);
+    (w > f32::EPSILON).then(|| (x / w, y / w))
+}
+
+#[test]
+fn two_point_perspective() {
+    let builder = ProjectionMatrixBuilder::new().set_near(0.1).set_width(720);
+    let (eye, target) = ([0.0, 1.5, 0.0], [10.0, 20.0, 10.0]);
+    let (view, projection) = builder.architectural_view(eye, target, [0.0, 1.0, 0.0], VanishingPoints::Two);
+    let view_projection = math::multiply(&view, &projection);
+
+    let ndc = |point: Vec3| {
+        let [x, y, _, w] = math::transform_point(point, &view_projection);
+        (x / w, y / w)
+    };
+    let (x, y) = ndc(target);
+    assert!(x.abs() < 0.0001 && y.abs() < 0.0001);
+    // the vertical edges keep the same horizontal position in the screen
+    let (bottom, top) = (ndc([8.0, 0.0, 12.0]), ndc([8.0, 30.0, 12.0]));
+    assert!((bottom.0 - top.0).abs() < 0.0001);
+    assert_eq!(None, vanishing_point([0.0, 1.0, 0.0], &view_projection));
+    assert!(vanishing_point([1.0, 0.0, 0.0], &view_projection).is_some());
+}
+
+#[test]
+fn three_point_perspective() {
+    let builder = ProjectionMatrixBuilder::new().set_near(0.1);
+    let (eye, target) = ([0.0, 1.5, 0.0], [10.0, 5.0, 10.0]);
+    let (view, projection) = builder.architectural_view(eye, target, [0.0, 1.0, 0.0], VanishingPoints::Three);
+    assert_eq!((math::look_at(eye, target, [0.0, 1.0, 0.0]), builder.build().matrix()), (view, projection));
+    let (x, y) = vanishing_point([0.0, 1.0, 0.0], &math::multiply(&view, &projection)).unwrap();
+    assert!(x.abs() < 0.0001 && y > 1.0);
+}
+
+#[test]
+#[should_panic]
+fn two_point_looking_up() {
+    ProjectionMatrixBuilder::new().architectural_view([0.0; 3], [0.0, 5.0, 0.0], [0.0, 1.0, 0.0], VanishingPoints::Two);
+}
@@ -0,0 +1,66 @@
+//! Model matrices of billboards, sprites always facing the camera
+//!
+//! The billboards are modeled in the x-y plane, with the x-axis to the right, the y-axis up and the z-axis pointing
+//! away from the camera.
+
+use crate::math::{self, Matrix, Vec3};
+
+/// Returns the model matrix of a billboard placed at `position` and parallel to the screen of the camera with the
+/// given view matrix, so it fully faces the camera from any angle, like particles
+pub fn spherical(view: &Matrix, position: Vec3) -> Matrix {
+    let axis = |j: usize| [view[0][j], view[1][j], view[2][j]];
+    model_matrix(axis(0), axis(1), axis(2), position)
+}
+
+/// Returns the model matrix of a billboard placed at `position` that only rotates around the given axis to face the
+/// camera with the given view matrix, like trees or lamps
+///
+/// # Panic
+/// If the view matrix can't be inverted
+pub fn cylindrical(view: &Matrix, position: Vec3, axis: Vec3) -> Matrix {
+    let camera = math::inverse(view).expect("The view matrix must be invertible");
+    let eye = [camera[3][0], camera[3][1], camera[3][2]];
+    let up = math::normalize(axis);
+    let mut right = math::cross(up, math::sub(position, eye));
+    if math::length(right) < f32::EPSILON {
+        // the camera is on the axis, the billboard faces the camera forward direction instead
+        right = math::cross(up, [view[0][2], view[1][2], view[2][2]]);
+    }
+    let right = math::normalize(right);
+    model_matrix(right, up, math::cross(right, up), position)
+}
+
+/// Returns the model matrix with the given axes and translation
+fn model_matrix(right: Vec3, up: Vec3, forward: Vec3, position: Vec3) -> Matrix {
+    [
+        [right[0], right[1], right[2], 0.0],
+        [up[0], up[1], up[2], 0.0],
+        [forward[0], forward[1], forward[2], 0.0],
+        [position[0], position[1], position[2], 1.0],
+    ]
+}
+
+#[test]
+fn spherical_billboard() {
+    let view = math::look_at([3.0, 4.0, -5.0], [0.0; 3], [0.0, 1.0, 0.0]);
+    let model = spherical(&view, [1.0, 2.0, 3.0]);
+    // in view space the billboard axes are the view axes
+    let model_view = math::multiply(&model, &view);
+    let identity = math::identity();
+    assert!((0..3).all(|i| (0..3).all(|j| (identity[i][j] - model_view[i][j]).abs() < 0.0001)));
+    assert_eq!([1.0, 2.0, 3.0, 1.0], model[3]);
+}
+
+#[test]
+fn cylindrical_billboard() {
+    let view = math::look_at([0.0, 10.0, -10.0], [0.0; 3], [0.0, 1.0, 0.0]);
+    let model = cylindrical(&view, [0.0; 3], [0.0, 1.0, 0.0]);
+    assert_eq!([1.0, 0.0, 0.0, 0.0], model[0]);
+    assert_eq!([0.0, 1.0, 0.0, 0.0], model[1]);
+    assert_eq!([0.0, 0.0, 1.0, 0.0], model[2]);
+
+    // looking from the right side
+    let view = math::look_at([10.0, 0.0, 0.0], [0.0; 3], [0.0, 1.0, 0.0]);
+    let model = cylindrical(&view, [0.0; 3], [0.0, 1.0, 0.0]);
+    assert!((0..3).all(|i| (model[2][i] - [-1.0, 0.0, 0.0][i]).abs() < 0.0001));
+}
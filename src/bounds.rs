@@ -0,0 +1,88 @@
+//! Bounding volumes of the scene objects
+
+use crate::math::{self, Vec3};
+
+/// Pairs of corners of a box joined by an edge, see [`Aabb::corners`]
+pub(crate) const BOX_EDGES: [(usize, usize); 12] = [
+    (0, 1), (2, 3), (4, 5), (6, 7),
+    (0, 2), (1, 3), (4, 6), (5, 7),
+    (0, 4), (1, 5), (2, 6), (3, 7),
+];
+
+/// Axis-aligned bounding box
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    /// Corner with the lowest coordinates
+    pub min: Vec3,
+    /// Corner with the highest coordinates
+    pub max: Vec3,
+}
+
+impl Aabb {
+    /// Returns the box between both corners
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    /// Returns the smallest box containing all the points, or `None` if there are no points
+    pub fn from_points(points: &[Vec3]) -> Option<Self> {
+        let (first, rest) = points.split_first()?;
+        Some(rest.iter().fold(Self::new(*first, *first), |aabb, point| Self {
+            min: [aabb.min[0].min(point[0]), aabb.min[1].min(point[1]), aabb.min[2].min(point[2])],
+            max: [aabb.max[0].max(point[0]), aabb.max[1].max(point[1]), aabb.max[2].max(point[2])],
+        }))
+    }
+
+    /// Returns the smallest box containing both boxes
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min: [0, 1, 2].map(|i| self.min[i].min(other.min[i])),
+            max: [0, 1, 2].map(|i| self.max[i].max(other.max[i])),
+        }
+    }
+
+    /// Returns the center of the box
+    pub fn center(&self) -> Vec3 {
+        math::lerp(self.min, self.max, 0.5)
+    }
+
+    /// Returns the distance from the center to the faces of the box along each axis
+    pub fn half_extents(&self) -> Vec3 {
+        math::scale(math::sub(self.max, self.min), 0.5)
+    }
+
+    /// Returns the eight corners of the box, the bits of the index tell if the corner takes the maximum of the x, y
+    /// and z axes, from the lowest to the highest bit
+    pub fn corners(&self) -> [Vec3; 8] {
+        let mut corners = [[0.0; 3]; 8];
+        for (i, corner) in corners.iter_mut().enumerate() {
+            for (axis, coordinate) in corner.iter_mut().enumerate() {
+                *coordinate = if i >> axis & 1 == 1 { self.max[axis] } else { self.min[axis] };
+            }
+        }
+        corners
+    }
+}
+
+#[test]
+fn aabb_from_points() {
+    let aabb = Aabb::from_points(&[[1.0, -2.0, 0.0], [-1.0, 4.0, 2.0], [0.0, 0.0, 3.0]]).unwrap();
+    assert_eq!(Aabb::new([-1.0, -2.0, 0.0], [1.0, 4.0, 3.0]), aabb);
+    assert_eq!([0.0, 1.0, 1.5], aabb.center());
+    assert_eq!([1.0, 3.0, 1.5], aabb.half_extents());
+    assert_eq!(None, Aabb::from_points(&[]));
+    let other = Aabb::new([0.0, 0.0, -1.0], [2.0, 1.0, 1.0]);
+    assert_eq!(Aabb::new([-1.0, -2.0, -1.0], [2.0, 4.0, 3.0]), aabb.union(&other));
+}
+
+#[test]
+fn aabb_corners() {
+    let corners = Aabb::new([0.0; 3], [1.0, 2.0, 3.0]).corners();
+    assert_eq!([0.0, 0.0, 0.0], corners[0]);
+    assert_eq!([1.0, 2.0, 0.0], corners[3]);
+    assert_eq!([0.0, 2.0, 3.0], corners[6]);
+    for (a, b) in BOX_EDGES {
+        let differences = (0..3).filter(|&axis| corners[a][axis] != corners[b][axis]).count();
+        assert_eq!(1, differences);
+    }
+}
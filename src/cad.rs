@@ -0,0 +1,105 @@
+//! Standard views of the engineering drawings, for CAD-style viewers
+
+use crate::math::{self, Matrix, Vec3};
+use crate::{Orthographic, Quaternion};
+
+/// Pitch of the isometric view, the angle of the diagonal of a cube over the horizontal plane
+const ISOMETRIC_PITCH: f32 = 35.264_39;
+
+/// Standard view of a model, looking at it from the side each variant names
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ViewPreset {
+    /// Looking along the positive z-axis
+    Front,
+    /// Looking along the negative z-axis
+    Back,
+    /// Looking along the positive x-axis
+    Left,
+    /// Looking along the negative x-axis
+    Right,
+    /// Looking down the negative y-axis, with the positive z-axis up in the screen
+    Top,
+    /// Looking up the positive y-axis, with the negative z-axis up in the screen
+    Bottom,
+    /// Looking down from the front right, with the three axes equally foreshortened
+    Isometric,
+    /// Looking with the given angles in degrees, the yaw turning from the front to the left and the pitch looking
+    /// down. Dimetric views are the ones with a yaw of `45.0`.
+    Trimetric {
+        /// Rotation around the y-axis
+        yaw: f32,
+        /// Rotation over the horizontal plane
+        pitch: f32,
+    },
+}
+
+impl ViewPreset {
+    /// Returns the rotation of the camera of the view, from looking along the z-axis
+    pub fn orientation(&self) -> Quaternion {
+        let (yaw, pitch) = match *self {
+            Self::Front => (0.0, 0.0),
+            Self::Back => (180.0, 0.0),
+            Self::Left => (90.0, 0.0),
+            Self::Right => (-90.0, 0.0),
+            Self::Top => (0.0, 90.0),
+            Self::Bottom => (0.0, -90.0),
+            Self::Isometric => (-45.0, ISOMETRIC_PITCH),
+            Self::Trimetric { yaw, pitch } => (yaw, pitch),
+        };
+        Quaternion::from_euler(yaw, pitch, 0.0)
+    }
+
+    /// Returns the orthographic projection and the view matrix showing the sphere with the given center and radius
+    /// from the preset side, in a screen with the given aspect ratio
+    pub fn view(&self, center: Vec3, radius: f32, aspect_ratio: f32) -> (Orthographic, Matrix) {
+        let orientation = self.orientation();
+        let axis = |axis: Vec3| orientation.rotate(axis);
+        let forward = axis([0.0, 0.0, 1.0]);
+        let eye = math::sub(center, math::scale(forward, radius));
+        let view = math::view_matrix(eye, axis([1.0, 0.0, 0.0]), axis([0.0, 1.0, 0.0]), forward);
+
+        let (width, height) = if aspect_ratio >= 1.0 { (aspect_ratio, 1.0) } else { (1.0, 1.0 / aspect_ratio) };
+        let projection = Orthographic::from_size(width * radius * 2.0, height * radius * 2.0, 0.0, radius * 2.0);
+        (projection, view)
+    }
+}
+
+#[cfg(test)]
+fn assert_vec_eq(expected: Vec3, actual: Vec3) {
+    assert!((0..3).all(|i| (expected[i] - actual[i]).abs() < 0.0001), "{expected:?} != {actual:?}");
+}
+
+#[test]
+fn axis_presets() {
+    let forward = |preset: ViewPreset| preset.orientation().rotate([0.0, 0.0, 1.0]);
+    assert_vec_eq([0.0, 0.0, 1.0], forward(ViewPreset::Front));
+    assert_vec_eq([0.0, 0.0, -1.0], forward(ViewPreset::Back));
+    assert_vec_eq([1.0, 0.0, 0.0], forward(ViewPreset::Left));
+    assert_vec_eq([-1.0, 0.0, 0.0], forward(ViewPreset::Right));
+    assert_vec_eq([0.0, -1.0, 0.0], forward(ViewPreset::Top));
+    assert_vec_eq([0.0, 1.0, 0.0], forward(ViewPreset::Bottom));
+    assert_vec_eq([0.0, 0.0, 1.0], ViewPreset::Top.orientation().rotate([0.0, 1.0, 0.0]));
+}
+
+#[test]
+fn isometric_preset() {
+    // the three axes are projected with the same length
+    let (_, view) = ViewPreset::Isometric.view([0.0; 3], 1.0, 1.0);
+    let lengths = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]].map(|axis| {
+        let [x, y, _, _] = math::transform([axis[0], axis[1], axis[2], 0.0], &view);
+        (x * x + y * y).sqrt()
+    });
+    assert!((lengths[0] - lengths[1]).abs() < 0.0001 && (lengths[1] - lengths[2]).abs() < 0.0001);
+    let forward = ViewPreset::Isometric.orientation().rotate([0.0, 0.0, 1.0]);
+    assert_vec_eq(math::normalize([-1.0, -1.0, 1.0]), forward);
+}
+
+#[test]
+fn preset_framing() {
+    use crate::Projection;
+    let (projection, view) = ViewPreset::Trimetric { yaw: 30.0, pitch: 20.0 }.view([5.0, 0.0, 5.0], 2.0, 2.0);
+    assert_eq!(Orthographic::from_size(8.0, 4.0, 0.0, 4.0), projection);
+    let frustum = projection.frustum(&view);
+    assert!(frustum.intersects_sphere([5.0, 0.0, 5.0], 2.0));
+    assert!(frustum.contains_point([5.0, 1.99, 5.0]) && frustum.contains_point([6.99, 0.0, 5.0]));
+}
@@ -0,0 +1,489 @@
+//! Cameras and helpers to place them around the scene
+
+use std::cell::Cell;
+
+use crate::math::{self, Matrix, Vec3};
+use crate::{Aabb, ProjectionMatrix, ProjectionMatrixBuilder, Quaternion};
+
+/// Pitch limit of the cameras, avoiding to look straight up or down where the view flips
+const MAX_PITCH: f32 = 89.0;
+/// Default degrees rotated by the orbit camera per pixel dragged
+const DEFAULT_ROTATION_SPEED: f32 = 0.25;
+/// Default fraction of the distance zoomed by the orbit camera per scroll step
+const DEFAULT_ZOOM_SPEED: f32 = 0.1;
+/// Closest distance of the orbit camera to its target
+const MIN_ORBIT_DISTANCE: f32 = 0.001;
+
+/// Projection that keeps its matrix between frames, only building it again when its parameters change, so it can be
+/// asked for the matrix every frame without redoing the calculations
+#[derive(Clone, Debug)]
+pub struct Camera {
+    projection: ProjectionMatrixBuilder,
+    matrix: Cell<Option<ProjectionMatrix>>,
+}
+
+impl Camera {
+    /// Returns a camera with the given projection
+    pub fn new(projection: ProjectionMatrixBuilder) -> Self {
+        Self { projection, matrix: Cell::new(None) }
+    }
+
+    /// Returns the projection matrix, building it only if the parameters changed since the last call
+    pub fn matrix(&self) -> ProjectionMatrix {
+        self.matrix.get().unwrap_or_else(|| {
+            let matrix = self.projection.build();
+            self.matrix.set(Some(matrix));
+            matrix
+        })
+    }
+
+    /// Returns the parameters of the projection
+    pub fn projection(&self) -> &ProjectionMatrixBuilder {
+        &self.projection
+    }
+
+    /// Returns if the matrix must be built again in the next call to [`Camera::matrix`]
+    pub fn is_dirty(&self) -> bool {
+        self.matrix.get().is_none()
+    }
+
+    /// Replaces the projection
+    pub fn set_projection(&mut self, projection: ProjectionMatrixBuilder) {
+        self.update(|_| projection);
+    }
+
+    /// Changes the projection with the builder setters, like `camera.update(|p| p.set_fov(60.0))`
+    pub fn update(&mut self, update: impl FnOnce(ProjectionMatrixBuilder) -> ProjectionMatrixBuilder) {
+        let projection = update(self.projection);
+        if projection != self.projection {
+            self.projection = projection;
+            self.matrix.set(None);
+        }
+    }
+
+    /// Sets the near clip position in the z axis
+    pub fn set_near(&mut self, near: f32) {
+        self.update(|projection| projection.set_near(near));
+    }
+
+    /// Sets the far clip position in the z axis
+    pub fn set_far(&mut self, far: f32) {
+        self.update(|projection| projection.set_far(far));
+    }
+
+    /// Sets the field of view in grades
+    ///
+    /// # Panic
+    /// If the entered fov is not within the supported [0.1, 179] range
+    pub fn set_fov(&mut self, fov: f32) {
+        self.update(|projection| projection.set_fov(fov));
+    }
+
+    /// Sets the size of the screen. A size without area, like the one of the minimized windows, is ignored keeping
+    /// the current projection.
+    pub fn set_size(&mut self, width: usize, height: usize) {
+        self.update(|projection| {
+            projection.try_set_width(width).and_then(|resized| resized.try_set_height(height)).unwrap_or(projection)
+        });
+    }
+
+    /// Handles the resize of the window, with its new size in physical pixels, returning the updated matrix. The
+    /// matrix is kept while the window is minimized and reports a size without area.
+    pub fn on_resize(&mut self, width: usize, height: usize) -> ProjectionMatrix {
+        self.set_size(width, height);
+        self.matrix()
+    }
+
+    /// Handles the resize of a window reporting the size of its drawable area apart from its logical size, like the
+    /// SDL windows, returning the updated matrix. See [`ProjectionMatrixBuilder::set_drawable_size`].
+    ///
+    /// # Panic
+    /// If any of the sizes is zero
+    pub fn on_drawable_resize(&mut self, window_width: usize, window_height: usize, drawable_width: usize,
+                              drawable_height: usize) -> ProjectionMatrix {
+        self.update(|projection| {
+            projection.set_drawable_size(window_width, window_height, drawable_width, drawable_height)
+        });
+        self.matrix()
+    }
+
+    /// Handles the change of the scale factor of the window, like moving it to a screen with another DPI, returning
+    /// the updated matrix. The logical size of the window is kept.
+    ///
+    /// # Panic
+    /// If the entered scale factor is not positive
+    pub fn on_scale_factor_changed(&mut self, scale_factor: f32) -> ProjectionMatrix {
+        self.update(|projection| projection.set_scale_factor(scale_factor));
+        self.matrix()
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new(ProjectionMatrixBuilder::new())
+    }
+}
+
+/// The cameras are compared by their projections, whether their matrices are already built or not
+impl PartialEq for Camera {
+    fn eq(&self, other: &Self) -> bool {
+        self.projection == other.projection
+    }
+}
+
+/// Camera orbiting around a target, the usual camera of the model viewers. Dragging the mouse rotates it around the
+/// target and scrolling gets it closer or further.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OrbitCamera {
+    /// Point the camera looks at
+    pub target: Vec3,
+    /// Rotation around the vertical axis, in degrees. With zero the camera looks along the z-axis.
+    pub yaw: f32,
+    /// Elevation over the target, in degrees
+    pub pitch: f32,
+    /// Distance to the target
+    pub distance: f32,
+    /// Degrees rotated per pixel dragged
+    pub rotation_speed: f32,
+    /// Fraction of the distance zoomed per scroll step
+    pub zoom_speed: f32,
+}
+
+impl OrbitCamera {
+    /// Returns a camera looking at the target from the given distance
+    pub fn new(target: Vec3, distance: f32) -> Self {
+        Self {
+            target,
+            yaw: 0.0,
+            pitch: 0.0,
+            distance,
+            rotation_speed: DEFAULT_ROTATION_SPEED,
+            zoom_speed: DEFAULT_ZOOM_SPEED,
+        }
+    }
+
+    /// Rotates the camera around the target with the mouse drag, in pixels. Dragging to the right moves the camera to
+    /// the left, making the scene turn with the mouse, and dragging down moves the camera up.
+    pub fn rotate(&mut self, dx: f32, dy: f32) {
+        self.yaw = (self.yaw + dx * self.rotation_speed) % 360.0;
+        self.pitch = (self.pitch + dy * self.rotation_speed).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// Moves the camera towards the target with the mouse scroll, the positive steps get closer
+    pub fn zoom(&mut self, steps: f32) {
+        self.distance = (self.distance * (1.0 - self.zoom_speed).powf(steps)).max(MIN_ORBIT_DISTANCE);
+    }
+
+    /// Returns the position of the camera
+    pub fn eye(&self) -> Vec3 {
+        let forward = Quaternion::from_euler(self.yaw, self.pitch, 0.0).rotate([0.0, 0.0, 1.0]);
+        math::sub(self.target, math::scale(forward, self.distance))
+    }
+
+    /// Returns the view matrix of the camera
+    pub fn view(&self) -> Matrix {
+        math::look_at(self.eye(), self.target, [0.0, 1.0, 0.0])
+    }
+
+    /// Returns the combined view and projection matrix of the camera with the given projection
+    pub fn view_projection(&self, projection: &ProjectionMatrixBuilder) -> Matrix {
+        math::multiply(&self.view(), &projection.build())
+    }
+}
+
+/// First person camera, turning with the yaw and pitch angles and moving over the horizontal plane
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FpsCamera {
+    /// Position of the camera
+    pub position: Vec3,
+    /// Rotation around the vertical axis, in degrees. With zero the camera looks along the z-axis and the positive
+    /// angles turn it to the right.
+    pub yaw: f32,
+    /// Rotation over the horizon, in degrees. The positive angles look up.
+    pub pitch: f32,
+}
+
+impl FpsCamera {
+    /// Returns a camera at the given position looking along the z-axis
+    pub fn new(position: Vec3) -> Self {
+        Self { position, yaw: 0.0, pitch: 0.0 }
+    }
+
+    /// Turns the camera by the given degrees, the pitch is limited to avoid looking straight up or down
+    pub fn rotate(&mut self, yaw: f32, pitch: f32) {
+        self.yaw = (self.yaw + yaw) % 360.0;
+        self.pitch = (self.pitch + pitch).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// Returns the direction the camera looks at
+    pub fn forward(&self) -> Vec3 {
+        self.orientation().rotate([0.0, 0.0, 1.0])
+    }
+
+    /// Returns the direction to the right of the camera, always horizontal
+    pub fn right(&self) -> Vec3 {
+        self.orientation().rotate([1.0, 0.0, 0.0])
+    }
+
+    /// Moves the camera forward, or backwards with negative distances, over the horizontal plane
+    pub fn move_forward(&mut self, distance: f32) {
+        let forward = Quaternion::from_axis_angle([0.0, 1.0, 0.0], self.yaw).rotate([0.0, 0.0, 1.0]);
+        self.position = math::add(self.position, math::scale(forward, distance));
+    }
+
+    /// Moves the camera to the right, or to the left with negative distances, over the horizontal plane
+    pub fn strafe(&mut self, distance: f32) {
+        self.position = math::add(self.position, math::scale(self.right(), distance));
+    }
+
+    /// Moves the camera up, or down with negative distances
+    pub fn fly(&mut self, distance: f32) {
+        self.position[1] += distance;
+    }
+
+    /// Returns the view matrix of the camera
+    pub fn view(&self) -> Matrix {
+        let orientation = self.orientation();
+        let axis = |axis: Vec3| orientation.rotate(axis);
+        math::view_matrix(self.position, axis([1.0, 0.0, 0.0]), axis([0.0, 1.0, 0.0]), axis([0.0, 0.0, 1.0]))
+    }
+
+    /// Returns the combined view and projection matrix of the camera with the given projection
+    pub fn view_projection(&self, projection: &ProjectionMatrixBuilder) -> Matrix {
+        projection.build_view_projection(self.position, &self.orientation())
+    }
+
+    /// Returns the rotation of the camera, from looking along the z-axis
+    fn orientation(&self) -> Quaternion {
+        // the positive pitch of the quaternions looks down
+        Quaternion::from_euler(self.yaw, -self.pitch, 0.0)
+    }
+}
+
+impl ProjectionMatrixBuilder {
+    /// Returns the distance from the center of the box where the camera must be placed to fit the whole box in the
+    /// view. The margin widens the box by the given fraction of its size.
+    pub fn frame_distance(&self, aabb: &Aabb, margin: f32) -> f32 {
+        let radius = framed_radius(aabb, margin);
+        radius / self.narrowest_half_fov().sin()
+    }
+
+    /// Returns the view matrix of a camera looking in the given direction that fits the whole box in the view, the
+    /// behavior of the "frame selection" key of the scene viewers. Check [`ProjectionMatrixBuilder::frame_distance`].
+    pub fn frame_view(&self, aabb: &Aabb, direction: Vec3, up: Vec3, margin: f32) -> Matrix {
+        let center = aabb.center();
+        let distance = self.frame_distance(aabb, margin);
+        let eye = math::sub(center, math::scale(math::normalize(direction), distance));
+        math::look_at(eye, center, up)
+    }
+
+    /// Returns a copy of the builder with the field of view needed to fit the whole box in the view of a camera
+    /// placed at `eye` and looking at the center of the box. The margin widens the box by the given fraction of its
+    /// size. The field of view is clamped to the supported [0.1, 179] range, so boxes too close to the camera don't
+    /// fully fit.
+    ///
+    /// # Panic
+    /// If the camera is inside the widened box
+    pub fn frame_fov(&self, aabb: &Aabb, eye: Vec3, margin: f32) -> Self {
+        let radius = framed_radius(aabb, margin);
+        let distance = math::length(math::sub(aabb.center(), eye));
+        if distance <= radius {
+            panic!("The camera must be outside the box to frame it");
+        }
+        let half_angle = (radius / distance).asin();
+        // the field of view defines the vertical extent, but the horizontal one is narrower on wide screens
+        self.with_fov_scale(1.0 / (half_angle.tan() * self.aspect_ratio().max(1.0)))
+    }
+
+    /// Builds the combined view and projection matrix of a camera placed in `eye` with the given rotation, from
+    /// looking along the z-axis, in a single pass. It's the product of the view matrix and the projection matrix
+    /// without the intermediate matrices, skipping the cells the projection leaves empty.
+    ///
+    /// # Panic
+    /// Like [`ProjectionMatrixBuilder::build`]
+    pub fn build_view_projection(&self, eye: Vec3, orientation: &Quaternion) -> Matrix {
+        let projection = self.build();
+        let axes = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]].map(|axis| orientation.rotate(axis));
+        // the rows of the view matrix are the components of its axes, with the translation in the last one
+        let view_row = |i: usize| {
+            if i < 3 { axes.map(|axis| axis[i]) } else { axes.map(|axis| -math::dot(axis, eye)) }
+        };
+        let mut matrix = [[0.0; 4]; 4];
+        for (i, row) in matrix.iter_mut().enumerate() {
+            let [right, up, forward] = view_row(i);
+            let w = if i < 3 { 0.0 } else { 1.0 };
+            *row = [
+                right * projection[0][0] + forward * projection[2][0],
+                up * projection[1][1] + forward * projection[2][1],
+                forward * projection[2][2] + w * projection[3][2],
+                forward * projection[2][3],
+            ];
+        }
+        matrix
+    }
+
+    /// Returns the half angle of the narrowest axis of the view
+    fn narrowest_half_fov(&self) -> f32 {
+        let fov_scale = self.fov_scale();
+        (1.0 / (fov_scale * self.aspect_ratio()).max(fov_scale)).atan()
+    }
+}
+
+/// Returns the radius of the sphere bounding the box widened by the margin
+fn framed_radius(aabb: &Aabb, margin: f32) -> f32 {
+    math::length(aabb.half_extents()) * (1.0 + margin)
+}
+
+#[test]
+fn frame_distance() {
+    let builder = ProjectionMatrixBuilder::new().set_width(720);
+    let aabb = Aabb::new([-1.0; 3], [1.0; 3]);
+    let radius = 3.0f32.sqrt();
+    assert!((radius * 2.0f32.sqrt() - builder.frame_distance(&aabb, 0.0)).abs() < 0.0001);
+    assert!((1.1 * radius * 2.0f32.sqrt() - builder.frame_distance(&aabb, 0.1)).abs() < 0.0001);
+}
+
+#[test]
+fn frame_view_fits_box() {
+    let builder = ProjectionMatrixBuilder::new().set_near(0.1);
+    let aabb = Aabb::new([4.0, 0.0, 0.0], [6.0, 1.0, 3.0]);
+    let view = builder.frame_view(&aabb, [1.0, -1.0, 1.0], [0.0, 1.0, 0.0], 0.0);
+    let frustum = crate::Frustum::new(&view, &builder);
+    assert!(aabb.corners().iter().all(|&corner| frustum.contains_point(corner)));
+}
+
+#[test]
+fn frame_fov_fits_box() {
+    let aabb = Aabb::new([-1.0; 3], [1.0; 3]);
+    let eye = [0.0, 0.0, -10.0];
+    let half_angle = (3.0f32.sqrt() / 10.0).asin();
+    let builder = ProjectionMatrixBuilder::new().set_width(720).frame_fov(&aabb, eye, 0.0);
+    assert!((half_angle.to_degrees() * 2.0 - builder.fov).abs() < 0.001);
+
+    let builder = ProjectionMatrixBuilder::new().set_near(0.1).frame_fov(&aabb, eye, 0.0);
+    let frustum = crate::Frustum::new(&math::look_at(eye, [0.0; 3], [0.0, 1.0, 0.0]), &builder);
+    assert!(aabb.corners().iter().all(|&corner| frustum.contains_point(corner)));
+
+    // the boxes right in front of the camera and far away ones stay within the supported fov
+    let close = ProjectionMatrixBuilder::new().set_width(720).frame_fov(&aabb, [0.0, 0.0, -1.7321], 0.0);
+    assert_eq!(crate::MAX_SUPPORTED_FOV, close.fov);
+    let far = ProjectionMatrixBuilder::new().set_width(720).frame_fov(&aabb, [0.0, 0.0, -1_000_000.0], 0.0);
+    assert_eq!(crate::MIN_SUPPORTED_FOV, far.fov);
+}
+
+#[test]
+fn orbit_camera() {
+    let mut camera = OrbitCamera::new([1.0, 0.0, 0.0], 10.0);
+    assert_eq!([1.0, 0.0, -10.0], camera.eye());
+
+    camera.rotate(0.0, 180.0);
+    assert_eq!(45.0, camera.pitch);
+    let eye = camera.eye();
+    assert!((eye[1] - 10.0 * 45.0f32.to_radians().sin()).abs() < 0.0001);
+    camera.rotate(0.0, 1000.0);
+    assert_eq!(MAX_PITCH, camera.pitch);
+
+    camera.zoom(1.0);
+    assert!((9.0 - camera.distance).abs() < 0.0001);
+    camera.zoom(-1.0);
+    assert!((10.0 - camera.distance).abs() < 0.0001);
+
+    // the target is always at the center of the view
+    camera.rotate(123.0, -456.0);
+    let view_projection = camera.view_projection(&ProjectionMatrixBuilder::new().set_near(1.0));
+    let [x, y, _, w] = math::transform_point(camera.target, &view_projection);
+    assert!((x / w).abs() < 0.0001 && (y / w).abs() < 0.0001 && (w - 10.0).abs() < 0.0001);
+}
+
+#[test]
+fn fps_camera_movement() {
+    let mut camera = FpsCamera::new([0.0, 2.0, 0.0]);
+    camera.rotate(90.0, 30.0);
+    let forward = camera.forward();
+    assert!(forward[0] > 0.0 && forward[1] > 0.0 && forward[2].abs() < 0.0001);
+    assert!(camera.right()[1].abs() < 0.0001);
+
+    // looking up doesn't take the camera off the ground
+    camera.move_forward(2.0);
+    camera.strafe(1.0);
+    camera.fly(-1.0);
+    let expected = [2.0, 1.0, -1.0];
+    assert!((0..3).all(|i| (expected[i] - camera.position[i]).abs() < 0.0001), "{:?}", camera.position);
+
+    camera.rotate(0.0, 100.0);
+    assert_eq!(MAX_PITCH, camera.pitch);
+}
+
+#[test]
+fn fps_camera_view() {
+    let mut camera = FpsCamera::new([1.0, 2.0, 3.0]);
+    camera.rotate(-40.0, 20.0);
+    let ahead = math::add(camera.position, math::scale(camera.forward(), 5.0));
+    let [x, y, z, _] = math::transform_point(ahead, &camera.view());
+    assert!(x.abs() < 0.0001 && y.abs() < 0.0001 && (z - 5.0).abs() < 0.0001);
+    let [x, _, w, _] = math::transform_point(math::add(camera.position, camera.right()), &camera.view());
+    assert!((x - 1.0).abs() < 0.0001 && w.abs() < 0.0001);
+}
+
+#[test]
+fn camera_rebuilds_lazily() {
+    let mut camera = Camera::default();
+    assert!(camera.is_dirty());
+    assert_eq!(ProjectionMatrixBuilder::new().build(), camera.matrix());
+    assert!(!camera.is_dirty());
+
+    // setting the same value keeps the matrix
+    camera.set_fov(90.0);
+    assert!(!camera.is_dirty());
+
+    camera.set_fov(60.0);
+    camera.set_size(800, 600);
+    assert!(camera.is_dirty());
+    let expected = ProjectionMatrixBuilder::new().set_fov(60.0).set_width(800).set_height(600);
+    assert_eq!(expected.build(), camera.matrix());
+    assert_eq!(&expected, camera.projection());
+
+    camera.update(|projection| projection.set_near(1.0).set_far(10.0));
+    assert!(camera.is_dirty());
+    assert_eq!(expected.set_near(1.0).set_far(10.0).build(), camera.matrix());
+    assert_eq!(Camera::new(expected.set_near(1.0).set_far(10.0)), camera);
+}
+
+#[test]
+fn camera_window_events() {
+    let mut camera = Camera::default();
+    let matrix = camera.on_resize(1000, 1000);
+    assert_eq!(ProjectionMatrixBuilder::new().set_width(1000).set_height(1000).build(), matrix);
+
+    camera.on_scale_factor_changed(2.0);
+    assert_eq!((2000, 2000), camera.projection().physical_size());
+    assert_eq!((1000.0, 1000.0), camera.projection().logical_size());
+    assert!(!camera.is_dirty());
+
+    let matrix = camera.on_drawable_resize(800, 600, 1200, 900);
+    assert_eq!((1200, 900), camera.projection().physical_size());
+    assert_eq!((800.0, 600.0), camera.projection().logical_size());
+    assert_eq!(ProjectionMatrixBuilder::new().set_width(800).set_height(600).build(), matrix);
+
+    // minimizing the window
+    assert_eq!(matrix, camera.on_resize(0, 0));
+    assert_eq!(matrix, camera.on_resize(0, 600));
+    assert_eq!((1200, 900), camera.projection().physical_size());
+    assert!(!camera.is_dirty());
+}
+
+#[test]
+fn fused_view_projection() {
+    let builder = ProjectionMatrixBuilder::new().set_near(0.5).set_far(300.0).set_overscan(10.0);
+    let orientation = Quaternion::from_euler(35.0, -20.0, 10.0);
+    let eye = [3.0, -2.0, 7.0];
+    let axis = |axis: Vec3| orientation.rotate(axis);
+    let view = math::view_matrix(eye, axis([1.0, 0.0, 0.0]), axis([0.0, 1.0, 0.0]), axis([0.0, 0.0, 1.0]));
+    let expected = math::multiply(&view, &builder.build());
+    let fused = builder.build_view_projection(eye, &orientation);
+    assert!((0..4).all(|i| (0..4).all(|j| (expected[i][j] - fused[i][j]).abs() <= expected[i][j].abs() * 1e-5)));
+
+    let mut camera = FpsCamera::new(eye);
+    camera.rotate(30.0, 15.0);
+    math::assert_matrix_eq(&math::multiply(&camera.view(), &builder.build()), &camera.view_projection(&builder));
+}
@@ -0,0 +1,164 @@
+//! Clipping of the geometry in clip space, the homogeneous coordinates produced by the projection matrices
+//!
+//! The projection matrices of the crate copy the view space depth into `w`, so the near clip is the plane where
+//! `w` is the near position.
+
+use crate::math::{self, Matrix, Vec3, Vec4};
+use crate::screen::ndc_to_screen;
+use crate::{ProjectionMatrixBuilder, Viewport};
+
+/// Point in normalized device coordinates, the result of the perspective division of the clip space coordinates.
+/// The points within the view have `x` and `y` in the `[-1, 1]` range.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NdcPoint {
+    /// Horizontal position, from the left to the right edge
+    pub x: f32,
+    /// Vertical position, from the bottom to the top edge
+    pub y: f32,
+    /// Depth produced by the projection
+    pub z: f32,
+}
+
+impl NdcPoint {
+    /// Returns if the point is within the horizontal and vertical edges of the view
+    pub fn is_on_screen(&self) -> bool {
+        (-1.0..=1.0).contains(&self.x) && (-1.0..=1.0).contains(&self.y)
+    }
+
+    /// Maps the point into pixels of the viewport, with the origin at the top left corner of the window
+    pub fn to_screen(&self, viewport: &Viewport) -> (f32, f32) {
+        ndc_to_screen(self.x, self.y, viewport)
+    }
+}
+
+/// Performs the perspective division of the clip space point, returning `None` if the point is not in front of the
+/// camera, with a `w` not positive
+pub fn clip_to_ndc(clip: Vec4) -> Option<NdcPoint> {
+    let [x, y, z, w] = clip;
+    (w > 0.0).then(|| NdcPoint { x: x / w, y: y / w, z: z / w })
+}
+
+/// Clips the clip space segment keeping the part in front of the near plane, with a `w` of at least `near`. Returns
+/// `None` if the segment is fully behind the plane.
+pub fn clip_segment_near(a: Vec4, b: Vec4, near: f32) -> Option<(Vec4, Vec4)> {
+    match (a[3] >= near, b[3] >= near) {
+        (true, true) => Some((a, b)),
+        (false, false) => None,
+        (true, false) => Some((a, lerp(a, b, (near - a[3]) / (b[3] - a[3])))),
+        (false, true) => Some((lerp(a, b, (near - a[3]) / (b[3] - a[3])), b)),
+    }
+}
+
+/// Clips the clip space polygon against the six planes of the view volume: the left, right, bottom and top edges of
+/// the normalized device coordinates, where `x` and `y` are `-w` or `w`, and the near and far clips. Returns the
+/// vertices of the clipped convex polygon, to be drawn as a fan, or an empty list if it's fully outside the view.
+pub fn clip_polygon(polygon: &[Vec4], near: f32, far: f32) -> Vec<Vec4> {
+    let planes: [&dyn Fn(&Vec4) -> f32; 6] = [
+        &|v| v[3] + v[0],
+        &|v| v[3] - v[0],
+        &|v| v[3] + v[1],
+        &|v| v[3] - v[1],
+        &|v| v[3] - near,
+        &|v| far - v[3],
+    ];
+    planes.iter().fold(polygon.to_vec(), |polygon, plane| clip_by(&polygon, plane))
+}
+
+/// Clips the clip space triangle against the six planes of the view volume, returning the triangles covering the
+/// clipped area. Check [`clip_polygon`].
+pub fn clip_triangle(triangle: &[Vec4; 3], near: f32, far: f32) -> Vec<[Vec4; 3]> {
+    let polygon = clip_polygon(triangle, near, far);
+    (2..polygon.len()).map(|i| [polygon[0], polygon[i - 1], polygon[i]]).collect()
+}
+
+/// Clips the polygon keeping the part where the distance function is not negative, Sutherland–Hodgman algorithm
+pub(crate) fn clip_by(polygon: &[Vec4], distance: impl Fn(&Vec4) -> f32) -> Vec<Vec4> {
+    let mut clipped = Vec::with_capacity(polygon.len() + 1);
+    for (i, current) in polygon.iter().enumerate() {
+        let previous = &polygon[(i + polygon.len() - 1) % polygon.len()];
+        let (current_distance, previous_distance) = (distance(current), distance(previous));
+        if (current_distance >= 0.0) != (previous_distance >= 0.0) {
+            let t = previous_distance / (previous_distance - current_distance);
+            clipped.push(lerp(*previous, *current, t));
+        }
+        if current_distance >= 0.0 {
+            clipped.push(*current);
+        }
+    }
+    clipped
+}
+
+impl ProjectionMatrixBuilder {
+    /// Projects the world space segment seen through the view matrix into the viewport, clipping it by the near plane
+    /// first so segments going behind the camera are drawn correctly. Returns the position in pixels and depth of both
+    /// ends or `None` if the segment is fully behind the near plane.
+    pub fn project_segment(&self, a: Vec3, b: Vec3, view: &Matrix, viewport: &Viewport) -> Option<(Vec3, Vec3)> {
+        let view_projection = math::multiply(view, &self.build());
+        let (a, b) = clip_segment_near(
+            math::transform_point(a, &view_projection),
+            math::transform_point(b, &view_projection),
+            self.near,
+        )?;
+        let to_screen = |[x, y, _, w]: Vec4| {
+            let (x, y) = ndc_to_screen(x / w, y / w, viewport);
+            [x, y, self.delinearize_depth(w)]
+        };
+        Some((to_screen(a), to_screen(b)))
+    }
+}
+
+/// Returns the linear interpolation between both homogeneous vectors
+pub(crate) fn lerp(a: Vec4, b: Vec4, t: f32) -> Vec4 {
+    [0, 1, 2, 3].map(|i| a[i] + (b[i] - a[i]) * t)
+}
+
+#[test]
+fn segment_clipping() {
+    let (a, b) = ([0.0, 0.0, 0.0, 2.0], [4.0, 0.0, 0.0, -2.0]);
+    assert_eq!(Some((a, [1.0, 0.0, 0.0, 1.0])), clip_segment_near(a, b, 1.0));
+    assert_eq!(Some(([1.0, 0.0, 0.0, 1.0], a)), clip_segment_near(b, a, 1.0));
+    assert_eq!(Some((a, a)), clip_segment_near(a, a, 1.0));
+    assert_eq!(None, clip_segment_near(b, b, 1.0));
+}
+
+#[test]
+fn project_segment() {
+    let builder = ProjectionMatrixBuilder::new().set_width(720).set_near(1.0);
+    let viewport = Viewport::new(0, 0, 720, 720);
+    let view = math::identity();
+    let (a, b) = builder.project_segment([0.0, 0.0, 10.0], [1.0, 0.0, -10.0], &view, &viewport).unwrap();
+    assert_eq!([360.0, 360.0], [a[0], a[1]]);
+    // the segment reaches the near plane at x = 0.45
+    assert!((360.0 + 0.45 * 360.0 - b[0]).abs() < 0.01);
+    assert_eq!(None, builder.project_segment([0.0, 0.0, -10.0], [1.0, 0.0, 0.5], &view, &viewport));
+}
+
+#[test]
+fn polygon_clipping() {
+    // a triangle fully inside keeps its vertices
+    let triangle = [[0.0, 0.0, 0.0, 5.0], [1.0, 0.0, 0.0, 5.0], [0.0, 1.0, 0.0, 5.0]];
+    assert_eq!(triangle.to_vec(), clip_polygon(&triangle, 1.0, 10.0));
+    assert_eq!(vec![triangle], clip_triangle(&triangle, 1.0, 10.0));
+
+    // a triangle crossing the right edge becomes a quad
+    let triangle = [[0.0, 0.0, 0.0, 2.0], [4.0, 0.0, 0.0, 2.0], [0.0, 1.0, 0.0, 2.0]];
+    let clipped = clip_polygon(&triangle, 1.0, 10.0);
+    assert_eq!(4, clipped.len());
+    assert!(clipped.iter().all(|vertex| vertex[0] <= vertex[3] + 0.0001));
+    assert_eq!(2, clip_triangle(&triangle, 1.0, 10.0).len());
+
+    // a triangle beyond the far plane is discarded
+    let triangle = [[0.0, 0.0, 0.0, 20.0], [1.0, 0.0, 0.0, 20.0], [0.0, 1.0, 0.0, 30.0]];
+    assert!(clip_polygon(&triangle, 1.0, 10.0).is_empty());
+}
+
+#[test]
+fn ndc_point() {
+    let point = clip_to_ndc([1.0, -2.0, 3.0, 4.0]).unwrap();
+    assert_eq!(NdcPoint { x: 0.25, y: -0.5, z: 0.75 }, point);
+    assert!(point.is_on_screen());
+    assert_eq!((250.0, 150.0), point.to_screen(&Viewport::new(0, 0, 400, 200)));
+    assert!(!clip_to_ndc([8.0, 0.0, 0.0, 4.0]).unwrap().is_on_screen());
+    assert_eq!(None, clip_to_ndc([1.0, 1.0, 1.0, 0.0]));
+    assert_eq!(None, clip_to_ndc([1.0, 1.0, 1.0, -1.0]));
+}
@@ -0,0 +1,281 @@
+//! Frustum culling of many bounds in a single call, storing the visibility of each one in a bitset, either testing
+//! all of them or skipping whole groups with a bounding volume hierarchy
+
+use std::thread;
+
+use crate::math;
+use crate::{Aabb, Frustum};
+
+/// Bits of each word of a [`Visibility`]
+const WORD_BITS: usize = u64::BITS as usize;
+/// Most boxes of the leaves of a [`Bvh`]
+const LEAF_SIZE: usize = 4;
+
+/// Visibility of a list of bounds, a bit per bound packed in 64 bits words
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Visibility {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl Visibility {
+    /// Returns the visibility of the given number of bounds, all of them hidden
+    pub fn new(len: usize) -> Self {
+        Self { words: vec![0; len.div_ceil(WORD_BITS)], len }
+    }
+
+    /// Returns the number of bounds
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns if there are no bounds
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns if the bound with the given index is visible
+    ///
+    /// # Panic
+    /// If the index is out of bounds
+    pub fn get(&self, index: usize) -> bool {
+        assert!(index < self.len, "The index {index} is out of the {} bounds", self.len);
+        self.words[index / WORD_BITS] & 1 << (index % WORD_BITS) != 0
+    }
+
+    /// Marks the bound with the given index as visible
+    ///
+    /// # Panic
+    /// If the index is out of bounds
+    pub fn set(&mut self, index: usize) {
+        assert!(index < self.len, "The index {index} is out of the {} bounds", self.len);
+        self.words[index / WORD_BITS] |= 1 << (index % WORD_BITS);
+    }
+
+    /// Returns the number of visible bounds
+    pub fn count(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Returns the words of the bitset, the bound `i` is the bit `i % 64` of the word `i / 64`
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Returns the indices of the visible bounds in increasing order
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(i, &word)| {
+            let mut bits = word;
+            std::iter::from_fn(move || {
+                if bits == 0 {
+                    return None;
+                }
+                let bit = bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                Some(i * WORD_BITS + bit)
+            })
+        })
+    }
+}
+
+impl Frustum {
+    /// Returns the visibility of the boxes, like [`Frustum::intersects_aabb`]. The boxes are tested in chunks of a
+    /// word, so each word of the result is written once.
+    pub fn cull_aabbs(&self, aabbs: &[Aabb]) -> Visibility {
+        let mut visibility = Visibility::new(aabbs.len());
+        self.cull_words(aabbs, &mut visibility.words);
+        visibility
+    }
+
+    /// Returns the visibility of the boxes like [`Frustum::cull_aabbs`], splitting them among the given number of
+    /// threads. Each thread takes a contiguous range of whole words, so they never share one.
+    pub fn cull_aabbs_parallel(&self, aabbs: &[Aabb], threads: usize) -> Visibility {
+        let mut visibility = Visibility::new(aabbs.len());
+        let words_per_thread = visibility.words.len().div_ceil(threads.max(1)).max(1);
+        thread::scope(|scope| {
+            let chunks = aabbs.chunks(words_per_thread * WORD_BITS).zip(visibility.words.chunks_mut(words_per_thread));
+            for (aabbs, words) in chunks {
+                scope.spawn(move || self.cull_words(aabbs, words));
+            }
+        });
+        visibility
+    }
+
+    /// Writes the visibility of the boxes into the words, a word per chunk of boxes
+    fn cull_words(&self, aabbs: &[Aabb], words: &mut [u64]) {
+        for (chunk, word) in aabbs.chunks(WORD_BITS).zip(words) {
+            *word = chunk
+                .iter()
+                .enumerate()
+                .fold(0, |bits, (i, aabb)| bits | (self.intersects_aabb(aabb) as u64) << i);
+        }
+    }
+}
+
+/// Node of a [`Bvh`], bounding the boxes of a range of its indices
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct BvhNode {
+    bounds: Aabb,
+    start: usize,
+    end: usize,
+    children: Option<[usize; 2]>,
+}
+
+/// Position of a box of a [`Bvh`] relative to a frustum
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Containment {
+    Outside,
+    Crossing,
+    Inside,
+}
+
+/// Bounding volume hierarchy of a list of boxes, to cull them skipping the groups fully inside or outside of the
+/// frustums. It's built once for the static geometry and culled every frame.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Bvh {
+    aabbs: Vec<Aabb>,
+    indices: Vec<usize>,
+    nodes: Vec<BvhNode>,
+}
+
+impl Bvh {
+    /// Returns the hierarchy of the boxes, splitting them by the middle of the longest axis of their centers
+    pub fn new(aabbs: &[Aabb]) -> Self {
+        let mut bvh = Self { aabbs: aabbs.to_vec(), indices: (0..aabbs.len()).collect(), nodes: Vec::new() };
+        if !aabbs.is_empty() {
+            bvh.split(0, aabbs.len());
+        }
+        bvh
+    }
+
+    /// Returns the number of boxes
+    pub fn len(&self) -> usize {
+        self.aabbs.len()
+    }
+
+    /// Returns if there are no boxes
+    pub fn is_empty(&self) -> bool {
+        self.aabbs.is_empty()
+    }
+
+    /// Returns the box bounding all the boxes, or `None` if there are no boxes
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.nodes.first().map(|node| node.bounds)
+    }
+
+    /// Adds the node of the given range of the indices and its descendants, returning its position
+    fn split(&mut self, start: usize, end: usize) -> usize {
+        let indices = &mut self.indices[start..end];
+        let aabbs = &self.aabbs;
+        let bounds = indices.iter().skip(1).fold(aabbs[indices[0]], |bounds, &i| bounds.union(&aabbs[i]));
+        let position = self.nodes.len();
+        self.nodes.push(BvhNode { bounds, start, end, children: None });
+        if end - start <= LEAF_SIZE {
+            return position;
+        }
+        let centers = Aabb::from_points(&indices.iter().map(|&i| aabbs[i].center()).collect::<Vec<_>>()).unwrap();
+        let extents = math::sub(centers.max, centers.min);
+        let axis = (0..3).fold(0, |longest, axis| if extents[axis] > extents[longest] { axis } else { longest });
+        let middle = indices.len() / 2;
+        indices.select_nth_unstable_by(middle, |&a, &b| aabbs[a].center()[axis].total_cmp(&aabbs[b].center()[axis]));
+        let left = self.split(start, start + middle);
+        let right = self.split(start + middle, end);
+        self.nodes[position].children = Some([left, right]);
+        position
+    }
+}
+
+impl Frustum {
+    /// Returns the visibility of the boxes of the hierarchy, the same one as [`Frustum::cull_aabbs`] of its boxes.
+    /// The groups fully outside the frustum are skipped and the ones fully inside are visible without testing their
+    /// boxes.
+    pub fn cull_bvh(&self, bvh: &Bvh) -> Visibility {
+        let mut visibility = Visibility::new(bvh.len());
+        let mut pending = if bvh.is_empty() { vec![] } else { vec![0] };
+        while let Some(position) = pending.pop() {
+            let node = &bvh.nodes[position];
+            let indices = &bvh.indices[node.start..node.end];
+            match (self.containment(&node.bounds), node.children) {
+                (Containment::Outside, _) => {}
+                (Containment::Inside, _) => indices.iter().for_each(|&i| visibility.set(i)),
+                (Containment::Crossing, Some(children)) => pending.extend(children),
+                (Containment::Crossing, None) => {
+                    for &i in indices.iter().filter(|&&i| self.intersects_aabb(&bvh.aabbs[i])) {
+                        visibility.set(i);
+                    }
+                }
+            }
+        }
+        visibility
+    }
+
+    /// Returns the position of the box relative to the frustum, conservative like [`Frustum::intersects_aabb`]
+    fn containment(&self, aabb: &Aabb) -> Containment {
+        let mut containment = Containment::Inside;
+        for plane in &self.planes {
+            let positive = [0, 1, 2].map(|i| if plane.normal[i] >= 0.0 { aabb.max[i] } else { aabb.min[i] });
+            if plane.signed_distance(positive) < 0.0 {
+                return Containment::Outside;
+            }
+            let negative = [0, 1, 2].map(|i| if plane.normal[i] >= 0.0 { aabb.min[i] } else { aabb.max[i] });
+            if plane.signed_distance(negative) < 0.0 {
+                containment = Containment::Crossing;
+            }
+        }
+        containment
+    }
+}
+
+#[cfg(test)]
+fn row_of_boxes(count: usize) -> Vec<Aabb> {
+    // a row along the x axis, only the first ones in front of the camera
+    (0..count).map(|i| Aabb::new([i as f32 * 2.0 - 0.5, -0.5, 9.5], [i as f32 * 2.0 + 0.5, 0.5, 10.5])).collect()
+}
+
+#[test]
+fn visibility_bitset() {
+    let mut visibility = Visibility::new(130);
+    assert_eq!(3, visibility.words().len());
+    visibility.set(0);
+    visibility.set(64);
+    visibility.set(129);
+    assert!(visibility.get(64) && !visibility.get(65));
+    assert_eq!(3, visibility.count());
+    assert_eq!(vec![0, 64, 129], visibility.iter().collect::<Vec<_>>());
+    assert!(Visibility::new(0).is_empty());
+}
+
+#[test]
+fn batch_culling() {
+    let builder = crate::ProjectionMatrixBuilder::new().set_near(1.0).set_far(100.0).set_width(720);
+    let frustum = Frustum::new(&crate::math::identity(), &builder);
+    let aabbs = row_of_boxes(200);
+    let visibility = frustum.cull_aabbs(&aabbs);
+    assert_eq!(200, visibility.len());
+    assert!((0..200).all(|i| visibility.get(i) == frustum.intersects_aabb(&aabbs[i])));
+    // the boxes starting before the right plane, at x = 10 for z = 10
+    assert_eq!(6, visibility.count());
+    assert_eq!(visibility, frustum.cull_aabbs_parallel(&aabbs, 3));
+    assert_eq!(visibility, frustum.cull_aabbs_parallel(&aabbs, 0));
+    assert_eq!(Visibility::new(0), frustum.cull_aabbs_parallel(&[], 4));
+}
+
+#[test]
+fn bvh_culling() {
+    let builder = crate::ProjectionMatrixBuilder::new().set_near(1.0).set_far(100.0).set_width(720);
+    // a grid of boxes around the camera
+    let aabbs: Vec<Aabb> = (0..1000)
+        .map(|i| [i % 10, i / 10 % 10, i / 100].map(|j| j as f32 * 10.0 - 45.0))
+        .map(|center| Aabb::new(math::sub(center, [1.0; 3]), math::add(center, [1.0; 3])))
+        .collect();
+    let bvh = Bvh::new(&aabbs);
+    assert_eq!(1000, bvh.len());
+    assert_eq!(Some(Aabb::new([-46.0; 3], [46.0; 3])), bvh.bounds());
+    for target in [[0.0, 0.0, 1.0], [1.0, 0.5, 0.0], [-1.0, -1.0, -1.0]] {
+        let frustum = Frustum::new(&math::look_at([0.0; 3], target, [0.0, 1.0, 0.0]), &builder);
+        let visibility = frustum.cull_bvh(&bvh);
+        assert_eq!(frustum.cull_aabbs(&aabbs), visibility);
+        assert!(visibility.count() > 0 && visibility.count() < 1000);
+    }
+    assert!(Frustum::new(&math::identity(), &builder).cull_bvh(&Bvh::new(&[])).is_empty());
+}
@@ -0,0 +1,303 @@
+//! Helpers to work with the depth values produced by the projection
+
+use crate::math::{self, Matrix, Vec3};
+use crate::{Aabb, ProjectionMatrixBuilder};
+
+/// Smallest ratio between the near and far clips when the near clip is picked automatically
+const MIN_NEAR_FAR_RATIO: f32 = 1.0e-4;
+
+/// Number of iterations of the search of the z-fighting distance
+const Z_FIGHTING_ITERATIONS: usize = 48;
+
+/// Encoding of the depth values of the projected points
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DepthMode {
+    /// Depth produced by the perspective division of the projection matrix
+    Standard,
+    /// Logarithmic depth, `log2(1 + z) * coefficient`, to keep precision with huge far/near ratios.
+    /// The matrix can't produce it, the depth must be overwritten per vertex using the
+    /// [`ProjectionMatrixBuilder::log_depth_coefficient`] and multiplied by `w` to survive the perspective division.
+    Logarithmic,
+    /// Depth linear in the view space, `(z - near) / (far - near)`, written by the matrix in the clip space z.
+    /// It must be stored without the perspective division, as W-buffers do, and interpolated like `w`.
+    WBuffer,
+}
+
+/// Report of the precision of a depth buffer with a projection
+#[derive(Clone, Debug, PartialEq)]
+pub struct DepthPrecisionReport {
+    /// Bits of the depth buffer
+    pub bits: u32,
+    /// Sampled distances along with the smallest separation in the z-axis the buffer can tell apart at them
+    pub samples: Vec<(f32, f32)>,
+    /// Closest distance where surfaces separated by the tolerance are likely to z-fight
+    pub z_fighting_distance: Option<f32>,
+}
+
+impl ProjectionMatrixBuilder {
+    /// Converts a normalized device depth produced by the projection into the view space depth, the position
+    /// in the z-axis, of the point
+    ///
+    /// With a near clip at `0.0` every point is projected into the same standard depth and no depth can be recovered.
+    pub fn linearize_depth(&self, ndc_z: f32) -> f32 {
+        match self.depth_mode {
+            DepthMode::Standard => {
+                let (depth_scale, depth_offset) = self.depth_terms();
+                depth_offset / (ndc_z - depth_scale)
+            }
+            DepthMode::Logarithmic => (ndc_z / self.log_depth_coefficient()).exp2() - 1.0,
+            DepthMode::WBuffer => self.near + ndc_z * (self.far - self.near),
+        }
+    }
+
+    /// Converts a view space depth, the position in the z-axis, into the normalized device depth produced by the
+    /// projection. It's the inverse of [`ProjectionMatrixBuilder::linearize_depth`].
+    pub fn delinearize_depth(&self, view_z: f32) -> f32 {
+        match self.depth_mode {
+            DepthMode::Standard => {
+                let (depth_scale, depth_offset) = self.depth_terms();
+                depth_scale + depth_offset / view_z
+            }
+            DepthMode::Logarithmic => (1.0 + view_z.max(0.0)).log2() * self.log_depth_coefficient(),
+            DepthMode::WBuffer => (view_z - self.near) / (self.far - self.near),
+        }
+    }
+
+    /// Returns the smallest separation in the z-axis that a depth buffer with the given bits can tell apart
+    /// at the specified distance with this projection
+    pub fn depth_resolution(&self, bits: u32, distance: f32) -> f32 {
+        let steps = 2.0f64.powi(bits as i32) - 1.0;
+        let resolution = (self.depth_range() / steps / self.depth_slope(distance as f64)) as f32;
+        if resolution.is_finite() { resolution.abs() } else { f32::INFINITY }
+    }
+
+    /// Returns the closest distance where the depth resolution of a buffer with the given bits is bigger than the
+    /// tolerance, and thus surfaces that close are likely to z-fight, or `None` if it doesn't happen before the far
+    /// clip
+    pub fn z_fighting_distance(&self, bits: u32, tolerance: f32) -> Option<f32> {
+        if self.depth_resolution(bits, self.near) > tolerance {
+            return Some(self.near);
+        }
+        if self.depth_resolution(bits, self.far) <= tolerance {
+            return None;
+        }
+        // the resolution only worsens with the distance
+        let (mut close, mut far) = (self.near, self.far);
+        for _ in 0..Z_FIGHTING_ITERATIONS {
+            let middle = (close + far) * 0.5;
+            if self.depth_resolution(bits, middle) > tolerance {
+                far = middle;
+            } else {
+                close = middle;
+            }
+        }
+        Some(far)
+    }
+
+    /// Reports the depth resolution of a buffer with the given bits at each of the sampled distances along with the
+    /// distance where z-fighting becomes likely for surfaces separated by the tolerance
+    pub fn depth_precision(&self, bits: u32, distances: &[f32], tolerance: f32) -> DepthPrecisionReport {
+        DepthPrecisionReport {
+            bits,
+            samples: distances
+                .iter()
+                .map(|&distance| (distance, self.depth_resolution(bits, distance)))
+                .collect(),
+            z_fighting_distance: self.z_fighting_distance(bits, tolerance),
+        }
+    }
+
+    /// Returns a copy of the builder with the near and far clips tightened around the given view space depths, like
+    /// the ones read back from the previous frame, to improve the depth precision. The bounds are widened by the
+    /// margin, a fraction of the distances. Depths not in front of the camera are ignored and, when none is left,
+    /// the builder is returned untouched.
+    pub fn fit_depth_range(&self, depths: &[f32], margin: f32) -> Self {
+        let (closest, furthest) = depths
+            .iter()
+            .filter(|depth| depth.is_finite() && **depth > 0.0)
+            .fold((f32::INFINITY, 0.0f32), |(min, max), &depth| (min.min(depth), max.max(depth)));
+        if furthest == 0.0 {
+            return *self;
+        }
+        self.set_near(closest * (1.0 - margin).max(0.0))
+            .set_far(furthest * (1.0 + margin))
+    }
+
+    /// Returns a copy of the builder with the tightest near and far clips containing the whole scene box seen
+    /// through the view matrix. When the camera is inside the box the near clip is placed at a small fraction of the
+    /// far one and, when the box is fully behind the camera, the builder is returned untouched.
+    pub fn fit_scene(&self, scene: &Aabb, view: &Matrix) -> Self {
+        let (closest, furthest) = scene
+            .corners()
+            .iter()
+            .map(|&corner| math::transform_point(corner, view)[2])
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), depth| (min.min(depth), max.max(depth)));
+        if furthest <= 0.0 {
+            return *self;
+        }
+        self.set_near(closest.max(furthest * MIN_NEAR_FAR_RATIO)).set_far(furthest)
+    }
+
+    /// Returns the smallest and biggest normalized device depths of the world space points seen through the view
+    /// matrix, for depth bounds tests restricting the light and shadow passes to the depths they affect. The points
+    /// are clamped to the near and far clips and the ones not in front of the camera are ignored, returning `None`
+    /// when none is left.
+    pub fn depth_bounds(&self, points: &[Vec3], view: &Matrix) -> Option<(f32, f32)> {
+        points
+            .iter()
+            .map(|&point| math::transform_point(point, view)[2])
+            .filter(|depth| *depth > 0.0)
+            .map(|depth| self.delinearize_depth(depth.clamp(self.near, self.far)))
+            .fold(None, |bounds, depth| match bounds {
+                None => Some((depth, depth)),
+                Some((min, max)) => Some((depth.min(min), depth.max(max))),
+            })
+    }
+
+    /// Size of the depth interval between the near and far clips, the logarithmic coefficient is left out of both the
+    /// range and the slope
+    fn depth_range(&self) -> f64 {
+        let (near, far) = (self.near as f64, self.far as f64);
+        match self.depth_mode {
+            DepthMode::Standard => {
+                let (_, depth_offset) = self.depth_terms();
+                depth_offset as f64 * (1.0 / near - 1.0 / far)
+            }
+            DepthMode::Logarithmic => (1.0 + far).log2() - (1.0 + near).log2(),
+            DepthMode::WBuffer => 1.0,
+        }
+        .abs()
+    }
+
+    /// Derivative of the depth with regard of the view space depth
+    fn depth_slope(&self, view_z: f64) -> f64 {
+        match self.depth_mode {
+            DepthMode::Standard => {
+                let (_, depth_offset) = self.depth_terms();
+                depth_offset as f64 / (view_z * view_z)
+            }
+            DepthMode::Logarithmic => 1.0 / ((1.0 + view_z) * std::f64::consts::LN_2),
+            DepthMode::WBuffer => 1.0 / (self.far - self.near) as f64,
+        }
+    }
+
+    /// Returns the coefficient of the logarithmic depth, `1 / log2(1 + far)`, mapping the far clip to a depth of `1.0`
+    pub fn log_depth_coefficient(&self) -> f32 {
+        1.0 / (1.0 + self.far).log2()
+    }
+}
+
+#[test]
+fn depth_matches_matrix() {
+    let builder = ProjectionMatrixBuilder::new().set_near(0.5).set_far(100.0);
+    let matrix = builder.build();
+    for view_z in [0.5, 1.0, 10.0, 42.0, 100.0] {
+        let ndc_z = (view_z * matrix[2][2] + matrix[3][2]) / (view_z * matrix[2][3]);
+        assert!((ndc_z - builder.delinearize_depth(view_z)).abs() < 0.0001);
+    }
+}
+
+#[test]
+fn depth_round_trip() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_far(4.0);
+    for view_z in [1.0, 2.5, 3.0, 4.0] {
+        let ndc_z = builder.delinearize_depth(view_z);
+        assert!((view_z - builder.linearize_depth(ndc_z)).abs() / view_z < 0.001);
+    }
+}
+
+#[test]
+fn logarithmic_depth() {
+    let builder = ProjectionMatrixBuilder::new()
+        .set_near(0.1)
+        .set_far(1.0e7)
+        .set_depth_mode(DepthMode::Logarithmic);
+    assert!((1.0 - builder.delinearize_depth(1.0e7)).abs() < 0.0001);
+    assert!(builder.delinearize_depth(0.0).abs() < 0.0001);
+    for view_z in [0.5, 10.0, 1.0e3, 1.0e6] {
+        let ndc_z = builder.delinearize_depth(view_z);
+        assert!((view_z - builder.linearize_depth(ndc_z)).abs() / view_z < 0.001);
+    }
+}
+
+#[test]
+fn w_buffer_depth() {
+    let builder = ProjectionMatrixBuilder::new()
+        .set_near(1.0)
+        .set_far(101.0)
+        .set_depth_mode(DepthMode::WBuffer);
+    let matrix = builder.build();
+    for (view_z, depth) in [(1.0, 0.0), (51.0, 0.5), (101.0, 1.0)] {
+        assert!((depth - (view_z * matrix[2][2] + matrix[3][2])).abs() < 0.0001);
+        assert!((depth - builder.delinearize_depth(view_z)).abs() < 0.0001);
+        assert!((view_z - builder.linearize_depth(depth)).abs() < 0.0001);
+    }
+}
+
+#[test]
+fn depth_resolution() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_far(1001.0);
+    // the standard depth resolution grows with the square of the distance
+    let close = builder.depth_resolution(24, 10.0);
+    let far = builder.depth_resolution(24, 100.0);
+    assert!((100.0 - far / close).abs() < 0.01);
+    assert!((100.0 * 100.0 / (1 << 24) as f32 - far).abs() < 0.0001);
+
+    let builder = builder.set_depth_mode(DepthMode::WBuffer);
+    assert!((1000.0 / 255.0 - builder.depth_resolution(8, 500.0)).abs() < 0.0001);
+    let zero_near = ProjectionMatrixBuilder::new().set_near_policy(crate::NearPolicy::Allow).set_near(0.0);
+    assert_eq!(f32::INFINITY, zero_near.depth_resolution(24, 100.0));
+}
+
+#[test]
+fn z_fighting_distance() {
+    let builder = ProjectionMatrixBuilder::new().set_near(0.1).set_far(1000.0);
+    let distance = builder.z_fighting_distance(16, 0.01).unwrap();
+    assert!((0.01 - builder.depth_resolution(16, distance)).abs() < 0.0001);
+    assert_eq!(None, builder.set_near(100.0).z_fighting_distance(32, 1.0));
+    let zero_near = ProjectionMatrixBuilder::new().set_near_policy(crate::NearPolicy::Allow).set_near(0.0);
+    assert_eq!(Some(0.0), zero_near.z_fighting_distance(24, 0.1));
+
+    let report = builder.depth_precision(16, &[1.0, 10.0], 0.01);
+    assert_eq!(Some(distance), report.z_fighting_distance);
+    assert_eq!(2, report.samples.len());
+}
+
+#[test]
+fn fit_depth_range() {
+    let builder = ProjectionMatrixBuilder::new().fit_depth_range(&[12.0, -3.0, 50.0, f32::NAN, 20.0, 0.0], 0.1);
+    assert!((10.8 - builder.near).abs() < 0.0001);
+    assert!((55.0 - builder.far).abs() < 0.0001);
+    assert_eq!(ProjectionMatrixBuilder::new(), ProjectionMatrixBuilder::new().fit_depth_range(&[-1.0], 0.1));
+}
+
+#[test]
+fn fit_scene() {
+    let scene = Aabb::new([-10.0; 3], [10.0; 3]);
+    let view = math::look_at([0.0, 0.0, -30.0], [0.0; 3], [0.0, 1.0, 0.0]);
+    let builder = ProjectionMatrixBuilder::new().fit_scene(&scene, &view);
+    assert_eq!((20.0, 40.0), (builder.near, builder.far));
+
+    let view = math::look_at([0.0; 3], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]);
+    let builder = ProjectionMatrixBuilder::new().fit_scene(&scene, &view);
+    assert!((10.0 * MIN_NEAR_FAR_RATIO - builder.near).abs() < 0.00001);
+    assert_eq!(10.0, builder.far);
+
+    let view = math::look_at([0.0, 0.0, -30.0], [0.0, 0.0, -40.0], [0.0, 1.0, 0.0]);
+    assert_eq!(ProjectionMatrixBuilder::new(), ProjectionMatrixBuilder::new().fit_scene(&scene, &view));
+}
+
+#[test]
+fn depth_bounds() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_far(100.0);
+    let view = math::look_at([0.0, 0.0, -10.0], [0.0, 0.0, 0.0], [0.0, 1.0, 0.0]);
+    let points = [[0.0, 0.0, 0.0], [5.0, 2.0, 20.0], [1.0, 1.0, -30.0], [0.0, 0.0, -9.5]];
+    let (min, max) = builder.depth_bounds(&points, &view).unwrap();
+    assert_eq!(builder.delinearize_depth(1.0), min);
+    assert_eq!(builder.delinearize_depth(30.0), max);
+
+    let builder = builder.set_depth_mode(DepthMode::WBuffer);
+    assert_eq!(Some((0.0, 1.0)), builder.depth_bounds(&[[0.0, 0.0, -9.0], [0.0, 0.0, 500.0]], &view));
+    assert_eq!(None, builder.depth_bounds(&[[0.0, 0.0, -20.0]], &view));
+    assert_eq!(None, builder.depth_bounds(&[], &view));
+}
@@ -0,0 +1,198 @@
+//! Lens distortion, to correct the one of the VR headsets or match the one of real cameras
+//!
+//! The distortions are applied in the tangent space of the projection, the view space positions divided by their
+//! depth, where the center of the lens is the origin.
+
+use crate::clip;
+use crate::math::{self, Matrix, Vec3};
+use crate::{ProjectionMatrixBuilder, Viewport};
+
+/// Iterations of the fixed-point search inverting the Brown-Conrady distortion
+const UNDISTORT_ITERATIONS: usize = 20;
+
+/// Brown-Conrady model of the lens distortion of a real camera, as given by the usual calibration tools, with the
+/// radial coefficients `k1`, `k2` and `k3` and the tangential ones `p1` and `p2`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BrownConrady {
+    /// First radial coefficient
+    pub k1: f32,
+    /// Second radial coefficient
+    pub k2: f32,
+    /// Third radial coefficient
+    pub k3: f32,
+    /// First tangential coefficient
+    pub p1: f32,
+    /// Second tangential coefficient
+    pub p2: f32,
+}
+
+impl BrownConrady {
+    /// Returns the model with the given coefficients
+    pub fn new(k1: f32, k2: f32, k3: f32, p1: f32, p2: f32) -> Self {
+        Self { k1, k2, k3, p1, p2 }
+    }
+
+    /// Returns where the lens moves the point of the tangent space
+    pub fn distort(&self, point: [f32; 2]) -> [f32; 2] {
+        let (radial, [x, y]) = self.terms(point);
+        [point[0] * radial + x, point[1] * radial + y]
+    }
+
+    /// Returns the point of the tangent space moved by the lens into the given one, the inverse of
+    /// [`BrownConrady::distort`], searched iteratively
+    pub fn undistort(&self, point: [f32; 2]) -> [f32; 2] {
+        let mut undistorted = point;
+        for _ in 0..UNDISTORT_ITERATIONS {
+            let (radial, [x, y]) = self.terms(undistorted);
+            undistorted = [(point[0] - x) / radial, (point[1] - y) / radial];
+        }
+        undistorted
+    }
+
+    /// Returns the radial scale and the tangential offset of the distortion of the point
+    fn terms(&self, [x, y]: [f32; 2]) -> (f32, [f32; 2]) {
+        let r2 = x * x + y * y;
+        let radial = 1.0 + r2 * (self.k1 + r2 * (self.k2 + r2 * self.k3));
+        let tangential = [
+            2.0 * self.p1 * x * y + self.p2 * (r2 + 2.0 * x * x),
+            self.p1 * (r2 + 2.0 * y * y) + 2.0 * self.p2 * x * y,
+        ];
+        (radial, tangential)
+    }
+}
+
+/// Grid mesh covering the screen with the texture coordinates warped by a lens distortion. Drawing the rendered
+/// texture of an eye over the mesh pre-distorts it to cancel the distortion of the lens.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DistortionMesh {
+    /// Position of the vertices in normalized device coordinates, by rows from the top left corner
+    pub positions: Vec<[f32; 2]>,
+    /// Texture coordinates of the vertices, with the origin at the top left corner of the texture
+    pub uvs: Vec<[f32; 2]>,
+    /// Indices of the vertices of the triangles of the mesh
+    pub indices: Vec<[usize; 3]>,
+}
+
+impl ProjectionMatrixBuilder {
+    /// Returns the grid mesh with the given number of cells of an eye with this projection warped by the radial
+    /// distortion `1 + k1 * r^2 + k2 * r^4`, positive coefficients for the barrel distortion and negative ones for
+    /// the pincushion distortion
+    ///
+    /// # Panic
+    /// If the grid has no columns or rows
+    pub fn distortion_mesh(&self, k1: f32, k2: f32, columns: usize, rows: usize) -> DistortionMesh {
+        if columns == 0 || rows == 0 {
+            panic!("The distortion mesh must have at least one column and one row");
+        }
+        let projection = self.build();
+        let mut mesh = DistortionMesh {
+            positions: Vec::with_capacity((columns + 1) * (rows + 1)),
+            uvs: Vec::with_capacity((columns + 1) * (rows + 1)),
+            indices: Vec::with_capacity(columns * rows * 2),
+        };
+        for row in 0..=rows {
+            for column in 0..=columns {
+                let (x, y) = (column as f32 / columns as f32 * 2.0 - 1.0, 1.0 - row as f32 / rows as f32 * 2.0);
+                let [tangent_x, tangent_y] = ndc_to_tangent([x, y], &projection);
+                let r2 = tangent_x * tangent_x + tangent_y * tangent_y;
+                let scale = 1.0 + k1 * r2 + k2 * r2 * r2;
+                let [source_x, source_y] = tangent_to_ndc([tangent_x * scale, tangent_y * scale], &projection);
+                mesh.positions.push([x, y]);
+                mesh.uvs.push([(source_x + 1.0) * 0.5, (1.0 - source_y) * 0.5]);
+            }
+        }
+        for row in 0..rows {
+            for column in 0..columns {
+                let top_left = row * (columns + 1) + column;
+                let bottom_left = top_left + columns + 1;
+                mesh.indices.push([top_left, bottom_left, top_left + 1]);
+                mesh.indices.push([top_left + 1, bottom_left, bottom_left + 1]);
+            }
+        }
+        mesh
+    }
+
+    /// Projects a world space point seen through the view matrix into the viewport like
+    /// [`ProjectionMatrixBuilder::world_to_screen`], moving it by the lens distortion so it lands where a real camera
+    /// with the lens would see it
+    pub fn world_to_screen_distorted(&self, point: Vec3, view: &Matrix, viewport: &Viewport, lens: &BrownConrady)
+        -> Option<(f32, f32, f32)> {
+        let projection = self.build();
+        let clip = math::transform_point(point, &math::multiply(view, &projection));
+        let w = clip[3];
+        let ndc = clip::clip_to_ndc(clip).filter(|_| w >= self.near && w <= self.far)?;
+        let [x, y] = tangent_to_ndc(lens.distort(ndc_to_tangent([ndc.x, ndc.y], &projection)), &projection);
+        let distorted = clip::NdcPoint { x, y, z: ndc.z };
+        let (screen_x, screen_y) = distorted.is_on_screen().then(|| distorted.to_screen(viewport))?;
+        Some((screen_x, screen_y, self.delinearize_depth(w)))
+    }
+}
+
+/// Returns the tangent space position projected into the normalized device coordinates
+fn ndc_to_tangent(ndc: [f32; 2], projection: &Matrix) -> [f32; 2] {
+    [(ndc[0] - projection[2][0]) / projection[0][0], (ndc[1] - projection[2][1]) / projection[1][1]]
+}
+
+/// Returns the normalized device coordinates of the tangent space position
+fn tangent_to_ndc(tangent: [f32; 2], projection: &Matrix) -> [f32; 2] {
+    [tangent[0] * projection[0][0] + projection[2][0], tangent[1] * projection[1][1] + projection[2][1]]
+}
+
+#[test]
+fn distortion_mesh() {
+    let builder = ProjectionMatrixBuilder::new().set_width(1000).set_height(1000);
+    let mesh = builder.distortion_mesh(0.2, 0.05, 4, 2);
+    assert_eq!(15, mesh.positions.len());
+    assert_eq!(16, mesh.indices.len());
+    assert_eq!([[0, 5, 1], [1, 5, 6]], mesh.indices[..2]);
+    assert_eq!([-1.0, 1.0], mesh.positions[0]);
+
+    // the center is kept and the corners are pulled from further away
+    assert_eq!([0.5, 0.5], mesh.uvs[7]);
+    let [u, v] = mesh.uvs[0];
+    assert!(u < 0.0 && v < 0.0 && (u - v).abs() < 0.0001);
+    let undistorted = builder.distortion_mesh(0.0, 0.0, 4, 2);
+    assert_eq!(mesh.positions, undistorted.positions);
+    assert!(undistorted.uvs.iter().zip(&undistorted.positions)
+        .all(|([u, v], [x, y])| ((u * 2.0 - 1.0) - x).abs() < 0.0001 && ((1.0 - v * 2.0) - y).abs() < 0.0001));
+}
+
+#[test]
+fn off_axis_distortion_mesh() {
+    // the center of the lens follows the shift of the projection, not the center of the screen
+    let builder = ProjectionMatrixBuilder::new().set_width(1000).set_height(1000).set_safe_area(0.2, 0.0, 0.0, 0.0);
+    let mesh = builder.distortion_mesh(0.5, 0.0, 10, 2);
+    let ([x, y], [u, v]) = (mesh.positions[17], mesh.uvs[17]);
+    assert!((x - 0.2).abs() < 0.0001 && y == 0.0);
+    assert!((u - 0.6).abs() < 0.0001 && (v - 0.5).abs() < 0.0001);
+    assert!((mesh.uvs[16][0] - 0.5).abs() > 0.0001);
+}
+
+#[test]
+fn brown_conrady_round_trip() {
+    let lens = BrownConrady::new(-0.28, 0.07, -0.01, 0.001, -0.0005);
+    for point in [[0.0, 0.0], [0.3, -0.2], [-0.5, 0.4], [0.1, 0.6]] {
+        let [x, y] = lens.undistort(lens.distort(point));
+        assert!((x - point[0]).abs() < 0.0001 && (y - point[1]).abs() < 0.0001, "{point:?} != {:?}", [x, y]);
+    }
+    assert_eq!([0.5, -0.5], BrownConrady::default().distort([0.5, -0.5]));
+    // the barrel distortion pulls the points towards the center
+    let [x, _] = BrownConrady::new(-0.2, 0.0, 0.0, 0.0, 0.0).distort([0.5, 0.0]);
+    assert!((0.475 - x).abs() < 0.0001);
+}
+
+#[test]
+fn distorted_projection() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_width(720);
+    let viewport = Viewport::new(0, 0, 720, 720);
+    let view = math::identity();
+    let lens = BrownConrady::new(-0.2, 0.0, 0.0, 0.0, 0.0);
+    assert_eq!(builder.world_to_screen([0.0, 0.0, 5.0], &view, &viewport),
+               builder.world_to_screen_distorted([0.0, 0.0, 5.0], &view, &viewport, &lens));
+
+    let (x, y, _) = builder.world_to_screen_distorted([2.5, 0.0, 5.0], &view, &viewport, &lens).unwrap();
+    assert!((360.0 + 0.475 * 360.0 - x).abs() < 0.01 && (360.0 - y).abs() < 0.01);
+    // the distortion brings points from out of the view into the screen
+    assert_eq!(None, builder.world_to_screen([5.5, 0.0, 5.0], &view, &viewport));
+    assert!(builder.world_to_screen_distorted([5.5, 0.0, 5.0], &view, &viewport, &lens).is_some());
+}
@@ -0,0 +1,265 @@
+//! Per-frame camera effects applied over the projection
+
+use crate::math::{self, Matrix};
+use crate::{ProjectionMatrix, ProjectionMatrixBuilder, MAX_SUPPORTED_FOV};
+
+/// Narrowest field of view reachable by the punches
+const MIN_PUNCHED_FOV: f32 = 1.0;
+/// Offsets of the punches small enough to be considered over
+const PUNCH_THRESHOLD: f32 = 0.001;
+
+/// Easing curve of the animations, mapping the linear progress into the eased one
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Easing {
+    /// Constant speed
+    Linear,
+    /// Starts slow and speeds up
+    EaseIn,
+    /// Starts fast and slows down
+    EaseOut,
+    /// Starts and ends slow
+    EaseInOut,
+}
+
+impl Easing {
+    /// Returns the eased progress of the linear progress, both in the `[0, 1]` range
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// Animator of smooth transitions between projections over time, like zooming when aiming down the sights or widening
+/// the field of view when sprinting
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FovAnimator {
+    from: ProjectionMatrixBuilder,
+    to: ProjectionMatrixBuilder,
+    duration: f32,
+    elapsed: f32,
+    easing: Easing,
+}
+
+impl FovAnimator {
+    /// Returns an animator resting at the given projection
+    pub fn new(projection: ProjectionMatrixBuilder) -> Self {
+        Self { from: projection, to: projection, duration: 0.0, elapsed: 0.0, easing: Easing::Linear }
+    }
+
+    /// Starts a transition from the current state towards the field of view over the given duration
+    pub fn animate_fov(&mut self, fov: f32, duration: f32, easing: Easing) {
+        self.animate_to(self.current().set_fov(fov), duration, easing);
+    }
+
+    /// Starts a transition from the current state towards the target projection over the given duration, every
+    /// parameter of the projection is animated, like the near and far clips
+    pub fn animate_to(&mut self, target: ProjectionMatrixBuilder, duration: f32, easing: Easing) {
+        self.from = self.current();
+        self.to = target;
+        self.duration = duration.max(0.0);
+        self.elapsed = 0.0;
+        self.easing = easing;
+    }
+
+    /// Advances the transition by the elapsed time since the last frame and returns the projection matrix of the frame
+    pub fn update(&mut self, delta: f32) -> ProjectionMatrix {
+        self.elapsed = (self.elapsed + delta).min(self.duration);
+        self.current().build()
+    }
+
+    /// Returns the projection at the current point of the transition
+    pub fn current(&self) -> ProjectionMatrixBuilder {
+        if self.is_finished() {
+            return self.to;
+        }
+        self.from.interpolate(&self.to, self.easing.apply(self.elapsed / self.duration))
+    }
+
+    /// Returns if the transition reached its target
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
+/// Short-lived field of view impulses, like recoils, hits or boosts, added over a base projection and decaying over
+/// time
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FovPunch {
+    /// Degrees currently added to the field of view
+    pub offset: f32,
+    /// Rate of the exponential decay of the offset, the bigger the faster the punches fade
+    pub decay: f32,
+}
+
+impl FovPunch {
+    /// Returns the effect without punches and the given decay rate
+    pub fn new(decay: f32) -> Self {
+        Self { offset: 0.0, decay }
+    }
+
+    /// Adds an impulse of the given degrees, negative degrees narrow the view
+    pub fn kick(&mut self, degrees: f32) {
+        self.offset += degrees;
+    }
+
+    /// Decays the punches by the elapsed time since the last frame and returns the projection matrix of the frame,
+    /// the base projection with the punches applied. The punched field of view is kept within the (1, 179) range.
+    pub fn update(&mut self, delta: f32, base: &ProjectionMatrixBuilder) -> ProjectionMatrix {
+        self.offset *= (-self.decay * delta).exp();
+        if self.offset.abs() < PUNCH_THRESHOLD {
+            self.offset = 0.0;
+        }
+        self.apply(base).build()
+    }
+
+    /// Returns the base projection with the current punches applied
+    pub fn apply(&self, base: &ProjectionMatrixBuilder) -> ProjectionMatrixBuilder {
+        base.set_fov((base.fov + self.offset).clamp(MIN_PUNCHED_FOV, MAX_SUPPORTED_FOV))
+    }
+
+    /// Returns if there are punches still in effect
+    pub fn is_active(&self) -> bool {
+        self.offset != 0.0
+    }
+}
+
+/// Camera shake applied in clip space over the projection, without touching the view matrix. The shake is driven by
+/// a trauma value that decays over time, the offsets and rotation grow with the square of the trauma and follow a
+/// smooth noise.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScreenShake {
+    /// Current trauma, in the `[0, 1]` range
+    pub trauma: f32,
+    /// Trauma lost per second
+    pub decay: f32,
+    /// Biggest offset of the view, in normalized device coordinates
+    pub max_offset: f32,
+    /// Biggest rotation of the view, in degrees
+    pub max_rotation: f32,
+    /// Speed of the noise driving the shake, the bigger the more violent
+    pub frequency: f32,
+    time: f32,
+}
+
+impl ScreenShake {
+    /// Returns the shake with no trauma and the given limits. By default the trauma decays in one second and the
+    /// noise frequency is `15.0`.
+    pub fn new(max_offset: f32, max_rotation: f32) -> Self {
+        Self { trauma: 0.0, decay: 1.0, max_offset, max_rotation, frequency: 15.0, time: 0.0 }
+    }
+
+    /// Adds trauma to the shake, the trauma is limited to `1.0`
+    pub fn add_trauma(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).clamp(0.0, 1.0);
+    }
+
+    /// Advances the shake by the elapsed time since the last frame and returns the projection matrix with the shake
+    /// of the frame applied
+    pub fn update(&mut self, delta: f32, projection: &Matrix) -> Matrix {
+        self.time += delta;
+        self.trauma = (self.trauma - self.decay * delta).max(0.0);
+        self.apply(projection)
+    }
+
+    /// Returns the projection matrix with the current shake applied
+    pub fn apply(&self, projection: &Matrix) -> Matrix {
+        let shake = self.trauma * self.trauma;
+        let t = self.time * self.frequency;
+        let (x, y) = (shake * self.max_offset * noise(0.0, t), shake * self.max_offset * noise(17.0, t));
+        let (sin, cos) = (shake * self.max_rotation * noise(43.0, t)).to_radians().sin_cos();
+        // rotation around the center of the screen and then the offset, scaled by w to survive the division
+        let clip_shake = [
+            [cos, sin, 0.0, 0.0],
+            [-sin, cos, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [x, y, 0.0, 1.0],
+        ];
+        math::multiply(projection, &clip_shake)
+    }
+}
+
+/// Smooth noise in the `[-1, 1]` range, different for each seed
+fn noise(seed: f32, t: f32) -> f32 {
+    ((t + seed).sin() + (t * 2.31 + seed * 1.7).sin() * 0.5 + (t * 4.73 + seed * 2.9).sin() * 0.25) / 1.75
+}
+
+#[test]
+fn easing_curves() {
+    for easing in [Easing::Linear, Easing::EaseIn, Easing::EaseOut, Easing::EaseInOut] {
+        assert_eq!(0.0, easing.apply(-1.0));
+        assert_eq!(1.0, easing.apply(1.0));
+    }
+    assert_eq!(0.25, Easing::EaseIn.apply(0.5));
+    assert_eq!(0.75, Easing::EaseOut.apply(0.5));
+    assert_eq!(0.5, Easing::EaseInOut.apply(0.5));
+}
+
+#[test]
+fn fov_animation() {
+    let mut animator = FovAnimator::new(ProjectionMatrixBuilder::new());
+    assert!(animator.is_finished());
+    animator.animate_fov(60.0, 1.0, Easing::Linear);
+    assert!(!animator.is_finished());
+
+    animator.update(0.5);
+    assert_eq!(75.0, animator.current().fov);
+    assert_eq!(ProjectionMatrixBuilder::new().set_fov(60.0).build(), animator.update(0.75));
+    assert!(animator.is_finished());
+
+    // retargeting starts from the current state
+    animator.animate_to(ProjectionMatrixBuilder::new().set_fov(90.0).set_far(2000.0), 2.0, Easing::EaseInOut);
+    animator.update(1.0);
+    assert_eq!((75.0, 1500.0), (animator.current().fov, animator.current().far));
+
+    animator.animate_fov(30.0, 0.0, Easing::Linear);
+    assert_eq!(30.0, animator.current().fov);
+}
+
+#[test]
+fn fov_punch() {
+    let base = ProjectionMatrixBuilder::new();
+    let mut punch = FovPunch::new(2.0_f32.ln());
+    assert!(!punch.is_active());
+    punch.kick(10.0);
+    punch.kick(6.0);
+    assert_eq!(106.0, punch.apply(&base).fov);
+
+    // the decay halves the offset every second
+    punch.update(1.0, &base);
+    assert!((8.0 - punch.offset).abs() < 0.0001);
+    assert!((98.0 - punch.apply(&base).fov).abs() < 0.0001);
+    punch.update(100.0, &base);
+    assert!(!punch.is_active());
+    assert_eq!(base.build(), punch.update(0.1, &base));
+
+    punch.kick(200.0);
+    assert_eq!(MAX_SUPPORTED_FOV, punch.apply(&base).fov);
+}
+
+#[test]
+fn screen_shake() {
+    let projection = ProjectionMatrixBuilder::new().set_near(1.0).build();
+    let point = [0.0, 0.0, 10.0, 1.0];
+    let mut shake = ScreenShake::new(0.1, 5.0);
+    assert_eq!(projection, shake.update(0.1, &projection));
+
+    shake.add_trauma(2.0);
+    assert_eq!(1.0, shake.trauma);
+    let mut moved = false;
+    for _ in 0..10 {
+        let shaken = shake.update(0.05, &projection);
+        let [x, y, z, w] = math::transform(point, &shaken);
+        assert!((x / w).abs() <= 0.1 && (y / w).abs() <= 0.1);
+        assert_eq!((z, w), { let clip = math::transform(point, &projection); (clip[2], clip[3]) });
+        moved |= x != 0.0 || y != 0.0;
+    }
+    assert!(moved);
+    assert!((0.5 - shake.trauma).abs() < 0.0001);
+    shake.update(1.0, &projection);
+    assert_eq!(projection, shake.apply(&projection));
+}
@@ -0,0 +1,106 @@
+//! Projections with the frame size fixed at compile time
+
+use crate::math::Matrix;
+use crate::{DepthMode, ProjectionMatrix, ProjectionMatrixBuilder};
+
+/// Builder of projection matrices for a frame size known at compile time, like the one of a fixed display or an
+/// offscreen target. The aspect ratio is computed at compile time and empty frames are rejected by the compiler.
+///
+/// ```compile_fail
+/// // a frame without height doesn't compile
+/// let builder = ferrux_projection_matrix::FixedProjectionBuilder::<1280, 0>::new();
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FixedProjectionBuilder<const WIDTH: usize, const HEIGHT: usize> {
+    builder: ProjectionMatrixBuilder,
+}
+
+impl<const WIDTH: usize, const HEIGHT: usize> FixedProjectionBuilder<WIDTH, HEIGHT> {
+    /// Ratio between the width and the height of the frame
+    pub const ASPECT_RATIO: f32 = {
+        assert!(WIDTH > 0 && HEIGHT > 0, "The frame width and height must be positive");
+        WIDTH as f32 / HEIGHT as f32
+    };
+
+    /// Returns an instance of a builder with the defaults of [`ProjectionMatrixBuilder::new`] and the fixed frame size
+    pub const fn new() -> Self {
+        // checks the frame size at compile time
+        let _ = Self::ASPECT_RATIO;
+        Self { builder: ProjectionMatrixBuilder::new().set_width(WIDTH).set_height(HEIGHT) }
+    }
+
+    /// Sets the near clip position in the z axis
+    pub const fn set_near(mut self, near: f32) -> Self {
+        self.builder = self.builder.set_near(near);
+        self
+    }
+
+    /// Sets the far clip position in the z axis
+    pub const fn set_far(mut self, far: f32) -> Self {
+        self.builder = self.builder.set_far(far);
+        self
+    }
+
+    /// Sets the field of view in grades
+    ///
+    /// # Panic
+    /// If the entered fov is not within the supported [0.1, 179] range
+    pub fn set_fov(mut self, fov: f32) -> Self {
+        self.builder = self.builder.set_fov(fov);
+        self
+    }
+
+    /// Sets the encoding of the depth values, see [`DepthMode`]
+    pub const fn set_depth_mode(mut self, depth_mode: DepthMode) -> Self {
+        self.builder = self.builder.set_depth_mode(depth_mode);
+        self
+    }
+
+    /// Returns the builder of the projection, to use the rest of the crate with it
+    pub const fn builder(&self) -> ProjectionMatrixBuilder {
+        self.builder
+    }
+
+    /// Builds the projection matrix derived from the entered parameters
+    ///
+    /// # Panic
+    /// If the far clip is closer than the near clip
+    pub fn build(&self) -> ProjectionMatrix {
+        self.builder.build()
+    }
+
+    /// Builds the projection matrix with the given fov scale, see [`ProjectionMatrixBuilder::build_with_fov_scale`]
+    ///
+    /// # Panic
+    /// If the far clip is closer than the near clip
+    pub const fn build_with_fov_scale(&self, fov_scale: f32) -> Matrix {
+        self.builder.build_with_fov_scale(fov_scale)
+    }
+}
+
+impl<const WIDTH: usize, const HEIGHT: usize> Default for FixedProjectionBuilder<WIDTH, HEIGHT> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const WIDTH: usize, const HEIGHT: usize> From<FixedProjectionBuilder<WIDTH, HEIGHT>> for ProjectionMatrixBuilder {
+    fn from(fixed: FixedProjectionBuilder<WIDTH, HEIGHT>) -> Self {
+        fixed.builder
+    }
+}
+
+#[test]
+fn fixed_resolution() {
+    type Display = FixedProjectionBuilder<1920, 1080>;
+    assert_eq!(1920.0 / 1080.0, Display::ASPECT_RATIO);
+    let fixed = Display::new().set_near(1.0).set_far(100.0).set_fov(70.0);
+    let builder = ProjectionMatrixBuilder::new().set_width(1920).set_height(1080).set_near(1.0).set_far(100.0)
+        .set_fov(70.0);
+    assert_eq!(builder, fixed.builder());
+    assert_eq!(builder.build(), fixed.build());
+    assert_eq!(builder, ProjectionMatrixBuilder::from(fixed));
+
+    const MATRIX: Matrix = FixedProjectionBuilder::<720, 720>::new().set_near(1.0).build_with_fov_scale(1.0);
+    assert_eq!(1.0, MATRIX[0][0]);
+}
@@ -0,0 +1,109 @@
+//! Conversions between the different ways to express a field of view
+//!
+//! All the angles are in degrees, like the field of view of the builder, and the aspect ratio is the width of the
+//! frame divided by its height.
+
+/// Width of a full frame, 35mm film, sensor in millimeters
+pub const FULL_FRAME_WIDTH: f32 = 36.0;
+/// Height of a full frame, 35mm film, sensor in millimeters
+pub const FULL_FRAME_HEIGHT: f32 = 24.0;
+
+/// Converts a horizontal field of view into the vertical one
+pub fn horizontal_to_vertical(hfov: f32, aspect_ratio: f32) -> f32 {
+    scale_fov(hfov, 1.0 / aspect_ratio)
+}
+
+/// Converts a vertical field of view into the horizontal one
+pub fn vertical_to_horizontal(vfov: f32, aspect_ratio: f32) -> f32 {
+    scale_fov(vfov, aspect_ratio)
+}
+
+/// Converts a diagonal field of view into the vertical one
+pub fn diagonal_to_vertical(dfov: f32, aspect_ratio: f32) -> f32 {
+    scale_fov(dfov, 1.0 / diagonal_ratio(aspect_ratio))
+}
+
+/// Converts a vertical field of view into the diagonal one
+pub fn vertical_to_diagonal(vfov: f32, aspect_ratio: f32) -> f32 {
+    scale_fov(vfov, diagonal_ratio(aspect_ratio))
+}
+
+/// Converts a horizontal field of view into the diagonal one
+pub fn horizontal_to_diagonal(hfov: f32, aspect_ratio: f32) -> f32 {
+    vertical_to_diagonal(horizontal_to_vertical(hfov, aspect_ratio), aspect_ratio)
+}
+
+/// Converts a diagonal field of view into the horizontal one
+pub fn diagonal_to_horizontal(dfov: f32, aspect_ratio: f32) -> f32 {
+    vertical_to_horizontal(diagonal_to_vertical(dfov, aspect_ratio), aspect_ratio)
+}
+
+/// Converts the focal length of a lens into the field of view covering the given size of the sensor, both in the
+/// same units
+pub fn focal_length_to_fov(focal_length: f32, sensor_size: f32) -> f32 {
+    (sensor_size * 0.5 / focal_length).atan().to_degrees() * 2.0
+}
+
+/// Converts a field of view into the focal length of a lens covering with it the given size of the sensor, both in
+/// the same units
+pub fn fov_to_focal_length(fov: f32, sensor_size: f32) -> f32 {
+    sensor_size * 0.5 / (fov * 0.5).to_radians().tan()
+}
+
+/// Converts a 35mm equivalent focal length, in millimeters, into the vertical field of view of a frame with the
+/// given aspect ratio. The equivalence keeps the diagonal of the full frame sensor.
+pub fn equivalent_focal_length_to_vertical(focal_length: f32, aspect_ratio: f32) -> f32 {
+    let diagonal = FULL_FRAME_WIDTH.hypot(FULL_FRAME_HEIGHT);
+    diagonal_to_vertical(focal_length_to_fov(focal_length, diagonal), aspect_ratio)
+}
+
+/// Converts the vertical field of view of a frame with the given aspect ratio into the 35mm equivalent focal length,
+/// in millimeters. The equivalence keeps the diagonal of the full frame sensor.
+pub fn vertical_to_equivalent_focal_length(vfov: f32, aspect_ratio: f32) -> f32 {
+    let diagonal = FULL_FRAME_WIDTH.hypot(FULL_FRAME_HEIGHT);
+    fov_to_focal_length(vertical_to_diagonal(vfov, aspect_ratio), diagonal)
+}
+
+/// Ratio between the diagonal and the height of the frame
+fn diagonal_ratio(aspect_ratio: f32) -> f32 {
+    (1.0 + aspect_ratio * aspect_ratio).sqrt()
+}
+
+/// Returns the field of view covering an extent scaled by the given ratio at the same distance
+fn scale_fov(fov: f32, ratio: f32) -> f32 {
+    ((fov * 0.5).to_radians().tan() * ratio).atan().to_degrees() * 2.0
+}
+
+#[test]
+fn horizontal_vertical() {
+    assert!((90.0 - vertical_to_horizontal(90.0, 1.0)).abs() < 0.001);
+    assert!((106.26 - vertical_to_horizontal(90.0, 4.0 / 3.0)).abs() < 0.01);
+    assert!((58.72 - horizontal_to_vertical(90.0, 16.0 / 9.0)).abs() < 0.01);
+    assert!((73.74 - horizontal_to_vertical(90.0, 4.0 / 3.0)).abs() < 0.01);
+}
+
+#[test]
+fn diagonal() {
+    let aspect_ratio = 4.0 / 3.0;
+    // a 3-4-5 frame at a distance of 2.5 units
+    assert!((90.0 - vertical_to_diagonal(2.0 * (1.5f32 / 2.5).atan().to_degrees(), aspect_ratio)).abs() < 0.001);
+    assert!((2.0 * (2.0f32 / 2.5).atan().to_degrees() - diagonal_to_horizontal(90.0, aspect_ratio)).abs() < 0.001);
+    let hfov = 75.0;
+    assert!((hfov - diagonal_to_horizontal(horizontal_to_diagonal(hfov, 2.0), 2.0)).abs() < 0.001);
+    let vfov = diagonal_to_vertical(horizontal_to_diagonal(hfov, 2.0), 2.0);
+    assert!((hfov - vertical_to_horizontal(vfov, 2.0)).abs() < 0.001);
+}
+
+#[test]
+fn focal_length() {
+    // a 50mm lens on a full frame sensor
+    assert!((26.99 - focal_length_to_fov(50.0, FULL_FRAME_HEIGHT)).abs() < 0.01);
+    assert!((39.60 - focal_length_to_fov(50.0, FULL_FRAME_WIDTH)).abs() < 0.01);
+    assert!((50.0 - fov_to_focal_length(focal_length_to_fov(50.0, 24.0), 24.0)).abs() < 0.001);
+
+    // the full frame aspect ratio keeps the vertical field of view of the sensor
+    let vfov = equivalent_focal_length_to_vertical(50.0, 1.5);
+    assert!((focal_length_to_fov(50.0, FULL_FRAME_HEIGHT) - vfov).abs() < 0.001);
+    let vfov = equivalent_focal_length_to_vertical(28.0, 1.5);
+    assert!((28.0 - vertical_to_equivalent_focal_length(vfov, 1.5)).abs() < 0.001);
+}
@@ -0,0 +1,298 @@
+//! Frustums of the projections, to test what is visible from the camera
+
+use std::io::{self, Write};
+
+use crate::bounds::BOX_EDGES;
+use crate::math::{self, Matrix, Vec3, Vec4};
+use crate::screen::screen_to_ndc;
+use crate::{Aabb, ProjectionMatrixBuilder, Quaternion, ScreenRect, Viewport};
+
+/// Plane of the space, the points with a signed distance of zero
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Plane {
+    /// Unit vector perpendicular to the plane, pointing to its positive side
+    pub normal: Vec3,
+    /// Signed distance from the plane to the origin
+    pub distance: f32,
+}
+
+impl Plane {
+    /// Returns the plane with the given normal and distance, normalizing both
+    pub fn new(normal: Vec3, distance: f32) -> Self {
+        let length = math::length(normal);
+        Self { normal: math::scale(normal, 1.0 / length), distance: distance / length }
+    }
+
+    /// Returns the plane going through the point with the given normal
+    pub fn from_point_normal(point: Vec3, normal: Vec3) -> Self {
+        let normal = math::normalize(normal);
+        Self { normal, distance: -math::dot(normal, point) }
+    }
+
+    /// Returns the signed distance from the plane to the point, positive in the side the normal points to
+    pub fn signed_distance(&self, point: Vec3) -> f32 {
+        math::dot(self.normal, point) + self.distance
+    }
+
+    /// Returns the plane from its homogeneous coefficients
+    fn from_coefficients(coefficients: Vec4) -> Self {
+        let [x, y, z, w] = coefficients;
+        Self::new([x, y, z], w)
+    }
+}
+
+/// Volume of the space seen by a camera, bounded by six planes with their normals pointing inwards
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frustum {
+    /// Left, right, bottom, top, near and far planes
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Returns the world space frustum of the projection seen through the view matrix
+    pub fn new(view: &Matrix, projection: &ProjectionMatrixBuilder) -> Self {
+        Self::from_matrix(view, &projection.build(), projection.near, projection.far)
+    }
+
+    /// Returns the world space frustum, seen through the view matrix, covering just the given rectangle of the
+    /// viewport, like the one of a selection box
+    pub fn from_screen_rect(rect: &ScreenRect, viewport: &Viewport, view: &Matrix,
+                            projection: &ProjectionMatrixBuilder) -> Self {
+        let (left, top) = screen_to_ndc(rect.left.min(rect.right), rect.top.min(rect.bottom), viewport);
+        let (right, bottom) = screen_to_ndc(rect.left.max(rect.right), rect.top.max(rect.bottom), viewport);
+        Self::from_ndc_rect([left, right, bottom, top], view, &projection.build(), projection.near, projection.far)
+    }
+
+    /// Returns if the point is inside the frustum
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        self.planes.iter().all(|plane| plane.signed_distance(point) >= 0.0)
+    }
+
+    /// Returns if the sphere is inside or crosses the frustum
+    pub fn intersects_sphere(&self, center: Vec3, radius: f32) -> bool {
+        self.planes.iter().all(|plane| plane.signed_distance(center) >= -radius)
+    }
+
+    /// Returns if the box is inside or crosses the frustum. The test is conservative, some boxes near the
+    /// edges of the frustum may be reported as intersecting without doing it.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // the corner furthest along the normal
+            let corner = [0, 1, 2].map(|i| if plane.normal[i] >= 0.0 { aabb.max[i] } else { aabb.min[i] });
+            plane.signed_distance(corner) >= 0.0
+        })
+    }
+
+    /// Returns if the box with the given center, half extents along its axes and orientation is inside or crosses the
+    /// frustum. The test is conservative like [`Frustum::intersects_aabb`].
+    pub fn intersects_obb(&self, center: Vec3, half_extents: Vec3, orientation: &Quaternion) -> bool {
+        let axes = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]].map(|axis| orientation.rotate(axis));
+        self.planes.iter().all(|plane| {
+            // the extent of the box along the normal
+            let radius: f32 = (0..3).map(|i| math::dot(plane.normal, axes[i]).abs() * half_extents[i]).sum();
+            plane.signed_distance(center) >= -radius
+        })
+    }
+
+    /// Returns if both frustums overlap, using the separating axis theorem
+    pub fn intersects(&self, other: &Frustum) -> bool {
+        let (corners, other_corners) = (self.corners(), other.corners());
+        let edges = |corners: &[Vec3; 8]| BOX_EDGES.map(|(a, b)| math::sub(corners[b], corners[a]));
+        let (edges, other_edges) = (edges(&corners), edges(&other_corners));
+
+        let face_normals = self.planes.iter().chain(other.planes.iter()).map(|plane| plane.normal);
+        let edge_normals = edges
+            .iter()
+            .flat_map(|&edge| other_edges.iter().map(move |&other_edge| math::cross(edge, other_edge)));
+        !face_normals
+            .chain(edge_normals)
+            .filter(|&axis| math::length(axis) > f32::EPSILON)
+            .any(|axis| {
+                let (min, max) = project_onto(&corners, axis);
+                let (other_min, other_max) = project_onto(&other_corners, axis);
+                max < other_min || other_max < min
+            })
+    }
+
+    /// Returns the eight corners of the frustum, the bits of the index tell if the corner is on the right, top and far
+    /// planes, from the lowest to the highest bit, like the corners of [`Aabb::corners`]
+    pub fn corners(&self) -> [Vec3; 8] {
+        let [left, right, bottom, top, near, far] = self.planes;
+        let mut corners = [[0.0; 3]; 8];
+        for (i, corner) in corners.iter_mut().enumerate() {
+            let x = if i & 1 == 1 { right } else { left };
+            let y = if i & 2 == 2 { top } else { bottom };
+            let z = if i & 4 == 4 { far } else { near };
+            *corner = intersection(&x, &y, &z);
+        }
+        corners
+    }
+
+    /// Returns the twelve edges of the frustum as a list of lines, each pair of points is a line, ready to draw
+    /// gizmos of the cameras
+    pub fn to_line_list(&self) -> [Vec3; 24] {
+        let corners = self.corners();
+        let mut lines = [[0.0; 3]; 24];
+        for (i, (a, b)) in BOX_EDGES.into_iter().enumerate() {
+            lines[i * 2] = corners[a];
+            lines[i * 2 + 1] = corners[b];
+        }
+        lines
+    }
+
+    /// Writes the frustum as a Wavefront OBJ mesh, with the edges as lines, to inspect it in modeling tools. With
+    /// `plane_quads` the near and far planes are also written as faces.
+    pub fn write_obj<W: Write>(&self, writer: &mut W, plane_quads: bool) -> io::Result<()> {
+        writeln!(writer, "o frustum")?;
+        for [x, y, z] in self.corners() {
+            writeln!(writer, "v {x} {y} {z}")?;
+        }
+        // the indices of the OBJ format start at one
+        for (a, b) in BOX_EDGES {
+            writeln!(writer, "l {} {}", a + 1, b + 1)?;
+        }
+        if plane_quads {
+            writeln!(writer, "f 1 2 4 3")?;
+            writeln!(writer, "f 5 7 8 6")?;
+        }
+        Ok(())
+    }
+
+    /// Returns the world space frustum of the projection matrix with the given clips seen through the view matrix
+    pub(crate) fn from_matrix(view: &Matrix, projection: &Matrix, near: f32, far: f32) -> Self {
+        Self::from_ndc_rect([-1.0, 1.0, -1.0, 1.0], view, projection, near, far)
+    }
+
+    /// Returns the frustum covering the `[left, right, bottom, top]` rectangle of the normalized device coordinates
+    fn from_ndc_rect(rect: [f32; 4], view: &Matrix, projection: &Matrix, near: f32, far: f32) -> Self {
+        let [left, right, bottom, top] = rect;
+        let view_projection = math::multiply(view, projection);
+        let column = |matrix: &Matrix, j: usize| [matrix[0][j], matrix[1][j], matrix[2][j], matrix[3][j]];
+        let combine = |a: Vec4, b: Vec4, factor: f32| [0, 1, 2, 3].map(|i| a[i] + b[i] * factor);
+
+        let (x, y, w) = (column(&view_projection, 0), column(&view_projection, 1), column(&view_projection, 3));
+        // the near and far clips are taken from the view space depth
+        let depth = column(view, 2);
+        let unit_w = [0.0, 0.0, 0.0, 1.0];
+
+        Self {
+            planes: [
+                combine(x, w, -left),
+                combine(x, w, -right).map(|c| -c),
+                combine(y, w, -bottom),
+                combine(y, w, -top).map(|c| -c),
+                combine(depth, unit_w, -near),
+                combine(depth, unit_w, -far).map(|c| -c),
+            ]
+            .map(Plane::from_coefficients),
+        }
+    }
+}
+
+/// Returns the interval covered by the points projected onto the axis
+fn project_onto(points: &[Vec3], axis: Vec3) -> (f32, f32) {
+    points
+        .iter()
+        .map(|&point| math::dot(point, axis))
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), distance| (min.min(distance), max.max(distance)))
+}
+
+/// Returns the point shared by the three planes
+fn intersection(a: &Plane, b: &Plane, c: &Plane) -> Vec3 {
+    let (bc, ca) = (math::cross(b.normal, c.normal), math::cross(c.normal, a.normal));
+    let ab = math::cross(a.normal, b.normal);
+    let sum = math::add(math::scale(bc, a.distance), math::scale(ca, b.distance));
+    let sum = math::add(sum, math::scale(ab, c.distance));
+    math::scale(sum, -1.0 / math::dot(a.normal, bc))
+}
+
+#[test]
+fn frustum_containment() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_far(100.0).set_width(720);
+    let frustum = Frustum::new(&math::look_at([0.0; 3], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]), &builder);
+    assert!(frustum.contains_point([0.0, 0.0, 10.0]));
+    assert!(frustum.contains_point([9.9, -9.9, 10.0]));
+    assert!(!frustum.contains_point([10.1, 0.0, 10.0]));
+    assert!(!frustum.contains_point([0.0, 0.0, 0.5]));
+    assert!(!frustum.contains_point([0.0, 0.0, 100.5]));
+    assert!(!frustum.contains_point([0.0, 0.0, -10.0]));
+
+    assert!(frustum.intersects_sphere([11.0, 0.0, 10.0], 1.0));
+    assert!(!frustum.intersects_sphere([12.0, 0.0, 10.0], 1.0));
+    assert!(frustum.intersects_aabb(&Aabb::new([-20.0, -1.0, 5.0], [-5.5, 1.0, 6.0])));
+    assert!(!frustum.intersects_aabb(&Aabb::new([-20.0, -1.0, -6.0], [20.0, 1.0, -5.0])));
+}
+
+#[test]
+fn frustum_obb_culling() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_far(100.0).set_width(720);
+    let frustum = Frustum::new(&math::identity(), &builder);
+    // a long plank next to the right plane, only touching it when rotated
+    let (center, half_extents) = ([14.0, 0.0, 10.0], [5.0, 0.5, 0.5]);
+    assert!(!frustum.intersects_obb(center, half_extents, &Quaternion::from_axis_angle([0.0, 0.0, 1.0], 90.0)));
+    assert!(frustum.intersects_obb(center, half_extents, &Quaternion::identity()));
+    assert!(frustum.intersects_obb([0.0, 0.0, 50.0], [1.0; 3], &Quaternion::from_euler(30.0, 20.0, 10.0)));
+    assert!(!frustum.intersects_obb([0.0, 0.0, -5.0], [1.0; 3], &Quaternion::from_euler(30.0, 20.0, 10.0)));
+}
+
+#[test]
+fn frustum_from_screen_rect() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_far(100.0).set_width(720);
+    let view = math::look_at([0.0, 0.0, -10.0], [0.0; 3], [0.0, 1.0, 0.0]);
+    let viewport = Viewport::new(0, 0, 720, 720);
+    // top left quarter of the screen, with the edges in any order
+    let rect = ScreenRect::new(360.0, 360.0, 0.0, 0.0);
+    let frustum = Frustum::from_screen_rect(&rect, &viewport, &view, &builder);
+    assert!(frustum.contains_point([-5.0, 5.0, 0.0]));
+    assert!(!frustum.contains_point([5.0, 5.0, 0.0]));
+    assert!(!frustum.contains_point([-5.0, -5.0, 0.0]));
+    assert!(!frustum.contains_point([-11.0, 5.0, 0.0]));
+}
+
+#[test]
+fn frustum_corners() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_far(10.0).set_width(720);
+    let frustum = Frustum::new(&math::look_at([0.0, 0.0, -1.0], [0.0; 3], [0.0, 1.0, 0.0]), &builder);
+    let corners = frustum.corners();
+    let expected = [[-1.0, -1.0, 0.0], [1.0, 1.0, 0.0], [-10.0, 10.0, 9.0], [10.0, 10.0, 9.0]];
+    for (corner, expected) in [corners[0], corners[3], corners[6], corners[7]].iter().zip(expected) {
+        assert!((0..3).all(|i| (corner[i] - expected[i]).abs() < 0.001), "{corner:?} != {expected:?}");
+    }
+
+    let lines = frustum.to_line_list();
+    assert_eq!([corners[0], corners[1]], [lines[0], lines[1]]);
+    assert_eq!([corners[3], corners[7]], [lines[22], lines[23]]);
+}
+
+#[test]
+fn frustum_obj() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_far(10.0);
+    let frustum = Frustum::new(&math::identity(), &builder);
+    let mut obj = Vec::new();
+    frustum.write_obj(&mut obj, false).unwrap();
+    let obj = String::from_utf8(obj).unwrap();
+    assert_eq!(8, obj.lines().filter(|line| line.starts_with("v ")).count());
+    assert_eq!(12, obj.lines().filter(|line| line.starts_with("l ")).count());
+    assert!(obj.contains("\nl 1 2\n"));
+    assert!(!obj.contains("\nf "));
+
+    let mut obj = Vec::new();
+    frustum.write_obj(&mut obj, true).unwrap();
+    assert_eq!(2, String::from_utf8(obj).unwrap().lines().filter(|line| line.starts_with("f ")).count());
+}
+
+#[test]
+fn frustums_intersection() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_far(10.0);
+    let up = [0.0, 1.0, 0.0];
+    let camera = Frustum::new(&math::look_at([0.0; 3], [0.0, 0.0, 1.0], up), &builder);
+    let facing = Frustum::new(&math::look_at([0.0, 0.0, 15.0], [0.0; 3], up), &builder);
+    let crossing = Frustum::new(&math::look_at([10.0, 0.0, 5.0], [0.0, 0.0, 5.0], up), &builder);
+    let behind = Frustum::new(&math::look_at([0.0, 0.0, -1.0], [0.0, 0.0, -2.0], up), &builder);
+    let aside = Frustum::new(&math::look_at([30.0, 0.0, 0.0], [30.0, 0.0, 1.0], up), &builder);
+    assert!(camera.intersects(&facing));
+    assert!(camera.intersects(&crossing));
+    assert!(crossing.intersects(&camera));
+    assert!(!camera.intersects(&behind));
+    assert!(!camera.intersects(&aside));
+}
@@ -0,0 +1,83 @@
+//! Lines of infinite ground grids, clipped to the part seen by the camera like the grids of the editors
+
+use crate::bounds::BOX_EDGES;
+use crate::math::{self, Vec3};
+use crate::Frustum;
+
+impl Frustum {
+    /// Returns the segments of the grid lines, parallel to the x and z axes at multiples of the spacing on the
+    /// horizontal plane at the given height, that are inside the frustum. The number of lines grows with the area of
+    /// the plane seen up to the far clip divided by the spacing.
+    ///
+    /// # Panic
+    /// If the spacing is not positive
+    pub fn grid_lines(&self, spacing: f32, height: f32) -> Vec<(Vec3, Vec3)> {
+        if spacing <= 0.0 || spacing.is_nan() {
+            panic!("The grid spacing must be positive");
+        }
+        let corners = self.corners();
+        // the bounds of the plane within the frustum, from the edges crossing it
+        let (mut min, mut max) = ([f32::INFINITY; 2], [f32::NEG_INFINITY; 2]);
+        for (a, b) in BOX_EDGES {
+            let (a, b) = (corners[a], corners[b]);
+            if (a[1] - height) * (b[1] - height) > 0.0 || a[1] == b[1] {
+                continue;
+            }
+            let point = math::lerp(a, b, (height - a[1]) / (b[1] - a[1]));
+            for (i, axis) in [0, 2].into_iter().enumerate() {
+                min[i] = min[i].min(point[axis]);
+                max[i] = max[i].max(point[axis]);
+            }
+        }
+
+        let mut lines = Vec::new();
+        let multiples = |min: f32, max: f32| ((min / spacing).ceil() as i64)..=((max / spacing).floor() as i64);
+        for x in multiples(min[0], max[0]) {
+            let x = x as f32 * spacing;
+            lines.extend(self.clip_segment([x, height, min[1]], [x, height, max[1]]));
+        }
+        for z in multiples(min[1], max[1]) {
+            let z = z as f32 * spacing;
+            lines.extend(self.clip_segment([min[0], height, z], [max[0], height, z]));
+        }
+        lines
+    }
+
+    /// Returns the part of the segment inside the frustum, if any
+    fn clip_segment(&self, a: Vec3, b: Vec3) -> Option<(Vec3, Vec3)> {
+        let (mut start, mut end) = (0.0f32, 1.0f32);
+        for plane in &self.planes {
+            let (distance_a, distance_b) = (plane.signed_distance(a), plane.signed_distance(b));
+            if distance_a < 0.0 && distance_b < 0.0 {
+                return None;
+            }
+            let t = distance_a / (distance_a - distance_b);
+            if distance_a < 0.0 {
+                start = start.max(t);
+            } else if distance_b < 0.0 {
+                end = end.min(t);
+            }
+        }
+        (start < end).then(|| (math::lerp(a, b, start), math::lerp(a, b, end)))
+    }
+}
+
+#[test]
+fn ground_grid() {
+    let builder = crate::ProjectionMatrixBuilder::new().set_near(1.0).set_far(100.0).set_width(720);
+    let view = math::look_at([0.0, 10.0, 0.0], [0.0; 3], [0.0, 0.0, 1.0]);
+    let frustum = Frustum::new(&view, &builder);
+    // the square from -10 to 10 is seen on the ground
+    let lines = frustum.grid_lines(3.0, 0.0);
+    assert_eq!(14, lines.len());
+    for (a, b) in &lines {
+        assert!((math::length(math::sub(*b, *a)) - 20.0).abs() < 0.001);
+        let axis = if a[0] == b[0] { 0 } else { 2 };
+        assert_eq!(0.0, a[axis] % 3.0);
+    }
+    assert_eq!(Vec::<(Vec3, Vec3)>::new(), frustum.grid_lines(3.0, 20.0));
+    // the lines seen to the horizon stop at the far clip
+    let horizon = Frustum::new(&math::look_at([0.0, 2.0, 0.0], [0.0, 2.0, 1.0], [0.0, 1.0, 0.0]), &builder);
+    let lines = horizon.grid_lines(1.0, 0.0);
+    assert!(!lines.is_empty() && lines.iter().all(|(a, b)| a[2].max(b[2]) <= 100.001 && a[2].min(b[2]) >= 1.999));
+}
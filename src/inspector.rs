@@ -0,0 +1,154 @@
+//! Inspection and live edition of the projection parameters, describing them with their ranges so any UI toolkit can
+//! show them as sliders and rebuild the matrix on every change
+
+use crate::{ParamError, ProjectionMatrix, ProjectionMatrixBuilder, MAX_SUPPORTED_FOV, MIN_SUPPORTED_FOV};
+
+/// Biggest clip distance offered by the sliders of the inspector
+const MAX_CLIP: f32 = 10_000.0;
+/// Biggest screen size offered by the sliders of the inspector
+const MAX_SIZE: f32 = 7680.0;
+
+/// Editable parameter of the projection
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Parameter {
+    /// Field of view in grades
+    Fov,
+    /// Near clip position
+    Near,
+    /// Far clip position
+    Far,
+    /// Width of the screen
+    Width,
+    /// Height of the screen
+    Height,
+}
+
+impl Parameter {
+    /// All the parameters, in the order to display them
+    pub const ALL: [Parameter; 5] = [Self::Fov, Self::Near, Self::Far, Self::Width, Self::Height];
+
+    /// Returns the label of the parameter
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Fov => "fov",
+            Self::Near => "near",
+            Self::Far => "far",
+            Self::Width => "width",
+            Self::Height => "height",
+        }
+    }
+
+    /// Returns the minimum and maximum values to offer in a slider. Any value within them is valid as long as the
+    /// near clip stays in front of the far clip.
+    pub const fn range(&self) -> (f32, f32) {
+        match self {
+            Self::Fov => (MIN_SUPPORTED_FOV, MAX_SUPPORTED_FOV),
+            Self::Near | Self::Far => (0.0, MAX_CLIP),
+            Self::Width | Self::Height => (1.0, MAX_SIZE),
+        }
+    }
+
+    /// Returns if the parameter only takes whole values
+    pub const fn is_integer(&self) -> bool {
+        matches!(self, Self::Width | Self::Height)
+    }
+}
+
+impl ProjectionMatrixBuilder {
+    /// Returns the current value of the parameter
+    pub fn parameter(&self, parameter: Parameter) -> f32 {
+        match parameter {
+            Parameter::Fov => self.fov,
+            Parameter::Near => self.near,
+            Parameter::Far => self.far,
+            Parameter::Width => self.width as f32,
+            Parameter::Height => self.height as f32,
+        }
+    }
+
+    /// Sets the parameter, rounding the sizes to the closest integer, or returns the error of its fallible setter
+    pub fn try_set_parameter(self, parameter: Parameter, value: f32) -> Result<Self, ParamError> {
+        match parameter {
+            Parameter::Fov => self.try_set_fov(value),
+            Parameter::Near => self.try_set_near(value),
+            Parameter::Far => self.try_set_far(value),
+            Parameter::Width => self.try_set_width(value.round() as usize),
+            Parameter::Height => self.try_set_height(value.round() as usize),
+        }
+    }
+}
+
+/// Camera inspector of debug panels, keeping the parameters being edited along with the matrix built from them
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Inspector {
+    builder: ProjectionMatrixBuilder,
+    matrix: ProjectionMatrix,
+}
+
+impl Inspector {
+    /// Returns an inspector of the given parameters
+    ///
+    /// # Panic
+    /// Like [`ProjectionMatrixBuilder::build`]
+    pub fn new(builder: ProjectionMatrixBuilder) -> Self {
+        Self { builder, matrix: builder.build() }
+    }
+
+    /// Returns the parameters being edited
+    pub fn builder(&self) -> &ProjectionMatrixBuilder {
+        &self.builder
+    }
+
+    /// Returns the matrix of the current parameters
+    pub fn matrix(&self) -> &ProjectionMatrix {
+        &self.matrix
+    }
+
+    /// Returns the parameters with their current values, to display them
+    pub fn values(&self) -> [(Parameter, f32); 5] {
+        Parameter::ALL.map(|parameter| (parameter, self.builder.parameter(parameter)))
+    }
+
+    /// Changes a parameter and rebuilds the matrix, or returns the error leaving the parameters untouched so the
+    /// panel can show it next to the field
+    pub fn set(&mut self, parameter: Parameter, value: f32) -> Result<(), ParamError> {
+        self.builder = self.builder.try_set_parameter(parameter, value)?;
+        self.matrix = self.builder.build();
+        Ok(())
+    }
+}
+
+impl Default for Inspector {
+    fn default() -> Self {
+        Self::new(ProjectionMatrixBuilder::new())
+    }
+}
+
+#[test]
+fn parameters() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_far(100.0).set_width(1280).set_height(720);
+    assert_eq!(100.0, builder.parameter(Parameter::Far));
+    assert_eq!(1280.0, builder.parameter(Parameter::Width));
+    assert_eq!(builder.set_width(800), builder.try_set_parameter(Parameter::Width, 799.6).unwrap());
+    assert_eq!(builder.set_fov(60.0), builder.try_set_parameter(Parameter::Fov, 60.0).unwrap());
+    assert_eq!(Err(ParamError::Size { width: 0, height: 720 }), builder.try_set_parameter(Parameter::Width, -3.0));
+    assert!(Parameter::ALL.iter().all(|parameter| {
+        let (min, max) = parameter.range();
+        builder.try_set_parameter(*parameter, min).is_ok() || builder.try_set_parameter(*parameter, max).is_ok()
+    }));
+}
+
+#[test]
+fn live_edition() {
+    let mut inspector = Inspector::default();
+    inspector.set(Parameter::Fov, 60.0).unwrap();
+    inspector.set(Parameter::Width, 1280.0).unwrap();
+    assert_eq!(ProjectionMatrixBuilder::new().set_fov(60.0).set_width(1280).build(), *inspector.matrix());
+    assert_eq!((Parameter::Fov, 60.0), inspector.values()[0]);
+
+    let before = inspector;
+    assert_eq!(Err(ParamError::Fov(180.0)), inspector.set(Parameter::Fov, 180.0));
+    assert_eq!(before, inspector);
+    let far = inspector.builder().parameter(Parameter::Far);
+    assert_eq!(Err(ParamError::ClipOrder { near: far, far }), inspector.set(Parameter::Near, far));
+}
@@ -0,0 +1,218 @@
+//! Rays and their intersections with the scene geometry, to pick what is under the cursor
+
+use crate::math::{self, Matrix, Vec3};
+use crate::screen::screen_to_ndc;
+use crate::{Aabb, Plane, ProjectionMatrixBuilder, Viewport};
+
+/// Smallest squared sine of the angle between the cursor rays and the axes dragged by
+/// [`ProjectionMatrixBuilder::drag_along_axis`], about a twentieth of a grade, as the rays almost parallel to the
+/// axis make the drag jump
+const MIN_AXIS_ANGLE_SIN_SQUARED: f32 = 0.000001;
+
+/// Half-line starting at an origin and extending along a direction
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    /// Starting point of the ray
+    pub origin: Vec3,
+    /// Unit vector with the direction of the ray
+    pub direction: Vec3,
+}
+
+impl Ray {
+    /// Returns the ray from the origin along the direction, normalizing the direction
+    pub fn new(origin: Vec3, direction: Vec3) -> Self {
+        Self { origin, direction: math::normalize(direction) }
+    }
+
+    /// Returns the point of the ray at the given distance of the origin
+    pub fn at(&self, distance: f32) -> Vec3 {
+        math::add(self.origin, math::scale(self.direction, distance))
+    }
+
+    /// Returns the distance from the origin to the closest intersection with the box, or `None` if the ray misses
+    /// it. When the origin is inside the box the distance is zero.
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        // slab test, intersecting the intervals where the ray is between the planes of each axis
+        let (mut enter, mut exit) = (0.0f32, f32::INFINITY);
+        for axis in 0..3 {
+            let inverse = 1.0 / self.direction[axis];
+            let t0 = (aabb.min[axis] - self.origin[axis]) * inverse;
+            let t1 = (aabb.max[axis] - self.origin[axis]) * inverse;
+            // parallel rays produce NaN when the origin is on the slab plane, min and max discard it
+            enter = enter.max(t0.min(t1));
+            exit = exit.min(t0.max(t1));
+        }
+        (enter <= exit).then_some(enter)
+    }
+
+    /// Returns the distance from the origin to the intersection with the triangle, or `None` if the ray misses it.
+    /// Both faces of the triangle are hit.
+    pub fn intersect_triangle(&self, a: Vec3, b: Vec3, c: Vec3) -> Option<f32> {
+        // Möller–Trumbore algorithm
+        let (edge_ab, edge_ac) = (math::sub(b, a), math::sub(c, a));
+        let p = math::cross(self.direction, edge_ac);
+        let determinant = math::dot(edge_ab, p);
+        if determinant.abs() < f32::EPSILON {
+            return None;
+        }
+        let inverse = 1.0 / determinant;
+        let offset = math::sub(self.origin, a);
+        let u = math::dot(offset, p) * inverse;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = math::cross(offset, edge_ab);
+        let v = math::dot(self.direction, q) * inverse;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let distance = math::dot(edge_ac, q) * inverse;
+        (distance >= 0.0).then_some(distance)
+    }
+
+    /// Returns the distance from the origin to the intersection with the plane, or `None` if the ray is parallel to
+    /// it or points away from it. Both sides of the plane are hit.
+    pub fn intersect_plane(&self, plane: &Plane) -> Option<f32> {
+        let speed = math::dot(plane.normal, self.direction);
+        if speed.abs() < f32::EPSILON {
+            return None;
+        }
+        let distance = -plane.signed_distance(self.origin) / speed;
+        (distance >= 0.0).then_some(distance)
+    }
+}
+
+impl ProjectionMatrixBuilder {
+    /// Returns the world space ray going from the camera through the given pixel of the viewport, or `None` if the view
+    /// matrix can't be inverted
+    pub fn screen_ray(&self, x: f32, y: f32, view: &Matrix, viewport: &Viewport) -> Option<Ray> {
+        let matrix = self.build();
+        let (ndc_x, ndc_y) = screen_to_ndc(x, y, viewport);
+        // view space direction reaching the pixel at a depth of one
+        let direction = [(ndc_x - matrix[2][0]) / matrix[0][0], (ndc_y - matrix[2][1]) / matrix[1][1], 1.0, 0.0];
+
+        let camera = math::inverse(view)?;
+        let [x, y, z, _] = math::transform(direction, &camera);
+        Some(Ray::new([camera[3][0], camera[3][1], camera[3][2]], [x, y, z]))
+    }
+
+    /// Returns the world space point of the plane under the given pixel of the viewport, like the point of the ground
+    /// under the cursor, or `None` if the ray through the pixel misses the plane or the view matrix can't be inverted
+    pub fn pick_on_plane(&self, x: f32, y: f32, view: &Matrix, viewport: &Viewport, plane: &Plane) -> Option<Vec3> {
+        let ray = self.screen_ray(x, y, view, viewport)?;
+        Some(ray.at(ray.intersect_plane(plane)?))
+    }
+
+    /// Returns the world space translation on the plane following the cursor dragged between the given pixels of the
+    /// viewport, for the gizmos moving the objects over a plane. It's `None` if any of the pixels misses the plane.
+    pub fn drag_on_plane(&self, from: (f32, f32), to: (f32, f32), view: &Matrix, viewport: &Viewport, plane: &Plane)
+        -> Option<Vec3> {
+        let start = self.pick_on_plane(from.0, from.1, view, viewport, plane)?;
+        let end = self.pick_on_plane(to.0, to.1, view, viewport, plane)?;
+        Some(math::sub(end, start))
+    }
+
+    /// Returns the world space translation along the axis through the origin following the cursor dragged between
+    /// the given pixels of the viewport, for the gizmos moving the objects along an axis. The cursor is followed by
+    /// the points of the axis closest to its rays. It's `None` if the axis is parallel to the rays.
+    pub fn drag_along_axis(&self, from: (f32, f32), to: (f32, f32), view: &Matrix, viewport: &Viewport, origin: Vec3,
+                           axis: Vec3) -> Option<Vec3> {
+        let axis = math::normalize(axis);
+        let closest = |(x, y): (f32, f32)| {
+            let ray = self.screen_ray(x, y, view, viewport)?;
+            let alignment = math::dot(axis, ray.direction);
+            let denominator = 1.0 - alignment * alignment;
+            if denominator < MIN_AXIS_ANGLE_SIN_SQUARED {
+                return None;
+            }
+            let offset = math::sub(origin, ray.origin);
+            Some((alignment * math::dot(ray.direction, offset) - math::dot(axis, offset)) / denominator)
+        };
+        Some(math::scale(axis, closest(to)? - closest(from)?))
+    }
+}
+
+#[test]
+fn ray_aabb() {
+    let aabb = Aabb::new([-1.0; 3], [1.0; 3]);
+    assert_eq!(Some(4.0), Ray::new([0.0, 0.0, -5.0], [0.0, 0.0, 1.0]).intersect_aabb(&aabb));
+    assert_eq!(Some(0.0), Ray::new([0.5, 0.0, 0.0], [1.0, 0.0, 0.0]).intersect_aabb(&aabb));
+    assert_eq!(None, Ray::new([0.0, 0.0, -5.0], [0.0, 0.0, -1.0]).intersect_aabb(&aabb));
+    assert_eq!(None, Ray::new([0.0, 2.0, -5.0], [0.0, 0.0, 1.0]).intersect_aabb(&aabb));
+    let distance = Ray::new([-5.0, -5.0, 0.0], [1.0, 1.0, 0.0]).intersect_aabb(&aabb).unwrap();
+    assert!((4.0 * 2.0f32.sqrt() - distance).abs() < 0.0001);
+}
+
+#[test]
+fn ray_triangle() {
+    let (a, b, c) = ([0.0, 0.0, 5.0], [2.0, 0.0, 5.0], [0.0, 2.0, 5.0]);
+    assert_eq!(Some(5.0), Ray::new([0.5, 0.5, 0.0], [0.0, 0.0, 1.0]).intersect_triangle(a, b, c));
+    assert_eq!(Some(5.0), Ray::new([0.5, 0.5, 10.0], [0.0, 0.0, -1.0]).intersect_triangle(a, b, c));
+    assert_eq!(None, Ray::new([1.5, 1.5, 0.0], [0.0, 0.0, 1.0]).intersect_triangle(a, b, c));
+    assert_eq!(None, Ray::new([0.5, 0.5, 0.0], [0.0, 0.0, -1.0]).intersect_triangle(a, b, c));
+    assert_eq!(None, Ray::new([0.5, 0.5, 0.0], [1.0, 0.0, 0.0]).intersect_triangle(a, b, c));
+}
+
+#[test]
+fn ray_plane() {
+    let plane = Plane::from_point_normal([0.0, 2.0, 0.0], [0.0, 1.0, 0.0]);
+    assert_eq!(Some(3.0), Ray::new([1.0, 5.0, 1.0], [0.0, -1.0, 0.0]).intersect_plane(&plane));
+    assert_eq!(Some(2.0), Ray::new([1.0, 0.0, 1.0], [0.0, 1.0, 0.0]).intersect_plane(&plane));
+    assert_eq!(None, Ray::new([1.0, 5.0, 1.0], [0.0, 1.0, 0.0]).intersect_plane(&plane));
+    assert_eq!(None, Ray::new([1.0, 5.0, 1.0], [1.0, 0.0, 0.0]).intersect_plane(&plane));
+}
+
+#[test]
+fn screen_ray() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0);
+    let view = math::look_at([0.0, 0.0, -10.0], [0.0; 3], [0.0, 1.0, 0.0]);
+    let viewport = Viewport::new(0, 0, 1280, 720);
+    let point = [2.0, -3.0, 5.0];
+    let (x, y, _) = builder.world_to_screen(point, &view, &viewport).unwrap();
+    let ray = builder.screen_ray(x, y, &view, &viewport).unwrap();
+    assert_eq!([0.0, 0.0, -10.0], ray.origin);
+    let hit = ray.at(math::length(math::sub(point, ray.origin)));
+    assert!((0..3).all(|i| (point[i] - hit[i]).abs() < 0.001), "{hit:?}");
+}
+
+#[test]
+fn pick_on_ground() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0);
+    let view = math::look_at([0.0, 10.0, -10.0], [0.0; 3], [0.0, 1.0, 0.0]);
+    let viewport = Viewport::new(0, 0, 1280, 720);
+    let ground = Plane::from_point_normal([0.0; 3], [0.0, 1.0, 0.0]);
+    let point = [3.0, 0.0, 4.0];
+    let (x, y, _) = builder.world_to_screen(point, &view, &viewport).unwrap();
+    let hit = builder.pick_on_plane(x, y, &view, &viewport, &ground).unwrap();
+    assert!((0..3).all(|i| (point[i] - hit[i]).abs() < 0.001), "{hit:?}");
+    // the top of the screen looks above the horizon
+    assert_eq!(None, builder.pick_on_plane(640.0, 0.0, &view, &viewport, &ground));
+}
+
+#[test]
+fn drag_gizmos() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0);
+    let view = math::look_at([0.0, 10.0, -10.0], [0.0; 3], [0.0, 1.0, 0.0]);
+    let viewport = Viewport::new(0, 0, 1280, 720);
+    let screen = |point: Vec3| {
+        let (x, y, _) = builder.world_to_screen(point, &view, &viewport).unwrap();
+        (x, y)
+    };
+    let assert_near = |expected: Vec3, actual: Vec3| {
+        assert!((0..3).all(|i| (expected[i] - actual[i]).abs() < 0.001), "{actual:?}");
+    };
+    let ground = Plane::from_point_normal([0.0; 3], [0.0, 1.0, 0.0]);
+    let (from, to) = (screen([1.0, 0.0, 1.0]), screen([4.0, 0.0, -1.0]));
+    assert_near([3.0, 0.0, -2.0], builder.drag_on_plane(from, to, &view, &viewport, &ground).unwrap());
+    assert_eq!(None, builder.drag_on_plane(from, (640.0, 0.0), &view, &viewport, &ground));
+
+    // the cursor dragged over the axis and off it
+    let (from, to) = (screen([2.0, 1.0, 0.0]), screen([5.0, 1.0, 0.0]));
+    let translation = builder.drag_along_axis(from, to, &view, &viewport, [0.0, 1.0, 0.0], [2.0, 0.0, 0.0]);
+    assert_near([3.0, 0.0, 0.0], translation.unwrap());
+    let translation = builder.drag_along_axis(from, (to.0, to.1 + 50.0), &view, &viewport, [0.0, 1.0, 0.0],
+                                              [1.0, 0.0, 0.0]);
+    assert_eq!(0.0, translation.unwrap()[1]);
+    let center = screen([0.0; 3]);
+    assert_eq!(None, builder.drag_along_axis(center, to, &view, &viewport, [0.0; 3], [0.0, -1.0, 1.0]));
+}
@@ -0,0 +1,183 @@
+//! Sub-pixel offsets of the projection, to alternate the sample grid between frames
+//!
+//! The offsets are in pixels of the render resolution of the builder, with the x-axis to the right and the y-axis
+//! down like the screen positions.
+
+use crate::{ProjectionMatrix, ProjectionMatrixBuilder};
+
+/// Plastic number, the ratio of the R2 sequence
+const PLASTIC_NUMBER: f64 = 1.324_717_957_244_746;
+
+/// Sequence of sub-pixel offsets applied to the projection of consecutive frames for temporal upscalers and
+/// anti-aliasing, cycling over a fixed number of offsets within half a pixel around the pixel centers
+#[derive(Clone, Debug, PartialEq)]
+pub struct JitterSequence {
+    offsets: Vec<(f32, f32)>,
+}
+
+impl JitterSequence {
+    /// Returns the sequence of the given length of the Halton sequence of bases 2 and 3
+    ///
+    /// # Panic
+    /// If the length is zero
+    pub fn halton(length: usize) -> Self {
+        Self::custom((1..=length).map(|i| (radical_inverse(i, 2) - 0.5, radical_inverse(i, 3) - 0.5)).collect())
+    }
+
+    /// Returns the sequence of the given length of the R2 sequence, the additive recurrence of the plastic number,
+    /// which covers the pixel more evenly than Halton for any length
+    ///
+    /// # Panic
+    /// If the length is zero
+    pub fn r2(length: usize) -> Self {
+        let (a1, a2) = (1.0 / PLASTIC_NUMBER, 1.0 / (PLASTIC_NUMBER * PLASTIC_NUMBER));
+        let r2 = |i: usize, a: f64| ((0.5 + a * i as f64).fract() - 0.5) as f32;
+        Self::custom((1..=length).map(|i| (r2(i, a1), r2(i, a2))).collect())
+    }
+
+    /// Returns the sequence cycling over the given offsets, in pixels
+    ///
+    /// # Panic
+    /// If there are no offsets
+    pub fn custom(offsets: Vec<(f32, f32)>) -> Self {
+        if offsets.is_empty() {
+            panic!("The jitter sequence must have at least one offset");
+        }
+        Self { offsets }
+    }
+
+    /// Returns the number of offsets before the sequence repeats
+    pub fn period(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// Returns the offset of the frame, in pixels
+    pub fn offset(&self, frame: u64) -> (f32, f32) {
+        self.offsets[(frame % self.offsets.len() as u64) as usize]
+    }
+
+    /// Returns the offset of the frame in normalized device coordinates with the render resolution of the builder,
+    /// to remove the jitter from the motion vectors
+    pub fn ndc_offset(&self, frame: u64, builder: &ProjectionMatrixBuilder) -> (f32, f32) {
+        let (x, y) = self.offset(frame);
+        let (width, height) = builder.render_resolution();
+        (2.0 * x / width as f32, -2.0 * y / height as f32)
+    }
+
+    /// Builds the projection matrix of the builder jittered by the offset of the frame
+    ///
+    /// # Panic
+    /// Like [`ProjectionMatrixBuilder::build`]
+    pub fn apply(&self, frame: u64, builder: &ProjectionMatrixBuilder) -> ProjectionMatrix {
+        let (x, y) = self.offset(frame);
+        builder.build_with_pixel_offset(x, y)
+    }
+}
+
+/// Returns the digits of the index in the base mirrored around the radix point, in the `[0, 1)` range
+fn radical_inverse(mut index: usize, base: usize) -> f32 {
+    let (mut result, mut fraction) = (0.0, 1.0 / base as f32);
+    while index > 0 {
+        result += (index % base) as f32 * fraction;
+        index /= base;
+        fraction /= base as f32;
+    }
+    result
+}
+
+/// Returns the offset of the sample grid of checkerboard rendering for the frame. The even frames are rendered at
+/// the pixel centers and the odd ones at their corners, half a pixel to the right and down, so two consecutive frames
+/// sample the frame in a checkerboard pattern.
+pub fn checkerboard_offset(frame: u64) -> (f32, f32) {
+    if frame.is_multiple_of(2) { (0.0, 0.0) } else { (0.5, 0.5) }
+}
+
+impl ProjectionMatrixBuilder {
+    /// Builds the projection matrix with the view shifted by the given offset, in pixels of the render resolution
+    ///
+    /// # Panic
+    /// Like [`ProjectionMatrixBuilder::build`]
+    pub fn build_with_pixel_offset(&self, x: f32, y: f32) -> ProjectionMatrix {
+        let (width, height) = self.render_resolution();
+        let mut matrix = self.build().matrix();
+        let (x, y) = (x / width as f32, y / height as f32);
+        // the offsets are scaled by w in clip space, the view depth copied by the third row
+        matrix[2][0] += 2.0 * x;
+        matrix[2][1] -= 2.0 * y;
+        // the insets shifting the view by the same offsets, keeping its scale
+        let [left, right, top, bottom] = self.safe_area;
+        let safe_area = [left + x, right - x, top + y, bottom - y];
+        ProjectionMatrix::new(matrix, self.fov, self.near, self.far, self.aspect_ratio(), safe_area, self.depth_mode)
+    }
+
+    /// Builds the projection matrix of the frame of checkerboard rendering, shifted by the
+    /// [`checkerboard_offset`] of the frame
+    ///
+    /// # Panic
+    /// Like [`ProjectionMatrixBuilder::build`]
+    pub fn build_checkerboard(&self, frame: u64) -> ProjectionMatrix {
+        let (x, y) = checkerboard_offset(frame);
+        self.build_with_pixel_offset(x, y)
+    }
+}
+
+#[test]
+fn pixel_offset() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_width(800).set_height(400).set_render_scale(0.5);
+    let viewport = crate::Viewport::new(0, 0, 400, 200);
+    let view = crate::math::identity();
+    let projection = builder.build_with_pixel_offset(1.0, 2.0);
+    let clip = crate::math::transform_point([0.0, 0.0, 10.0], &projection);
+    let (x, y) = crate::clip::clip_to_ndc(clip).unwrap().to_screen(&viewport);
+    let (center_x, center_y, _) = builder.world_to_screen([0.0, 0.0, 10.0], &view, &viewport).unwrap();
+    assert!((center_x + 1.0 - x).abs() < 0.0001 && (center_y + 2.0 - y).abs() < 0.0001);
+    assert_eq!(builder.build(), builder.build_with_pixel_offset(0.0, 0.0));
+    assert_eq!(builder.fov, projection.fov());
+}
+
+#[test]
+fn checkerboard() {
+    let builder = ProjectionMatrixBuilder::new().set_width(200).set_height(100);
+    assert_eq!((0.0, 0.0), checkerboard_offset(4));
+    assert_eq!((0.5, 0.5), checkerboard_offset(7));
+    assert_eq!(builder.build(), builder.build_checkerboard(0));
+    let odd = builder.build_checkerboard(1);
+    assert_eq!((0.005, -0.01), (odd[2][0], odd[2][1]));
+    assert_eq!(builder.build_checkerboard(1), builder.build_checkerboard(3));
+}
+
+#[test]
+fn jitter_sequences() {
+    let halton = JitterSequence::halton(8);
+    assert_eq!(8, halton.period());
+    assert_eq!((0.0, 1.0 / 3.0 - 0.5), halton.offset(0));
+    assert_eq!((-0.25, 2.0 / 3.0 - 0.5), halton.offset(1));
+    assert_eq!(halton.offset(2), halton.offset(10));
+
+    let r2 = JitterSequence::r2(16);
+    for sequence in [&halton, &r2] {
+        assert!((0..16).all(|frame| {
+            let (x, y) = sequence.offset(frame);
+            (-0.5..0.5).contains(&x) && (-0.5..0.5).contains(&y)
+        }));
+    }
+    // the offsets are all different
+    assert!((0..16).all(|i| (0..i).all(|j| r2.offset(i) != r2.offset(j))));
+}
+
+#[test]
+fn jitter_projection() {
+    let builder = ProjectionMatrixBuilder::new().set_width(200).set_height(100);
+    let sequence = JitterSequence::custom(vec![(0.5, -0.25), (0.0, 0.0)]);
+    assert_eq!((0.005, 0.005), sequence.ndc_offset(0, &builder));
+    let jittered = sequence.apply(2, &builder);
+    assert_eq!(builder.build_with_pixel_offset(0.5, -0.25), jittered);
+    assert_eq!((0.005, 0.005), (jittered[2][0], jittered[2][1]));
+    assert_eq!(builder.build(), sequence.apply(1, &builder));
+}
+
+#[test]
+#[should_panic]
+fn empty_jitter_sequence() {
+    JitterSequence::custom(Vec::new());
+}
@@ -3,11 +3,16 @@
 //! 
 //! ## Building the matrix
 //! To build the matrix just create a new Builder and set the properties that will define the projection matrix:
-//! * `near`: Position of the near clip in the z-axis. By default `0.0`.
+//! * `near`: Position of the near clip in the z-axis. By default `0.1`.
+//! * `near_policy`: Handling of the near clips below a minimum, see `NearPolicy`. By default clamped to `0.001`.
 //! * `far`: Position of the far clip in the z-axis. By default `1000.0`.
 //! * `fov`: Field of view in degrees. By default `90.0`.
+//! * `depth_mode`: Encoding of the depth values. By default `Standard`.
 //! * `width`: Frame width. By default `1280`.
 //! * `height`: Frame height. By default `720`.
+//! * `scale_factor`: Ratio between physical and logical pixels of the screen. By default `1.0`.
+//! * `render_scale`: Scale of the internal render resolution over the frame size. By default `1.0`.
+//! * `safe_area`: Insets of the frame where the view is kept out, as fractions of the frame size. By default none.
 //! 
 //! ```
 //! let matrix = ferrux_projection_matrix::ProjectionMatrixBuilder::new()
@@ -19,24 +24,125 @@
 //!                .build();
 //! println!("{matrix:?}");
 //! ```
+//!
+//! ## Features
+//! * `inspector`: Description and live edition of the parameters, to build camera inspectors in debug panels.
+//! * `npy`: Export of the matrices as NumPy `.npy` files.
+//! * `sampling`: Generation of random valid projections for property tests.
+//! * `terminal`: Wireframe rendering into text with Braille characters, to draw the projections in a terminal.
 
 use std::f32::consts::PI;
 
-const DEFAULT_NEAR: f32 = 0.0;
+pub mod architectural;
+pub mod billboard;
+pub mod clip;
+pub mod distortion;
+pub mod fov;
+#[cfg(feature = "inspector")]
+pub mod inspector;
+pub mod jitter;
+pub mod math;
+#[cfg(feature = "npy")]
+pub mod npy;
+pub mod raster;
+pub mod relative;
+#[cfg(feature = "sampling")]
+pub mod sampling;
+#[cfg(feature = "terminal")]
+pub mod terminal;
+pub mod vision;
+
+mod bounds;
+mod cad;
+mod camera;
+mod culling;
+mod depth;
+mod effects;
+mod fixed;
+mod frustum;
+mod grid;
+mod intersect;
+mod map;
+mod marker;
+mod matrix;
+mod path;
+mod portal;
+mod projection;
+mod projector;
+mod quat;
+mod screen;
+mod sensor;
+mod shadow;
+mod shared;
+mod skybox;
+mod stack;
+mod timestep;
+mod validation;
+mod viewport;
+
+pub use bounds::Aabb;
+pub use cad::ViewPreset;
+pub use camera::{Camera, FpsCamera, OrbitCamera};
+pub use culling::{Bvh, Visibility};
+pub use depth::{DepthMode, DepthPrecisionReport};
+pub use effects::{Easing, FovAnimator, FovPunch, ScreenShake};
+pub use fixed::FixedProjectionBuilder;
+pub use frustum::{Frustum, Plane};
+pub use intersect::Ray;
+pub use jitter::JitterSequence;
+pub use map::MapProjectionBuilder;
+pub use marker::ScreenMarker;
+pub use matrix::{CellDiff, MatrixDiff, ParameterDiff, ProjectionMatrix};
+pub use path::{CameraPath, Interpolation, Keyframe};
+pub use projection::{Orthographic, Projection, ProjectionKind};
+pub use projector::{Projector, ProjectorArrayBuilder};
+pub use quat::Quaternion;
+pub use raster::{DepthImage, Triangle2D};
+pub use relative::RelativeCamera;
+pub use screen::ScreenRect;
+pub use sensor::SensorFrustum;
+pub use shadow::ShadowBias;
+pub use shared::{CameraSnapshot, SharedCamera};
+pub use stack::MatrixStack;
+pub use timestep::{CameraState, InterpolatedCamera};
+pub use validation::{
+    NearPolicy, ParamError, Warning, MAX_DEPTH_RATIO, MAX_FOV, MAX_SUPPORTED_FOV, MIN_NEAR, MIN_SUPPORTED_FOV,
+};
+pub use viewport::{FitMode, FoveatedRegion, SplitLayout, Viewport};
+
+const DEFAULT_NEAR: f32 = 0.1;
+const DEFAULT_NEAR_POLICY: NearPolicy = NearPolicy::Clamp(MIN_NEAR);
 const DEFAULT_FAR: f32 = 1000.0;
 const DEFAULT_FIELD_OF_VIEW: f32 = 90.0;
+/// Cotangent of half the default field of view
+const DEFAULT_FOV_SCALE: f32 = 1.0;
+const DEFAULT_DEPTH_MODE: DepthMode = DepthMode::Standard;
 const DEFAULT_WIDTH: usize = 1280;
 const DEFAULT_HEIGHT: usize = 720;
+const DEFAULT_SCALE_FACTOR: f32 = 1.0;
+const DEFAULT_RENDER_SCALE: f32 = 1.0;
+const DEFAULT_SAFE_AREA: [f32; 4] = [0.0; 4];
+/// Most iterations of the search of the far clip producing the standard depth terms
+const DEPTH_CLIPS_ITERATIONS: usize = 64;
 
-type Matrix = [[f32; 4]; 4];
+use math::Matrix;
 
 /// Builder to construct projection matrices
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct ProjectionMatrixBuilder {
     near: f32,
+    near_policy: NearPolicy,
     far: f32,
     fov: f32,
+    /// Cotangent of half the fov, cached to skip the trigonometry when the projections are rebuilt every frame
+    fov_scale: f32,
+    depth_mode: DepthMode,
     width: usize,
     height: usize,
+    scale_factor: f32,
+    render_scale: f32,
+    /// Left, right, top and bottom insets
+    safe_area: [f32; 4],
 }
 
 impl ProjectionMatrixBuilder {
@@ -44,30 +150,54 @@ impl ProjectionMatrixBuilder {
     ///
     /// # Default
     /// By default, the matrix builder sets the following defaults:
-    /// * `screen_position`: 0.0
+    /// * `screen_position`: 0.1
+    /// * `near_policy`: Clamp to 0.001
     /// * `view_limit`: 1000.0
     /// * `fov`: 90.0
+    /// * `depth_mode`: Standard
     /// * `width`: 1280
     /// * `height`: 720
+    /// * `scale_factor`: 1.0
+    /// * `render_scale`: 1.0
+    /// * `safe_area`: 0.0 on every side
     ///
-    pub fn new() -> Self {
+    pub const fn new() -> Self {
         Self {
             near: DEFAULT_NEAR,
+            near_policy: DEFAULT_NEAR_POLICY,
             far: DEFAULT_FAR,
             fov: DEFAULT_FIELD_OF_VIEW,
+            fov_scale: DEFAULT_FOV_SCALE,
+            depth_mode: DEFAULT_DEPTH_MODE,
             width: DEFAULT_WIDTH,
             height: DEFAULT_HEIGHT,
+            scale_factor: DEFAULT_SCALE_FACTOR,
+            render_scale: DEFAULT_RENDER_SCALE,
+            safe_area: DEFAULT_SAFE_AREA,
         }
     }
 
-    /// Sets the near clip position in the z axis
-    pub fn set_near(mut self, near: f32) -> Self {
-        self.near = near;
+    /// Sets the near clip position in the z axis, subject to the near policy of the builder
+    ///
+    /// # Panic
+    /// If the near policy is [`NearPolicy::Error`] and the entered near clip is below its minimum
+    pub const fn set_near(mut self, near: f32) -> Self {
+        self.near = self.near_policy.apply(near);
         self
     }
 
+    /// Sets the handling of the near clips below a minimum, applying it to the current near clip too. See
+    /// [`NearPolicy`].
+    ///
+    /// # Panic
+    /// If the entered policy is [`NearPolicy::Error`] and the current near clip is below its minimum
+    pub const fn set_near_policy(mut self, near_policy: NearPolicy) -> Self {
+        self.near_policy = near_policy;
+        self.set_near(self.near)
+    }
+
     /// Sets the far clip position in the z axis
-    pub fn set_far(mut self, far: f32) -> Self {
+    pub const fn set_far(mut self, far: f32) -> Self {
         self.far = far;
         self
     }
@@ -75,50 +205,299 @@ impl ProjectionMatrixBuilder {
     /// Sets the field of view in grades
     ///
     /// # Panic
-    /// If the entered fov is not within the (0, 360) range
-    pub fn set_fov(mut self, fov: f32) -> Self {
-        if !(0.0..360.0).contains(&fov) {
-            panic!("The field of view must be a positive value between 0.0 and 360.0");
-        }
-        self.fov = fov;
+    /// If the entered fov is not within the supported [0.1, 179] range
+    pub fn set_fov(self, fov: f32) -> Self {
+        self.try_set_fov(fov).unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Sets the field of view matching the one of a lens with the given focal length over a sensor of the given
+    /// height, both in the same units. Check the [`fov`] module for other conversions.
+    ///
+    /// # Panic
+    /// If the resulting fov is not within the supported [0.1, 179] range
+    pub fn set_focal_length(self, focal_length: f32, sensor_height: f32) -> Self {
+        self.set_fov(fov::focal_length_to_fov(focal_length, sensor_height))
+    }
+
+    /// Sets the encoding of the depth values, see [`DepthMode`]
+    pub const fn set_depth_mode(mut self, depth_mode: DepthMode) -> Self {
+        self.depth_mode = depth_mode;
         self
     }
 
     /// Sets the width of the screen
-    pub fn set_width(mut self, width: usize) -> Self {
+    pub const fn set_width(mut self, width: usize) -> Self {
         self.width = width;
         self
     }
 
     /// Sets the height of the screen
-    pub fn set_height(mut self, height: usize) -> Self {
+    pub const fn set_height(mut self, height: usize) -> Self {
         self.height = height;
         self
     }
 
-    /// Builds the projection matrix derived from the entered parameters and consumes the builder
+    /// Sets the size of the screen in logical pixels along with the scale factor to convert them into physical ones,
+    /// as reported by the OS for high-DPI screens. The width and height of the builder are the physical ones.
+    ///
+    /// # Panic
+    /// If the entered scale factor is not positive, or the width or the height is not positive and finite or rounds
+    /// to zero physical pixels
+    pub fn set_logical_size(self, width: f32, height: f32, scale_factor: f32) -> Self {
+        if !width.is_finite() || !height.is_finite() || width <= 0.0 || height <= 0.0 {
+            panic!("The logical size must be positive and finite");
+        }
+        self.set_scale_factor(scale_factor)
+            .try_set_width((width * scale_factor).round() as usize)
+            .and_then(|builder| builder.try_set_height((height * scale_factor).round() as usize))
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Sets the ratio between physical and logical pixels keeping the logical size of the screen
+    ///
+    /// # Panic
+    /// If the entered scale factor is not positive
+    pub fn set_scale_factor(self, scale_factor: f32) -> Self {
+        self.try_set_scale_factor(scale_factor).unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Sets the size of the screen from the size of the window in logical pixels and the size of its drawable area in
+    /// physical ones, like the ones given by `size` and `drawable_size` of the SDL windows, deriving the scale factor
+    /// from both
+    ///
+    /// # Panic
+    /// If any of the sizes is zero
+    pub fn set_drawable_size(self, window_width: usize, window_height: usize, drawable_width: usize,
+                             drawable_height: usize) -> Self {
+        if window_width == 0 || window_height == 0 || drawable_width == 0 || drawable_height == 0 {
+            panic!("The window and drawable sizes must be positive");
+        }
+        let mut builder = self.set_scale_factor(drawable_width as f32 / window_width as f32);
+        builder.width = drawable_width;
+        builder.height = drawable_height;
+        builder
+    }
+
+    /// Returns the size of the screen in logical pixels
+    pub fn logical_size(&self) -> (f32, f32) {
+        (self.width as f32 / self.scale_factor, self.height as f32 / self.scale_factor)
+    }
+
+    /// Returns the size of the screen in physical pixels
+    pub fn physical_size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Converts a position in logical pixels into physical ones
+    pub fn to_physical(&self, x: f32, y: f32) -> (f32, f32) {
+        (x * self.scale_factor, y * self.scale_factor)
+    }
+
+    /// Converts a position in physical pixels into logical ones
+    pub fn to_logical(&self, x: f32, y: f32) -> (f32, f32) {
+        (x / self.scale_factor, y / self.scale_factor)
+    }
+
+    /// Sets the scale of the internal render resolution relative to the screen size, below `1.0` for dynamic
+    /// resolution and above it for supersampling. The aspect ratio is always the one of the screen.
+    ///
+    /// # Panic
+    /// If the entered scale is not positive
+    pub fn set_render_scale(self, render_scale: f32) -> Self {
+        self.try_set_render_scale(render_scale).unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Returns the internal render resolution, the screen size scaled by the render scale
+    pub fn render_resolution(&self) -> (usize, usize) {
+        let scale = |size: usize| ((size as f32 * self.render_scale).round() as usize).max(1);
+        (scale(self.width), scale(self.height))
+    }
+
+    /// Sets the percentage of the frame cropped by the screen overscan. The view is shrunk to fit in the safe area
+    /// left by the overscan, a negative percentage expands it instead.
+    ///
+    /// # Panic
+    /// If the entered percentage is not within the (-100, 100) range
+    pub fn set_overscan(self, percent: f32) -> Self {
+        self.try_set_overscan(percent).unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Sets the insets of the safe area of the frame, as fractions of the frame size. The view is shrunk to fit
+    /// within the insets, negative insets expand it beyond the frame.
+    ///
+    /// # Panic
+    /// If the insets of any axis leave no safe area
+    pub const fn set_safe_area(mut self, left: f32, right: f32, top: f32, bottom: f32) -> Self {
+        if left + right >= 1.0 || top + bottom >= 1.0 {
+            panic!("The safe area insets must leave part of the frame visible");
+        }
+        self.safe_area = [left, right, top, bottom];
+        self
+    }
+
+    /// Returns the builder of the projection with the given edges in the near clip, like `glFrustum`. The field of
+    /// view and the safe area are set to match the edges with the default frame size, changing the size afterwards
+    /// stretches the horizontal edges. The field of view is clamped to the supported [0.1, 179] range, so edges
+    /// wider than it are narrowed.
+    ///
+    /// # Panic
+    /// If the near clip is not positive or any of the planes is not bigger than its opposite one
+    pub fn from_planes(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Self {
+        if near <= 0.0 || right <= left || top <= bottom || far <= near {
+            panic!("The near clip must be positive and the right, top and far planes bigger than their opposites");
+        }
+        let (x_scale, x_offset) = (2.0 * near / (right - left), -(right + left) / (right - left));
+        let (y_scale, y_offset) = (2.0 * near / (top - bottom), -(top + bottom) / (top - bottom));
+        Self::new().set_near(near).set_far(far).with_clip_terms(x_scale, x_offset, y_scale, y_offset)
+    }
+
+    /// Builds the projection matrix derived from the entered parameters and consumes the builder. The matrix keeps the
+    /// field of view, clips, aspect ratio and depth mode it was built with, see [`ProjectionMatrix`].
     ///
     /// # Panic
     /// If the specified view limit position is less than the specified screen position. The view limit must be
     /// further in the Z-axis.
-    pub fn build(&self) -> Matrix {
-        let mut matrix = [[0.0; 4]; 4];
-        let aspect_ratio = self.width as f32 / self.height as f32;
-        let fov_rad: f32 = 1.0 / (self.fov * 0.5 / 180.0 * PI).tan();
+    pub fn build(&self) -> ProjectionMatrix {
+        let matrix = self.build_with_fov_scale(self.fov_scale());
+        self.with_parameters(matrix)
+    }
 
+    /// Builds the projection matrix like [`ProjectionMatrixBuilder::build`] with the given fov scale, the cotangent
+    /// of half the field of view, in place of the field of view of the builder. Unlike `build` it can be evaluated
+    /// at compile time, to bake fixed projections into `const` items.
+    ///
+    /// ```
+    /// use ferrux_projection_matrix::{math::Matrix, ProjectionMatrixBuilder};
+    ///
+    /// // the fov scale of a field of view of 90 degrees
+    /// const PROJECTION: Matrix = ProjectionMatrixBuilder::new().set_near(1.0).build_with_fov_scale(1.0);
+    /// let built = ProjectionMatrixBuilder::new().set_near(1.0).build();
+    /// assert!((built[1][1] - PROJECTION[1][1]).abs() < 0.0001);
+    /// ```
+    ///
+    /// # Panic
+    /// If the specified view limit position is less than the specified screen position
+    pub const fn build_with_fov_scale(&self, fov_scale: f32) -> Matrix {
         if self.far < self.near {
             panic!("The view limit must be bigger than the screen position, the Z-axis direction is away from the screen");
         }
-        let distance = self.far - self.near;
+        projection_cells(self.aspect_ratio(), fov_scale, self.depth_terms(), self.safe_area)
+    }
+
+    /// Returns the builder with the parameters interpolated between this builder, with `t = 0.0`, and the other one,
+    /// with `t = 1.0`. Interpolating the parameters instead of the matrix elements keeps every step a valid
+    /// projection, to animate the transitions between them. The depth mode switches halfway.
+    pub fn interpolate(&self, other: &Self, t: f32) -> Self {
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        let lerp_size = |a: usize, b: usize| (lerp(a as f32, b as f32).round() as usize).max(1);
+        Self {
+            near: lerp(self.near, other.near),
+            near_policy: if t < 0.5 { self.near_policy } else { other.near_policy },
+            far: lerp(self.far, other.far),
+            fov: lerp(self.fov, other.fov),
+            fov_scale: matrix::fov_scale(lerp(self.fov, other.fov)),
+            depth_mode: if t < 0.5 { self.depth_mode } else { other.depth_mode },
+            width: lerp_size(self.width, other.width),
+            height: lerp_size(self.height, other.height),
+            scale_factor: lerp(self.scale_factor, other.scale_factor),
+            render_scale: lerp(self.render_scale, other.render_scale),
+            safe_area: [0, 1, 2, 3].map(|i| lerp(self.safe_area[i], other.safe_area[i])),
+        }
+    }
+
+    /// Returns the matrix along with the parameters of the builder
+    fn with_parameters(&self, matrix: Matrix) -> ProjectionMatrix {
+        let Self { fov, near, far, safe_area, depth_mode, .. } = *self;
+        ProjectionMatrix::new(matrix, fov, near, far, self.aspect_ratio(), safe_area, depth_mode)
+    }
+
+    /// Ratio between the width and the height of the screen
+    const fn aspect_ratio(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+
+    /// Scale applied to the y-axis by the field of view, the cotangent of half the fov
+    const fn fov_scale(&self) -> f32 {
+        self.fov_scale
+    }
+
+    /// Scale and offset applied to the z-axis, the clip space depth of a point is `z * depth_scale + depth_offset`
+    const fn depth_terms(&self) -> (f32, f32) {
+        depth_terms(self.near, self.far, self.depth_mode)
+    }
+
+    /// Returns a copy of the builder with the field of view matching the given fov scale, clamped to the supported
+    /// [0.1, 179] range like the one of [`ProjectionMatrixBuilder::set_fov`]
+    fn with_fov_scale(mut self, fov_scale: f32) -> Self {
+        let fov = (1.0 / fov_scale).atan() * 2.0 * 180.0 / PI;
+        self.fov = fov.clamp(MIN_SUPPORTED_FOV, MAX_SUPPORTED_FOV);
+        self.fov_scale = matrix::fov_scale(self.fov);
+        self
+    }
+
+    /// Returns a copy of the builder with the field of view and the safe area producing the given scales and offsets
+    /// of the x and y axes in clip space with the current frame size
+    fn with_clip_terms(self, x_scale: f32, x_offset: f32, y_scale: f32, y_offset: f32) -> Self {
+        let builder = self.with_fov_scale(y_scale);
+        // the insets of the y-axis compensate each other, the ones of the x-axis absorb the aspect ratio
+        let kept = x_scale / (builder.aspect_ratio() * builder.fov_scale());
+        let (left, right) = ((1.0 - kept + x_offset) * 0.5, (1.0 - kept - x_offset) * 0.5);
+        builder.set_safe_area(left, right, -y_offset * 0.5, y_offset * 0.5)
+    }
+}
 
-        matrix[0][0] = aspect_ratio * fov_rad;
-        matrix[1][1] = fov_rad;
-        matrix[2][2] = self.far * distance;
-        matrix[3][2] = (-self.far * self.near) / distance;
-        matrix[2][3] = 1.0;
+/// Scale and offset applied to the z-axis by the projections with the given clips and depth mode, shared by the
+/// builder and the matrices interpolating their parameters
+const fn depth_terms(near: f32, far: f32, depth_mode: DepthMode) -> (f32, f32) {
+    let distance = far - near;
+    match depth_mode {
+        DepthMode::WBuffer => (1.0 / distance, -near / distance),
+        DepthMode::Standard | DepthMode::Logarithmic => (far * distance, (-far * near) / distance),
+    }
+}
 
-        matrix
+/// Returns the near and far clips producing the given depth terms with the depth mode, the inverse of
+/// [`depth_terms`]. The terms not produced by any pair of clips with the depth mode give meaningless clips.
+fn depth_clips(depth_terms: (f32, f32), depth_mode: DepthMode) -> (f32, f32) {
+    let (depth_scale, depth_offset) = depth_terms;
+    if depth_mode == DepthMode::WBuffer {
+        let near = -depth_offset / depth_scale;
+        return (near, near + 1.0 / depth_scale);
+    }
+    // the far clip is the only positive root of far^3 - scale * far + offset * scale, found with the Newton method
+    // from above it, where the cubic is convex and the method can't overshoot the root
+    let (scale, offset) = (depth_scale as f64, depth_offset as f64);
+    let mut far = scale.max(0.0).sqrt() + offset.abs();
+    for _ in 0..DEPTH_CLIPS_ITERATIONS {
+        let step = (far * far * far - scale * far + offset * scale) / (3.0 * far * far - scale);
+        if step.is_nan() || step <= 0.0 {
+            break;
+        }
+        far -= step;
     }
+    ((-offset * scale / (far * far)) as f32, far as f32)
+}
+
+/// Returns the cells of the projection matrix with the given aspect ratio, fov scale, depth terms and safe area
+/// insets, shared by the builder and the matrices interpolating their parameters
+const fn projection_cells(aspect_ratio: f32, fov_scale: f32, depth_terms: (f32, f32), safe_area: [f32; 4]) -> Matrix {
+    let mut matrix = [[0.0; 4]; 4];
+    let (depth_scale, depth_offset) = depth_terms;
+
+    matrix[0][0] = aspect_ratio * fov_scale;
+    matrix[1][1] = fov_scale;
+    matrix[2][2] = depth_scale;
+    matrix[3][2] = depth_offset;
+    matrix[2][3] = 1.0;
+
+    // the view is scaled and shifted in clip space to land between the insets
+    let [left, right, top, bottom] = safe_area;
+    matrix[0][0] *= 1.0 - left - right;
+    matrix[1][1] *= 1.0 - top - bottom;
+    matrix[2][0] = left - right;
+    matrix[2][1] = bottom - top;
+
+    matrix
 }
 
 impl Default for ProjectionMatrixBuilder {
@@ -139,6 +518,12 @@ fn invalid_fov_high() {
     ProjectionMatrixBuilder::new().set_fov(360.0);
 }
 
+#[test]
+#[should_panic]
+fn invalid_fov_flat() {
+    ProjectionMatrixBuilder::new().set_fov(180.0);
+}
+
 #[test]
 #[should_panic]
 fn invalid_zoom() {
@@ -148,13 +533,167 @@ fn invalid_zoom() {
         .build();
 }
 
+#[test]
+#[should_panic]
+fn invalid_render_scale() {
+    ProjectionMatrixBuilder::new().set_render_scale(0.0);
+}
+
+#[test]
+#[should_panic]
+fn negative_logical_size() {
+    ProjectionMatrixBuilder::new().set_logical_size(-1280.0, 720.0, 1.0);
+}
+
+#[test]
+#[should_panic]
+fn nan_logical_size() {
+    ProjectionMatrixBuilder::new().set_logical_size(1280.0, f32::NAN, 1.0);
+}
+
+#[test]
+#[should_panic]
+fn empty_logical_size() {
+    ProjectionMatrixBuilder::new().set_logical_size(1280.0, 0.2, 1.0);
+}
+
+#[test]
+fn render_scale() {
+    let builder = ProjectionMatrixBuilder::new().set_render_scale(0.5);
+    assert_eq!((640, 360), builder.render_resolution());
+    assert_eq!(ProjectionMatrixBuilder::new().build(), builder.build());
+    assert_eq!((2560, 1440), builder.set_render_scale(2.0).render_resolution());
+}
+
+#[test]
+fn logical_size() {
+    let builder = ProjectionMatrixBuilder::new().set_logical_size(1280.0, 720.0, 1.5);
+    assert_eq!((1920, 1080), builder.physical_size());
+    assert_eq!((1280.0, 720.0), builder.logical_size());
+    assert_eq!((150.0, 300.0), builder.to_physical(100.0, 200.0));
+    assert_eq!((100.0, 200.0), builder.to_logical(150.0, 300.0));
+
+    let builder = builder.set_scale_factor(2.0);
+    assert_eq!((2560, 1440), builder.physical_size());
+    assert_eq!((1280.0, 720.0), builder.logical_size());
+}
+
+#[test]
+fn drawable_size() {
+    let builder = ProjectionMatrixBuilder::new().set_drawable_size(1280, 720, 2560, 1440);
+    assert_eq!((2560, 1440), builder.physical_size());
+    assert_eq!((1280.0, 720.0), builder.logical_size());
+    assert_eq!(ProjectionMatrixBuilder::new().set_logical_size(1280.0, 720.0, 2.0), builder);
+}
+
+#[test]
+#[should_panic]
+fn invalid_drawable_size() {
+    ProjectionMatrixBuilder::new().set_drawable_size(1280, 720, 0, 0);
+}
+
+#[test]
+#[should_panic]
+fn invalid_scale_factor() {
+    ProjectionMatrixBuilder::new().set_scale_factor(-1.0);
+}
+
+#[test]
+#[should_panic]
+fn invalid_overscan() {
+    ProjectionMatrixBuilder::new().set_overscan(100.0);
+}
+
+#[test]
+#[should_panic]
+fn invalid_safe_area() {
+    ProjectionMatrixBuilder::new().set_safe_area(0.0, 0.0, 0.6, 0.4);
+}
+
+#[test]
+fn safe_area() {
+    let base = ProjectionMatrixBuilder::new().build();
+    let matrix = ProjectionMatrixBuilder::new().set_overscan(10.0).build();
+    assert!((base[0][0] * 0.9 - matrix[0][0]).abs() < 0.0001);
+    assert!((base[1][1] * 0.9 - matrix[1][1]).abs() < 0.0001);
+    assert_eq!([0.0, 0.0], [matrix[2][0], matrix[2][1]]);
+
+    // the left edge of the view lands on the left inset
+    let matrix = ProjectionMatrixBuilder::new().set_safe_area(0.2, 0.0, 0.1, 0.0).build();
+    let edge = -1.0 / base[0][0];
+    assert!((-0.6 - (edge * matrix[0][0] + matrix[2][0])).abs() < 0.0001);
+    assert!((0.8 - (1.0 / base[1][1] * matrix[1][1] + matrix[2][1])).abs() < 0.0001);
+}
+
+#[test]
+fn from_planes() {
+    let builder = ProjectionMatrixBuilder::from_planes(-1.0, 3.0, -0.5, 2.0, 2.0, 100.0);
+    let matrix = builder.build();
+    let points = [([-1.0, -0.5, 2.0], [-1.0, -1.0]), ([3.0, 2.0, 2.0], [1.0, 1.0]), ([40.0, 20.0, 40.0], [0.5, 0.2])];
+    for (point, expected) in points {
+        let clip = math::transform_point(point, &matrix);
+        assert!((clip[0] / clip[3] - expected[0]).abs() < 0.0001 && (clip[1] / clip[3] - expected[1]).abs() < 0.0001);
+    }
+    assert_eq!((2.0, 100.0), (builder.near, builder.far));
+}
+
+#[test]
+#[should_panic]
+fn from_planes_without_near() {
+    ProjectionMatrixBuilder::from_planes(-1.0, 1.0, -1.0, 1.0, 0.0, 100.0);
+}
+
+#[test]
+fn from_planes_fov_envelope() {
+    let wide = ProjectionMatrixBuilder::from_planes(-1.0, 1.0, -1000.0, 1000.0, 0.001, 100.0);
+    assert_eq!(MAX_SUPPORTED_FOV, wide.fov);
+    let narrow = ProjectionMatrixBuilder::from_planes(-0.00001, 0.00001, -0.00001, 0.00001, 100.0, 1000.0);
+    assert_eq!(MIN_SUPPORTED_FOV, narrow.fov);
+    assert!(narrow.build().iter().flatten().all(|cell| cell.is_finite()));
+}
+
+#[test]
+fn cached_fov_scale() {
+    assert_eq!(ProjectionMatrixBuilder::new(), ProjectionMatrixBuilder::new().set_fov(60.0).set_fov(90.0));
+    let builder = ProjectionMatrixBuilder::new().set_fov(70.0);
+    assert_eq!(1.0 / (35.0 / 180.0 * PI).tan(), builder.fov_scale());
+    assert_eq!(builder, builder.with_fov_scale(builder.fov_scale()));
+    assert_eq!(matrix::fov_scale(80.0), builder.interpolate(&builder.set_fov(90.0), 0.5).fov_scale());
+}
+
+#[test]
+fn focal_length() {
+    let builder = ProjectionMatrixBuilder::new().set_focal_length(12.0, 24.0);
+    assert!((90.0 - builder.fov).abs() < 0.0001);
+}
+
+#[test]
+fn interpolation() {
+    let from = ProjectionMatrixBuilder::new().set_near(1.0).set_far(100.0).set_fov(60.0);
+    let to = ProjectionMatrixBuilder::new()
+        .set_near_policy(NearPolicy::Allow)
+        .set_near(0.0)
+        .set_far(200.0)
+        .set_fov(100.0)
+        .set_width(720)
+        .set_overscan(10.0)
+        .set_depth_mode(DepthMode::WBuffer);
+    let middle = from.interpolate(&to, 0.5);
+    assert_eq!((0.5, 150.0, 80.0), (middle.near, middle.far, middle.fov));
+    assert_eq!((1000, 720), (middle.width, middle.height));
+    assert_eq!([0.025; 4], middle.safe_area);
+    assert_eq!(DepthMode::WBuffer, middle.depth_mode);
+    assert_eq!(from, from.interpolate(&to, 0.0));
+    assert_eq!(to.build(), from.interpolate(&to, 1.0).build());
+}
+
 #[test]
 fn default_building() {
     let matrix = ProjectionMatrixBuilder::new().build();
     assert!((DEFAULT_WIDTH as f32 / DEFAULT_HEIGHT as f32 - matrix[0][0]).abs() < 0.0001);
     assert!((1.0 - matrix[1][1]).abs() < 0.0001);
-    assert!((DEFAULT_FAR.powf(2.0) - matrix[2][2]).abs() < 0.0001);
-    assert!((0.0 - matrix[3][2]).abs() < 0.0001);
+    assert!((DEFAULT_FAR * (DEFAULT_FAR - DEFAULT_NEAR) - matrix[2][2]).abs() < 0.0001);
+    assert!((-DEFAULT_FAR * DEFAULT_NEAR / (DEFAULT_FAR - DEFAULT_NEAR) - matrix[3][2]).abs() < 0.0001);
     assert!((1.0 - matrix[2][3]).abs() < 0.0001);
 }
 
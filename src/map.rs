@@ -0,0 +1,143 @@
+//! Top-down orthographic projections of maps and minimaps, mapping the world units into pixels at a fixed scale
+
+use crate::math::{self, Matrix, Vec3};
+use crate::Orthographic;
+
+const DEFAULT_CENTER: Vec3 = [0.0; 3];
+const DEFAULT_METERS_PER_PIXEL: f32 = 1.0;
+const DEFAULT_RESOLUTION: (usize, usize) = (256, 256);
+const DEFAULT_ALTITUDE_RANGE: (f32, f32) = (-1000.0, 1000.0);
+
+/// Builder of the top-down projections of the maps, looking down the y-axis with the positive z-axis pointing to the
+/// top of the map
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MapProjectionBuilder {
+    center: Vec3,
+    meters_per_pixel: f32,
+    width: usize,
+    height: usize,
+    altitude_range: (f32, f32),
+}
+
+impl MapProjectionBuilder {
+    /// Returns an instance of a builder
+    ///
+    /// # Default
+    /// By default, the map builder sets the following defaults:
+    /// * `center`: the origin
+    /// * `meters_per_pixel`: 1.0
+    /// * `resolution`: 256x256
+    /// * `altitude_range`: from -1000.0 to 1000.0
+    pub fn new() -> Self {
+        Self {
+            center: DEFAULT_CENTER,
+            meters_per_pixel: DEFAULT_METERS_PER_PIXEL,
+            width: DEFAULT_RESOLUTION.0,
+            height: DEFAULT_RESOLUTION.1,
+            altitude_range: DEFAULT_ALTITUDE_RANGE,
+        }
+    }
+
+    /// Sets the world position shown at the center of the map, its altitude is ignored
+    pub fn set_center(mut self, center: Vec3) -> Self {
+        self.center = center;
+        self
+    }
+
+    /// Sets the world units covered by each pixel of the map
+    ///
+    /// # Panic
+    /// If the entered scale is not positive
+    pub fn set_meters_per_pixel(mut self, meters_per_pixel: f32) -> Self {
+        if meters_per_pixel <= 0.0 {
+            panic!("The meters per pixel must be a positive value");
+        }
+        self.meters_per_pixel = meters_per_pixel;
+        self
+    }
+
+    /// Sets the size of the map in pixels
+    pub fn set_resolution(mut self, width: usize, height: usize) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Sets the lowest and highest altitude, in the y-axis, of the content shown in the map
+    ///
+    /// # Panic
+    /// If the highest altitude is not bigger than the lowest one
+    pub fn set_altitude_range(mut self, lowest: f32, highest: f32) -> Self {
+        if highest <= lowest {
+            panic!("The highest altitude must be bigger than the lowest one");
+        }
+        self.altitude_range = (lowest, highest);
+        self
+    }
+
+    /// Builds the orthographic projection and the view matrix of the map
+    pub fn build(&self) -> (Orthographic, Matrix) {
+        let (lowest, highest) = self.altitude_range;
+        let (half_width, half_height) = self.half_extents();
+        let [x, _, z] = self.center;
+        let projection = Orthographic::new(x - half_width, x + half_width, z - half_height, z + half_height, 0.0,
+                                           highest - lowest);
+        // the x and z axes are kept, so the planes of the projection are the world positions
+        let view = math::view_matrix([0.0, highest, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, -1.0, 0.0]);
+        (projection, view)
+    }
+
+    /// Returns the pixel of the map where the world position is shown, with the origin at the top left corner
+    pub fn world_to_pixel(&self, point: Vec3) -> (f32, f32) {
+        let (half_width, half_height) = self.half_extents();
+        (
+            (point[0] - self.center[0] + half_width) / self.meters_per_pixel,
+            (self.center[2] - point[2] + half_height) / self.meters_per_pixel,
+        )
+    }
+
+    /// Returns half the world units covered by the width and height of the map
+    fn half_extents(&self) -> (f32, f32) {
+        (self.width as f32 * self.meters_per_pixel * 0.5, self.height as f32 * self.meters_per_pixel * 0.5)
+    }
+}
+
+impl Default for MapProjectionBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn map_scale() {
+    use crate::{Projection, Viewport};
+    let builder = MapProjectionBuilder::new()
+        .set_center([100.0, 5.0, -50.0])
+        .set_meters_per_pixel(0.5)
+        .set_resolution(200, 100);
+    let (projection, view) = builder.build();
+    let viewport = Viewport::new(0, 0, 200, 100);
+
+    for point in [[100.0, 0.0, -50.0], [60.0, 200.0, -30.0], [149.5, -300.0, -74.5]] {
+        let [x, y, z, _] = math::transform_point(point, &view);
+        let ndc = projection.project([x, y, z]).unwrap();
+        let (x, y) = ndc.to_screen(&viewport);
+        let (expected_x, expected_y) = builder.world_to_pixel(point);
+        assert!((x - expected_x).abs() < 0.001 && (y - expected_y).abs() < 0.001);
+    }
+    assert_eq!((0.0, 0.0), builder.world_to_pixel([50.0, 0.0, -25.0]));
+    assert_eq!((100.0, 50.0), builder.world_to_pixel([100.0, 0.0, -50.0]));
+}
+
+#[test]
+fn map_altitude_range() {
+    let (projection, view) = MapProjectionBuilder::new().set_altitude_range(-10.0, 30.0).build();
+    assert_eq!((0.0, 40.0), (projection.near, projection.far));
+    assert_eq!([0.0, 0.0, 30.0, 1.0], math::transform_point([0.0; 3], &view));
+}
+
+#[test]
+#[should_panic]
+fn invalid_meters_per_pixel() {
+    MapProjectionBuilder::new().set_meters_per_pixel(0.0);
+}
@@ -0,0 +1,68 @@
+//! Markers of the HUD anchored to world positions, like the ones of waypoints or enemies, kept within the screen
+//! edges with an indicator pointing to them while they are out of the view
+
+use crate::math::{self, Matrix, Vec3};
+use crate::{ProjectionMatrixBuilder, Viewport};
+
+/// Placement of the marker of a world anchor in the viewport
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScreenMarker {
+    /// Horizontal position in pixels, from the left of the window
+    pub x: f32,
+    /// Vertical position in pixels, from the top of the window
+    pub y: f32,
+    /// If the anchor is in front of the camera within the margins, otherwise the marker sits on the margins
+    pub on_screen: bool,
+    /// Direction from the center of the viewport to the anchor, in grades counterclockwise from the right, to rotate
+    /// the indicator arrows
+    pub angle: f32,
+}
+
+impl ProjectionMatrixBuilder {
+    /// Returns the placement of the marker of the world space anchor seen through the view matrix, keeping it at
+    /// least the margin in pixels away from the edges of the viewport. The anchors behind the camera are placed on
+    /// the edge of their side, or on the bottom one when they are straight behind it.
+    pub fn place_marker(&self, anchor: Vec3, view: &Matrix, viewport: &Viewport, margin: f32) -> ScreenMarker {
+        let [x, y, _, w] = math::transform_point(anchor, &math::multiply(view, &self.build()));
+        let half_size = (viewport.width as f32 * 0.5, viewport.height as f32 * 0.5);
+        let center = (viewport.x as f32 + half_size.0, viewport.y as f32 + half_size.1);
+        // the behind ones are divided by the distance without flipping them
+        let distance = if w.abs() > f32::EPSILON { w.abs() } else { 1.0 };
+        let mut direction = (x / distance * half_size.0, -y / distance * half_size.1);
+        let in_front = w >= self.near;
+        if !in_front && direction.0 == 0.0 && direction.1 == 0.0 {
+            direction = (0.0, 1.0);
+        }
+        let bounds = ((half_size.0 - margin).max(0.0), (half_size.1 - margin).max(0.0));
+        let angle = (-direction.1).atan2(direction.0).to_degrees();
+
+        let on_screen = in_front && direction.0.abs() <= bounds.0 && direction.1.abs() <= bounds.1;
+        if !on_screen {
+            // pushed along its direction until it reaches the margins
+            let scale = (bounds.0 / direction.0.abs()).min(bounds.1 / direction.1.abs());
+            direction = (direction.0 * scale, direction.1 * scale);
+        }
+        ScreenMarker { x: center.0 + direction.0, y: center.1 + direction.1, on_screen, angle }
+    }
+}
+
+#[test]
+fn world_markers() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_width(720);
+    let view = math::identity();
+    let viewport = Viewport::new(0, 0, 720, 720);
+
+    let visible = builder.place_marker([2.0, 0.0, 10.0], &view, &viewport, 20.0);
+    let (x, y, _) = builder.world_to_screen([2.0, 0.0, 10.0], &view, &viewport).unwrap();
+    assert!(visible.on_screen && (visible.x - x).abs() < 0.001 && visible.y == y && visible.angle == 0.0);
+
+    let right = builder.place_marker([30.0, 10.0, 10.0], &view, &viewport, 20.0);
+    assert!(!right.on_screen && right.x == 700.0 && (right.y - (360.0 - 340.0 / 3.0)).abs() < 0.001, "{right:?}");
+    assert!((right.angle - (1.0f32 / 3.0).atan().to_degrees()).abs() < 0.0001);
+
+    // behind the camera they stay on their side
+    let behind = builder.place_marker([-5.0, 0.0, -10.0], &view, &viewport, 20.0);
+    assert_eq!(ScreenMarker { x: 20.0, y: 360.0, on_screen: false, angle: 180.0 }, behind);
+    let straight_behind = builder.place_marker([0.0, 0.0, -10.0], &view, &viewport, 20.0);
+    assert_eq!((360.0, 700.0, -90.0), (straight_behind.x, straight_behind.y, straight_behind.angle));
+}
@@ -0,0 +1,245 @@
+//! Vector and matrix operations over plain arrays
+//!
+//! Points and vectors are row vectors multiplied on the left side of the matrices, so the transformation of a
+//! matrix `a` followed by the one of `b` is `multiply(a, b)`. The view space looks down the positive z-axis like the
+//! projection matrices of the crate.
+
+/// Vector of three components
+pub type Vec3 = [f32; 3];
+/// Vector of three double precision components, for the positions of huge worlds
+pub type DVec3 = [f64; 3];
+/// Vector of four components, homogeneous coordinates of a three dimensional point
+pub type Vec4 = [f32; 4];
+/// Four by four matrix, indexed by row and then column
+pub type Matrix = [[f32; 4]; 4];
+
+/// Three by three matrix, indexed by row and then column
+pub type Matrix3 = [[f32; 3]; 3];
+
+/// Returns the identity matrix
+pub fn identity() -> Matrix {
+    let mut matrix = [[0.0; 4]; 4];
+    for (i, row) in matrix.iter_mut().enumerate() {
+        row[i] = 1.0;
+    }
+    matrix
+}
+
+/// Returns the matrix applying the transformation of `a` followed by the one of `b`
+pub fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut matrix = [[0.0; 4]; 4];
+    for (i, row) in matrix.iter_mut().enumerate() {
+        for (j, cell) in row.iter_mut().enumerate() {
+            *cell = (0..4).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    matrix
+}
+
+/// Applies the matrix to the vector
+pub fn transform(vector: Vec4, matrix: &Matrix) -> Vec4 {
+    let mut result = [0.0; 4];
+    for (j, cell) in result.iter_mut().enumerate() {
+        *cell = (0..4).map(|k| vector[k] * matrix[k][j]).sum();
+    }
+    result
+}
+
+/// Applies the matrix to the point, returning its homogeneous coordinates
+pub fn transform_point(point: Vec3, matrix: &Matrix) -> Vec4 {
+    transform([point[0], point[1], point[2], 1.0], matrix)
+}
+
+/// Returns the transposed matrix
+pub fn transpose(matrix: &Matrix) -> Matrix {
+    let mut transposed = [[0.0; 4]; 4];
+    for (i, row) in transposed.iter_mut().enumerate() {
+        for (j, cell) in row.iter_mut().enumerate() {
+            *cell = matrix[j][i];
+        }
+    }
+    transposed
+}
+
+/// Returns the inverse of the matrix or `None` if it's singular
+pub fn inverse(matrix: &Matrix) -> Option<Matrix> {
+    // Gauss-Jordan elimination with partial pivoting
+    let mut m = *matrix;
+    let mut inverse = identity();
+    for column in 0..4 {
+        let pivot = (column..4).max_by(|&a, &b| m[a][column].abs().total_cmp(&m[b][column].abs()))?;
+        if m[pivot][column].abs() < f32::EPSILON {
+            return None;
+        }
+        m.swap(column, pivot);
+        inverse.swap(column, pivot);
+        let divisor = m[column][column];
+        for j in 0..4 {
+            m[column][j] /= divisor;
+            inverse[column][j] /= divisor;
+        }
+        for row in 0..4 {
+            if row != column {
+                let factor = m[row][column];
+                for j in 0..4 {
+                    m[row][j] -= factor * m[column][j];
+                    inverse[row][j] -= factor * inverse[column][j];
+                }
+            }
+        }
+    }
+    Some(inverse)
+}
+
+/// Returns the matrix to transform normals with the given model-view matrix, the inverse-transpose of its upper
+/// three by three part, keeping them perpendicular to the surfaces under non-uniform scales. Returns `None` if the
+/// matrix is singular.
+pub fn normal_matrix(model_view: &Matrix) -> Option<Matrix3> {
+    let m = |i: usize, j: usize| model_view[i % 3][j % 3];
+    // the inverse-transpose is the cofactor matrix divided by the determinant
+    let mut cofactors = [[0.0; 3]; 3];
+    for (i, row) in cofactors.iter_mut().enumerate() {
+        for (j, cell) in row.iter_mut().enumerate() {
+            *cell = m(i + 1, j + 1) * m(i + 2, j + 2) - m(i + 1, j + 2) * m(i + 2, j + 1);
+        }
+    }
+    let determinant: f32 = (0..3).map(|j| model_view[0][j] * cofactors[0][j]).sum();
+    if determinant.abs() < f32::EPSILON {
+        return None;
+    }
+    Some(cofactors.map(|row| row.map(|cell| cell / determinant)))
+}
+
+/// Returns the view matrix of a camera placed in `eye` looking at `target`, with `up` pointing to the top of the
+/// screen. The view space x-axis points to the right, the y-axis up and the z-axis forward.
+pub fn look_at(eye: Vec3, target: Vec3, up: Vec3) -> Matrix {
+    let forward = normalize(sub(target, eye));
+    let right = normalize(cross(up, forward));
+    let up = cross(forward, right);
+    view_matrix(eye, right, up, forward)
+}
+
+/// Returns the view matrix of a camera placed in `eye` with the given orthonormal axes
+pub(crate) fn view_matrix(eye: Vec3, right: Vec3, up: Vec3, forward: Vec3) -> Matrix {
+    [
+        [right[0], up[0], forward[0], 0.0],
+        [right[1], up[1], forward[1], 0.0],
+        [right[2], up[2], forward[2], 0.0],
+        [-dot(right, eye), -dot(up, eye), -dot(forward, eye), 1.0],
+    ]
+}
+
+/// Returns the 64 bytes of the matrix, the little-endian bytes of its floats by rows, to store it or send it in a
+/// fixed layout
+pub fn to_le_bytes(matrix: &Matrix) -> [u8; 64] {
+    let mut bytes = [0; 64];
+    for (chunk, cell) in bytes.chunks_exact_mut(4).zip(matrix.iter().flatten()) {
+        chunk.copy_from_slice(&cell.to_le_bytes());
+    }
+    bytes
+}
+
+/// Returns the matrix stored in the 64 bytes of the layout of [`to_le_bytes`]
+pub fn from_le_bytes(bytes: &[u8; 64]) -> Matrix {
+    let mut matrix = [[0.0; 4]; 4];
+    for (cell, chunk) in matrix.iter_mut().flatten().zip(bytes.chunks_exact(4)) {
+        *cell = f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    matrix
+}
+
+/// Returns the sum of both vectors
+pub fn add(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+/// Returns the difference between both vectors
+pub fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+/// Returns the vector multiplied by the scalar
+pub fn scale(vector: Vec3, scalar: f32) -> Vec3 {
+    [vector[0] * scalar, vector[1] * scalar, vector[2] * scalar]
+}
+
+/// Returns the dot product of both vectors
+pub fn dot(a: Vec3, b: Vec3) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Returns the cross product of both vectors
+pub fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+/// Returns the length of the vector
+pub fn length(vector: Vec3) -> f32 {
+    dot(vector, vector).sqrt()
+}
+
+/// Returns the vector scaled to a length of one, or the vector itself if it has no length
+pub fn normalize(vector: Vec3) -> Vec3 {
+    let length = length(vector);
+    if length == 0.0 { vector } else { scale(vector, 1.0 / length) }
+}
+
+/// Returns the linear interpolation between both vectors, `a` with `t = 0.0` and `b` with `t = 1.0`
+pub fn lerp(a: Vec3, b: Vec3, t: f32) -> Vec3 {
+    add(a, scale(sub(b, a), t))
+}
+
+#[cfg(test)]
+pub(crate) fn assert_matrix_eq(expected: &Matrix, actual: &Matrix) {
+    for i in 0..4 {
+        for j in 0..4 {
+            assert!((expected[i][j] - actual[i][j]).abs() < 0.0001, "{expected:?} != {actual:?}");
+        }
+    }
+}
+
+#[test]
+fn inverse_matrix() {
+    let matrix = look_at([1.0, 2.0, -3.0], [4.0, 0.0, 5.0], [0.0, 1.0, 0.0]);
+    let inverted = inverse(&matrix).unwrap();
+    assert_matrix_eq(&identity(), &multiply(&matrix, &inverted));
+    assert_eq!(None, inverse(&[[0.0; 4]; 4]));
+}
+
+#[test]
+fn le_bytes_layout() {
+    let matrix = look_at([1.0, 2.0, -3.0], [4.0, 0.0, 5.0], [0.0, 1.0, 0.0]);
+    let bytes = to_le_bytes(&matrix);
+    assert_eq!(matrix[0][1].to_le_bytes(), bytes[4..8]);
+    assert_eq!(matrix[3][2].to_le_bytes(), bytes[56..60]);
+    assert_eq!(matrix, from_le_bytes(&bytes));
+    assert_eq!([0, 0, 128, 63], to_le_bytes(&identity())[..4]);
+}
+
+#[test]
+fn look_at_view_space() {
+    let view = look_at([0.0, 0.0, -5.0], [0.0, 0.0, 0.0], [0.0, 1.0, 0.0]);
+    assert_eq!([0.0, 0.0, 5.0, 1.0], transform_point([0.0, 0.0, 0.0], &view));
+    assert_eq!([1.0, 1.0, 5.0, 1.0], transform_point([1.0, 1.0, 0.0], &view));
+
+    let view = look_at([0.0, 0.0, 0.0], [10.0, 0.0, 0.0], [0.0, 1.0, 0.0]);
+    assert_matrix_eq(
+        &[[0.0, 0.0, 1.0, 0.0], [0.0, 1.0, 0.0, 0.0], [-1.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 1.0]],
+        &view,
+    );
+}
+
+#[test]
+fn normal_matrix_scaling() {
+    let mut model = identity();
+    model[0][0] = 2.0;
+    model[1][1] = 4.0;
+    model[3] = [5.0, 6.0, 7.0, 1.0];
+    assert_eq!(Some([[0.5, 0.0, 0.0], [0.0, 0.25, 0.0], [0.0, 0.0, 1.0]]), normal_matrix(&model));
+
+    // rotations are kept untouched
+    let view = look_at([1.0, 2.0, 3.0], [0.0; 3], [0.0, 1.0, 0.0]);
+    let normal = normal_matrix(&view).unwrap();
+    assert!((0..3).all(|i| (0..3).all(|j| (view[i][j] - normal[i][j]).abs() < 0.0001)));
+    assert_eq!(None, normal_matrix(&[[0.0; 4]; 4]));
+}
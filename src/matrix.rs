@@ -0,0 +1,466 @@
+//! Projection matrices along with the parameters they were built from
+
+use std::borrow::Borrow;
+use std::f32::consts::PI;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+
+use crate::math::Matrix;
+use crate::{DepthMode, ParamError, MAX_SUPPORTED_FOV, MIN_SUPPORTED_FOV};
+
+/// Projection matrix built by the [`crate::ProjectionMatrixBuilder`], remembering the parameters used to build it so
+/// they can be queried without the builder. It dereferences to the plain [`Matrix`].
+///
+/// The equality and hash are bit-exact, comparing the bit patterns of the floats, so the matrices can key caches of
+/// derived resources. Beware that `0.0` and `-0.0` are different matrices and a `NaN` is equal to itself, unlike with
+/// the usual comparison of floats. The comparisons with plain matrices do use the usual comparison.
+#[derive(Clone, Copy, Debug)]
+pub struct ProjectionMatrix {
+    matrix: Matrix,
+    fov: f32,
+    near: f32,
+    far: f32,
+    aspect_ratio: f32,
+    safe_area: [f32; 4],
+    depth_mode: DepthMode,
+}
+
+impl ProjectionMatrix {
+    /// Returns the matrix with the given parameters
+    pub(crate) fn new(matrix: Matrix, fov: f32, near: f32, far: f32, aspect_ratio: f32, safe_area: [f32; 4],
+                      depth_mode: DepthMode) -> Self {
+        Self { matrix, fov, near, far, aspect_ratio, safe_area, depth_mode }
+    }
+
+    /// Wraps a plain matrix built with the given depth mode, deriving the parameters from its cells: the field of
+    /// view and aspect ratio from the scales of the axes and the clips from its depth cells. The offsets of the axes
+    /// are kept by a safe area shifting the view without scaling it. The matrices built by the crate keep their clips.
+    pub fn from_matrix(matrix: Matrix, depth_mode: DepthMode) -> Self {
+        let (fov, aspect_ratio) = scales_parameters(&matrix);
+        let (near, far) = crate::depth_clips((matrix[2][2], matrix[3][2]), depth_mode);
+        let (x_offset, y_offset) = (matrix[2][0] * 0.5, matrix[2][1] * 0.5);
+        let safe_area = [x_offset, -x_offset, -y_offset, y_offset];
+        Self::new(matrix, fov, near, far, aspect_ratio, safe_area, depth_mode)
+    }
+
+    /// Returns the plain matrix
+    pub fn matrix(&self) -> Matrix {
+        self.matrix
+    }
+
+    /// Returns the field of view in degrees
+    pub fn fov(&self) -> f32 {
+        self.fov
+    }
+
+    /// Returns the position of the near clip in the z-axis
+    pub fn near(&self) -> f32 {
+        self.near
+    }
+
+    /// Returns the position of the far clip in the z-axis
+    pub fn far(&self) -> f32 {
+        self.far
+    }
+
+    /// Returns the ratio between the width and the height of the frame
+    pub fn aspect_ratio(&self) -> f32 {
+        self.aspect_ratio
+    }
+
+    /// Returns the `[left, right, top, bottom]` insets of the safe area, as fractions of the frame size, including
+    /// the sub-pixel offsets of the jittered matrices
+    pub fn safe_area(&self) -> [f32; 4] {
+        self.safe_area
+    }
+
+    /// Returns the encoding of the depth values
+    pub fn depth_mode(&self) -> DepthMode {
+        self.depth_mode
+    }
+
+    /// Updates the matrix to a frame of the given size rescaling only the x-axis scale, instead of building the
+    /// whole matrix again, for dynamic resolutions changing every frame
+    ///
+    /// # Panic
+    /// If the width or the height is zero
+    pub fn update_resolution(&mut self, width: usize, height: usize) {
+        if width == 0 || height == 0 {
+            panic!("{}", ParamError::Size { width, height });
+        }
+        let aspect_ratio = width as f32 / height as f32;
+        self.matrix[0][0] *= aspect_ratio / self.aspect_ratio;
+        self.aspect_ratio = aspect_ratio;
+    }
+
+    /// Updates the matrix to the given field of view in degrees rescaling only the x-axis and y-axis scales, instead
+    /// of building the whole matrix again
+    ///
+    /// # Panic
+    /// If the entered fov is not within the supported [0.1, 179] range
+    pub fn update_fov(&mut self, fov: f32) {
+        if !(MIN_SUPPORTED_FOV..=MAX_SUPPORTED_FOV).contains(&fov) {
+            panic!("{}", ParamError::Fov(fov));
+        }
+        let ratio = fov_scale(fov) / fov_scale(self.fov);
+        self.matrix[0][0] *= ratio;
+        self.matrix[1][1] *= ratio;
+        self.fov = fov;
+    }
+
+    /// Returns the matrix with the parameters interpolated between this matrix, with `t = 0.0`, and the other one,
+    /// with `t = 1.0`, like [`crate::ProjectionMatrixBuilder::interpolate`]. The field of view, clips, aspect ratio and
+    /// safe area offsets are interpolated and the depth mode switches halfway, so every step is a valid projection.
+    /// The matrix is built again from the parameters, dropping the changes made after building, like the ones of
+    /// [`crate::ProjectionMatrixBuilder::oblique`].
+    pub fn interpolate(&self, other: &Self, t: f32) -> Self {
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        let (fov, near, far) = (lerp(self.fov, other.fov), lerp(self.near, other.near), lerp(self.far, other.far));
+        let aspect_ratio = lerp(self.aspect_ratio, other.aspect_ratio);
+        let safe_area = [0, 1, 2, 3].map(|i| lerp(self.safe_area[i], other.safe_area[i]));
+        let depth_mode = if t < 0.5 { self.depth_mode } else { other.depth_mode };
+        let depth_terms = crate::depth_terms(near, far, depth_mode);
+        let matrix = crate::projection_cells(aspect_ratio, fov_scale(fov), depth_terms, safe_area);
+        Self::new(matrix, fov, near, far, aspect_ratio, safe_area, depth_mode)
+    }
+
+    /// Returns the differences with the other matrix, in the cells and in the parameters they were built with, to
+    /// find why two matrices don't match
+    pub fn diff(&self, other: &Self) -> MatrixDiff {
+        let mut cells = Vec::new();
+        for (row, (values, others)) in self.matrix.iter().zip(&other.matrix).enumerate() {
+            for (column, (&value, &other)) in values.iter().zip(others).enumerate() {
+                if value.to_bits() != other.to_bits() {
+                    cells.push(CellDiff { row, column, value, other });
+                }
+            }
+        }
+        let max_delta = cells.iter().fold(0.0f32, |max, cell| max.max((cell.value - cell.other).abs()));
+        let parameters = [
+            ("fov", self.fov, other.fov),
+            ("near", self.near, other.near),
+            ("far", self.far, other.far),
+            ("aspect_ratio", self.aspect_ratio, other.aspect_ratio),
+            ("safe_area_left", self.safe_area[0], other.safe_area[0]),
+            ("safe_area_right", self.safe_area[1], other.safe_area[1]),
+            ("safe_area_top", self.safe_area[2], other.safe_area[2]),
+            ("safe_area_bottom", self.safe_area[3], other.safe_area[3]),
+        ]
+        .into_iter()
+        .filter(|(_, value, other)| value.to_bits() != other.to_bits())
+        .map(|(name, value, other)| ParameterDiff { name, value, other })
+        .collect();
+        let depth_modes = (self.depth_mode != other.depth_mode).then_some((self.depth_mode, other.depth_mode));
+        MatrixDiff { max_delta, cells, parameters, depth_modes }
+    }
+
+    /// Returns a readable description of the matrix for troubleshooting, with each row labelled with the meaning of
+    /// its cells and the field of view and aspect ratio derived from the cells next to the clips and depth mode
+    ///
+    /// ```
+    /// use ferrux_projection_matrix::ProjectionMatrixBuilder;
+    ///
+    /// let explanation = ProjectionMatrixBuilder::new().set_width(720).set_height(360).build().explain();
+    /// assert!(explanation.contains("fov: 90"));
+    /// assert!(explanation.contains("aspect ratio: 2"));
+    /// ```
+    pub fn explain(&self) -> String {
+        let labels = [
+            "x-scale",
+            "y-scale",
+            "x-offset, y-offset, depth scale, w coupling",
+            "depth offset",
+        ];
+        let mut explanation = String::new();
+        for (i, (row, label)) in self.matrix.iter().zip(labels).enumerate() {
+            explanation += &format!("row {i} {row:?}: {label}\n");
+        }
+        let (fov, aspect_ratio) = scales_parameters(&self.matrix);
+        explanation += &format!(
+            "fov: {fov}, aspect ratio: {aspect_ratio}, near: {}, far: {}, depth mode: {:?}",
+            self.near,
+            self.far,
+            self.depth_mode
+        );
+        explanation
+    }
+
+    /// Returns the bit patterns of the matrix and the parameters
+    fn bits(&self) -> ([[u32; 4]; 4], [u32; 8]) {
+        let [left, right, top, bottom] = self.safe_area;
+        let parameters = [self.fov, self.near, self.far, self.aspect_ratio, left, right, top, bottom].map(f32::to_bits);
+        (matrix_bits(&self.matrix), parameters)
+    }
+}
+
+/// Returns the scale applied to the y-axis by the field of view in degrees, the cotangent of half the fov
+pub(crate) fn fov_scale(fov: f32) -> f32 {
+    1.0 / (fov * 0.5 / 180.0 * PI).tan()
+}
+
+/// Cell of the matrix with different values in two matrices
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CellDiff {
+    /// Row of the cell
+    pub row: usize,
+    /// Column of the cell
+    pub column: usize,
+    /// Value in the compared matrix
+    pub value: f32,
+    /// Value in the other matrix
+    pub other: f32,
+}
+
+/// Parameter with different values in two matrices
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParameterDiff {
+    /// Name of the parameter
+    pub name: &'static str,
+    /// Value in the compared matrix
+    pub value: f32,
+    /// Value in the other matrix
+    pub other: f32,
+}
+
+/// Differences between two projection matrices, see [`ProjectionMatrix::diff`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatrixDiff {
+    /// Biggest absolute difference between the cells
+    pub max_delta: f32,
+    /// Cells with different values, by rows
+    pub cells: Vec<CellDiff>,
+    /// Parameters with different values
+    pub parameters: Vec<ParameterDiff>,
+    /// Depth modes of both matrices, if they are different
+    pub depth_modes: Option<(DepthMode, DepthMode)>,
+}
+
+impl MatrixDiff {
+    /// Returns if both matrices are the same
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty() && self.parameters.is_empty() && self.depth_modes.is_none()
+    }
+}
+
+impl fmt::Display for MatrixDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "The matrices are the same");
+        }
+        write!(f, "Max delta: {}", self.max_delta)?;
+        for cell in &self.cells {
+            write!(f, "\n[{}][{}]: {} != {}", cell.row, cell.column, cell.value, cell.other)?;
+        }
+        for parameter in &self.parameters {
+            write!(f, "\n{}: {} != {}", parameter.name, parameter.value, parameter.other)?;
+        }
+        if let Some((depth_mode, other)) = self.depth_modes {
+            write!(f, "\ndepth_mode: {depth_mode:?} != {other:?}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Deref for ProjectionMatrix {
+    type Target = Matrix;
+
+    fn deref(&self) -> &Matrix {
+        &self.matrix
+    }
+}
+
+impl AsRef<Matrix> for ProjectionMatrix {
+    fn as_ref(&self) -> &Matrix {
+        &self.matrix
+    }
+}
+
+/// Borrows the cells of the matrix, without the parameters it was built with
+impl Borrow<Matrix> for ProjectionMatrix {
+    fn borrow(&self) -> &Matrix {
+        &self.matrix
+    }
+}
+
+impl From<ProjectionMatrix> for Matrix {
+    fn from(projection: ProjectionMatrix) -> Self {
+        projection.matrix
+    }
+}
+
+/// Wraps a plain matrix with the [`DepthMode::Standard`], like [`ProjectionMatrix::from_matrix`]
+impl From<Matrix> for ProjectionMatrix {
+    fn from(matrix: Matrix) -> Self {
+        Self::from_matrix(matrix, DepthMode::Standard)
+    }
+}
+
+impl PartialEq for ProjectionMatrix {
+    fn eq(&self, other: &Self) -> bool {
+        self.bits() == other.bits() && self.depth_mode == other.depth_mode
+    }
+}
+
+impl Eq for ProjectionMatrix {}
+
+impl Hash for ProjectionMatrix {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.bits().hash(state);
+        self.depth_mode.hash(state);
+    }
+}
+
+/// The cells are compared like the ones of the plain matrices, with the float equality
+impl PartialEq<Matrix> for ProjectionMatrix {
+    fn eq(&self, other: &Matrix) -> bool {
+        self.matrix == *other
+    }
+}
+
+impl PartialEq<ProjectionMatrix> for Matrix {
+    fn eq(&self, other: &ProjectionMatrix) -> bool {
+        other == self
+    }
+}
+
+/// Returns the field of view in degrees and the aspect ratio derived from the scales of the x and y axes
+fn scales_parameters(matrix: &Matrix) -> (f32, f32) {
+    let [[x_scale, ..], [_, y_scale, ..], ..] = *matrix;
+    ((1.0 / y_scale).atan().to_degrees() * 2.0, x_scale / y_scale)
+}
+
+/// Returns the bit patterns of the cells of the matrix
+fn matrix_bits(matrix: &Matrix) -> [[u32; 4]; 4] {
+    matrix.map(|row| row.map(f32::to_bits))
+}
+
+#[test]
+fn matrix_parameters() {
+    let matrix = crate::ProjectionMatrixBuilder::new().set_near(1.0).set_far(50.0).set_fov(60.0).set_width(800)
+        .set_height(600).set_depth_mode(DepthMode::WBuffer).build();
+    assert_eq!((60.0, 1.0, 50.0), (matrix.fov(), matrix.near(), matrix.far()));
+    assert_eq!(800.0 / 600.0, matrix.aspect_ratio());
+    assert_eq!(DepthMode::WBuffer, matrix.depth_mode());
+    assert_eq!(1.0, matrix[2][3]);
+    assert_eq!(matrix.matrix(), matrix);
+    assert_eq!(matrix, *matrix);
+}
+
+#[test]
+fn bit_exact_hashing() {
+    use std::collections::HashMap;
+    let builder = crate::ProjectionMatrixBuilder::new().set_near(1.0);
+    let mut cache = HashMap::new();
+    cache.insert(builder.build(), "frustum");
+    assert_eq!(Some(&"frustum"), cache.get(&builder.build()));
+    assert_eq!(None, cache.get(&builder.set_fov(60.0).build()));
+
+    let mut negative_zero = builder.build();
+    negative_zero.matrix[3][0] = -0.0;
+    assert_ne!(builder.build(), negative_zero);
+    let mut nan = builder.build();
+    nan.matrix[0][1] = f32::NAN;
+    assert_eq!(nan, nan);
+}
+
+#[test]
+fn matrix_diff() {
+    let builder = crate::ProjectionMatrixBuilder::new().set_near(1.0).set_far(100.0);
+    assert!(builder.build().diff(&builder.build()).is_empty());
+    assert_eq!("The matrices are the same", builder.build().diff(&builder.build()).to_string());
+
+    let diff = builder.build().diff(&builder.set_far(200.0).set_depth_mode(DepthMode::WBuffer).build());
+    assert_eq!(vec![2, 3], diff.cells.iter().map(|cell| cell.row).collect::<Vec<_>>());
+    assert_eq!(vec![ParameterDiff { name: "far", value: 100.0, other: 200.0 }], diff.parameters);
+    assert_eq!(Some((DepthMode::Standard, DepthMode::WBuffer)), diff.depth_modes);
+    assert_eq!(9900.0 - 1.0 / 199.0, diff.max_delta);
+    assert!(diff.to_string().contains("far: 100 != 200"));
+}
+
+#[test]
+fn explain_matrix() {
+    let matrix = crate::ProjectionMatrixBuilder::new().set_near(1.0).set_far(2.0).build();
+    let explanation = matrix.explain();
+    let lines: Vec<&str> = explanation.lines().collect();
+    assert_eq!(5, lines.len());
+    assert_eq!("row 1 [0.0, 1.0, 0.0, 0.0]: y-scale", lines[1]);
+    assert_eq!("row 2 [0.0, 0.0, 2.0, 1.0]: x-offset, y-offset, depth scale, w coupling", lines[2]);
+    assert_eq!("row 3 [0.0, 0.0, -2.0, 0.0]: depth offset", lines[3]);
+    assert!(lines[4].starts_with("fov: 90"));
+    assert!(lines[4].ends_with("near: 1, far: 2, depth mode: Standard"));
+}
+
+#[test]
+fn partial_updates() {
+    let builder = crate::ProjectionMatrixBuilder::new().set_near(1.0).set_far(100.0).set_overscan(10.0);
+    let mut matrix = builder.build();
+    matrix.update_resolution(800, 600);
+    let expected = builder.set_width(800).set_height(600);
+    crate::math::assert_matrix_eq(&expected.build(), &matrix);
+    assert_eq!(800.0 / 600.0, matrix.aspect_ratio());
+
+    matrix.update_fov(60.0);
+    crate::math::assert_matrix_eq(&expected.set_fov(60.0).build(), &matrix);
+    assert_eq!(60.0, matrix.fov());
+}
+
+#[test]
+fn matrix_interpolation() {
+    let from = crate::ProjectionMatrixBuilder::new().set_near(1.0).set_far(100.0).set_width(1280).set_height(720)
+        .set_safe_area(0.1, 0.0, 0.05, 0.0);
+    let to = from.set_near(0.5).set_far(20.0).set_fov(60.0).set_width(720).set_safe_area(0.0, 0.2, -0.1, 0.1)
+        .set_depth_mode(DepthMode::WBuffer);
+    for t in [0.0, 0.25, 0.75, 1.0] {
+        let matrix = from.build().interpolate(&to.build(), t);
+        let expected = from.interpolate(&to, t).build();
+        crate::math::assert_matrix_eq(&expected, &matrix);
+        assert_eq!(expected.depth_mode(), matrix.depth_mode());
+        assert!(expected.safe_area().iter().zip(matrix.safe_area()).all(|(a, b)| (a - b).abs() < 0.0001));
+    }
+}
+
+#[test]
+#[should_panic]
+fn update_empty_resolution() {
+    crate::ProjectionMatrixBuilder::new().build().update_resolution(0, 600);
+}
+
+#[test]
+fn raw_matrix_conversions() {
+    fn first_cell(matrix: impl AsRef<Matrix>) -> f32 {
+        matrix.as_ref()[0][0]
+    }
+    let projection = crate::ProjectionMatrixBuilder::new().set_width(720).set_near(1.0).set_far(4.0).set_fov(60.0)
+        .build();
+    let raw: Matrix = projection.into();
+    assert_eq!(raw[0][0], first_cell(projection));
+    assert_eq!(&raw, Borrow::<Matrix>::borrow(&projection));
+    // like the plain matrices, the signed zeros are equal
+    let mut signed = raw;
+    signed[0][1] = -0.0;
+    assert!(projection == signed && *projection == signed);
+
+    let wrapped = ProjectionMatrix::from(raw);
+    assert_eq!(raw, wrapped);
+    assert!((60.0 - wrapped.fov()).abs() < 0.0001 && (1.0 - wrapped.aspect_ratio()).abs() < 0.0001);
+    assert_eq!(DepthMode::Standard, wrapped.depth_mode());
+    assert!((1.0 - wrapped.near()).abs() < 0.0001 && (4.0 - wrapped.far()).abs() < 0.0001);
+}
+
+#[test]
+fn raw_matrix_clips() {
+    let builder = crate::ProjectionMatrixBuilder::new().set_safe_area(0.1, 0.0, 0.0, 0.05);
+    for (near, far) in [(0.1, 1000.0), (1.0, 100.0), (0.001, 10.0), (99.0, 100.0), (0.01, 100_000.0)] {
+        for depth_mode in [DepthMode::Standard, DepthMode::WBuffer] {
+            let built = builder.set_far(far).set_near(near).set_depth_mode(depth_mode).build();
+            let wrapped = ProjectionMatrix::from_matrix(built.matrix(), depth_mode);
+            assert!((near - wrapped.near()).abs() < near * 0.001, "{near} != {} ({depth_mode:?})", wrapped.near());
+            assert!((far - wrapped.far()).abs() < far * 0.0001, "{far} != {} ({depth_mode:?})", wrapped.far());
+            crate::math::assert_matrix_eq(&built, &wrapped.interpolate(&wrapped, 0.0));
+        }
+    }
+    let built = builder.set_near(1.0).set_far(100.0).build();
+    let wrapped = ProjectionMatrix::from(built.matrix());
+    assert!((1.0 - wrapped.near()).abs() < 0.0001 && (100.0 - wrapped.far()).abs() < 0.0001);
+}
@@ -0,0 +1,58 @@
+//! Export of the matrices as NumPy `.npy` files, to load them in Python analysis scripts with `numpy.load`
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::math::{self, Matrix};
+
+/// Magic string and version 1.0 of the format
+const MAGIC: &[u8] = b"\x93NUMPY\x01\x00";
+/// Description of an array of four by four little-endian floats in row-major order
+const HEADER: &str = "{'descr': '<f4', 'fortran_order': False, 'shape': (4, 4), }";
+/// Alignment of the data after the header
+const ALIGNMENT: usize = 64;
+
+/// Saves the matrix into the file as a four by four float32 array
+pub fn write_npy(matrix: &Matrix, path: impl AsRef<Path>) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_npy_to(matrix, &mut writer)?;
+    writer.flush()
+}
+
+/// Writes the matrix into the writer as a four by four float32 array in the `.npy` format
+pub fn write_npy_to<W: Write>(matrix: &Matrix, writer: &mut W) -> io::Result<()> {
+    // the header is padded with spaces and ends with a new line, the length is a little-endian u16
+    let unpadded = MAGIC.len() + 2 + HEADER.len() + 1;
+    let header_length = HEADER.len() + 1 + (ALIGNMENT - unpadded % ALIGNMENT) % ALIGNMENT;
+    writer.write_all(MAGIC)?;
+    writer.write_all(&(header_length as u16).to_le_bytes())?;
+    write!(writer, "{HEADER:<width$}", width = header_length - 1)?;
+    writer.write_all(b"\n")?;
+    writer.write_all(&math::to_le_bytes(matrix))
+}
+
+#[test]
+fn npy_layout() {
+    let matrix = crate::ProjectionMatrixBuilder::new().set_near(1.0).build();
+    let mut bytes = Vec::new();
+    write_npy_to(&matrix, &mut bytes).unwrap();
+
+    assert_eq!(128 + 64, bytes.len());
+    assert_eq!(MAGIC, &bytes[..8]);
+    assert_eq!(118, u16::from_le_bytes([bytes[8], bytes[9]]));
+    assert_eq!(b'\n', bytes[127]);
+    assert!(std::str::from_utf8(&bytes[10..127]).unwrap().starts_with(HEADER));
+    assert_eq!(matrix[0][0].to_le_bytes(), bytes[128..132]);
+    assert_eq!(matrix[2][3].to_le_bytes(), bytes[128 + 44..128 + 48]);
+}
+
+#[test]
+fn npy_file() {
+    let path = std::env::temp_dir().join(format!("ferrux_projection_matrix_{}.npy", std::process::id()));
+    write_npy(&crate::math::identity(), &path).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(192, bytes.len());
+    assert_eq!(1.0f32.to_le_bytes(), bytes[128..132]);
+}
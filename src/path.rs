@@ -0,0 +1,153 @@
+//! Keyframed camera paths, for cutscenes and turntable renders
+
+use crate::math::{self, Matrix, Vec3};
+use crate::{ProjectionMatrixBuilder, Quaternion, MAX_SUPPORTED_FOV, MIN_SUPPORTED_FOV};
+
+/// Interpolation between the keyframes of a path
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Straight lines between keyframes
+    Linear,
+    /// Smooth Catmull-Rom spline going through every keyframe
+    CatmullRom,
+}
+
+/// State of the camera at a point in time
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Keyframe {
+    /// Time of the keyframe, in any unit
+    pub time: f32,
+    /// Position of the camera
+    pub position: Vec3,
+    /// Rotation of the camera from looking along the z-axis with the y-axis up
+    pub orientation: Quaternion,
+    /// Field of view of the camera in degrees
+    pub fov: f32,
+}
+
+impl Keyframe {
+    /// Returns the view matrix of the camera
+    pub fn view(&self) -> Matrix {
+        let axis = |axis: Vec3| self.orientation.rotate(axis);
+        math::view_matrix(self.position, axis([1.0, 0.0, 0.0]), axis([0.0, 1.0, 0.0]), axis([0.0, 0.0, 1.0]))
+    }
+}
+
+/// Path of a camera through a sequence of keyframes
+#[derive(Clone, Debug, PartialEq)]
+pub struct CameraPath {
+    keyframes: Vec<Keyframe>,
+    interpolation: Interpolation,
+}
+
+impl CameraPath {
+    /// Returns an empty path with the given interpolation
+    pub fn new(interpolation: Interpolation) -> Self {
+        Self { keyframes: Vec::new(), interpolation }
+    }
+
+    /// Adds the keyframe to the path, keeping them sorted by time
+    pub fn add_keyframe(mut self, keyframe: Keyframe) -> Self {
+        let index = self.keyframes.partition_point(|other| other.time <= keyframe.time);
+        self.keyframes.insert(index, keyframe);
+        self
+    }
+
+    /// Returns the keyframes of the path sorted by time
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    /// Returns the state of the camera at the given time, or `None` if the path is empty. Before the first keyframe
+    /// and after the last one the camera stays still. The fov overshooting the spline between keyframes is clamped
+    /// to the supported [0.1, 179] range.
+    pub fn sample(&self, time: f32) -> Option<Keyframe> {
+        let (first, last) = (self.keyframes.first()?, self.keyframes.last()?);
+        if time <= first.time {
+            return Some(Keyframe { time, ..*first });
+        }
+        if time >= last.time {
+            return Some(Keyframe { time, ..*last });
+        }
+        let next = self.keyframes.partition_point(|keyframe| keyframe.time <= time);
+        let (a, b) = (&self.keyframes[next - 1], &self.keyframes[next]);
+        let t = (time - a.time) / (b.time - a.time);
+
+        let (position, fov) = match self.interpolation {
+            Interpolation::Linear => (math::lerp(a.position, b.position, t), a.fov + (b.fov - a.fov) * t),
+            Interpolation::CatmullRom => {
+                // the ends are repeated to get the neighbours of the first and last keyframes
+                let before = &self.keyframes[next.saturating_sub(2)];
+                let after = &self.keyframes[(next + 1).min(self.keyframes.len() - 1)];
+                let position = [0, 1, 2].map(|i| {
+                    catmull_rom(before.position[i], a.position[i], b.position[i], after.position[i], t)
+                });
+                let fov = catmull_rom(before.fov, a.fov, b.fov, after.fov, t);
+                (position, fov.clamp(MIN_SUPPORTED_FOV, MAX_SUPPORTED_FOV))
+            }
+        };
+        Some(Keyframe { time, position, orientation: a.orientation.slerp(&b.orientation, t), fov })
+    }
+
+    /// Returns the combined view and projection matrix of the camera at the given time, using its field of view in
+    /// the projection, or `None` if the path is empty
+    pub fn view_projection(&self, time: f32, projection: &ProjectionMatrixBuilder) -> Option<Matrix> {
+        let keyframe = self.sample(time)?;
+        Some(math::multiply(&keyframe.view(), &projection.set_fov(keyframe.fov).build()))
+    }
+}
+
+/// Catmull-Rom spline between `b` and `c`
+fn catmull_rom(a: f32, b: f32, c: f32, d: f32, t: f32) -> f32 {
+    let (t2, t3) = (t * t, t * t * t);
+    0.5 * (2.0 * b + (c - a) * t + (2.0 * a - 5.0 * b + 4.0 * c - d) * t2 + (3.0 * b - a - 3.0 * c + d) * t3)
+}
+
+#[cfg(test)]
+fn keyframe(time: f32, position: Vec3, yaw: f32, fov: f32) -> Keyframe {
+    Keyframe { time, position, orientation: Quaternion::from_euler(yaw, 0.0, 0.0), fov }
+}
+
+#[test]
+fn linear_path() {
+    let path = CameraPath::new(Interpolation::Linear)
+        .add_keyframe(keyframe(2.0, [10.0, 0.0, 0.0], 90.0, 60.0))
+        .add_keyframe(keyframe(0.0, [0.0; 3], 0.0, 90.0));
+    assert_eq!(0.0, path.keyframes()[0].time);
+
+    let middle = path.sample(1.0).unwrap();
+    assert_eq!([5.0, 0.0, 0.0], middle.position);
+    assert_eq!(75.0, middle.fov);
+    let forward = middle.orientation.rotate([0.0, 0.0, 1.0]);
+    assert!((forward[0] - forward[2]).abs() < 0.0001);
+
+    assert_eq!([0.0; 3], path.sample(-1.0).unwrap().position);
+    assert_eq!([10.0, 0.0, 0.0], path.sample(3.0).unwrap().position);
+    assert_eq!(None, CameraPath::new(Interpolation::Linear).sample(0.0));
+}
+
+#[test]
+fn catmull_rom_path() {
+    let path = CameraPath::new(Interpolation::CatmullRom)
+        .add_keyframe(keyframe(0.0, [0.0; 3], 0.0, 90.0))
+        .add_keyframe(keyframe(1.0, [1.0, 1.0, 0.0], 0.0, 90.0))
+        .add_keyframe(keyframe(2.0, [2.0, 0.0, 0.0], 0.0, 90.0));
+    // it goes through the keyframes
+    assert_eq!([1.0, 1.0, 0.0], path.sample(1.0).unwrap().position);
+    // and curves around the middle one
+    assert!(path.sample(0.9).unwrap().position[1] > 0.9);
+    assert!(path.sample(1.1).unwrap().position[1] > 0.9);
+
+    let view_projection = path.view_projection(1.0, &ProjectionMatrixBuilder::new().set_near(0.1)).unwrap();
+    let [x, y, _, w] = math::transform_point([1.0, 1.0, 5.0], &view_projection);
+    assert!((x / w).abs() < 0.0001 && (y / w).abs() < 0.0001);
+
+    // the spline between the widest keyframes overshoots the supported fov
+    let path = CameraPath::new(Interpolation::CatmullRom)
+        .add_keyframe(keyframe(0.0, [0.0; 3], 0.0, 10.0))
+        .add_keyframe(keyframe(1.0, [0.0; 3], 0.0, 179.0))
+        .add_keyframe(keyframe(2.0, [0.0; 3], 0.0, 179.0))
+        .add_keyframe(keyframe(3.0, [0.0; 3], 0.0, 10.0));
+    assert_eq!(MAX_SUPPORTED_FOV, path.sample(1.5).unwrap().fov);
+    assert!(path.view_projection(1.5, &ProjectionMatrixBuilder::new()).is_some());
+}
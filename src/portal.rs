@@ -0,0 +1,128 @@
+//! Rendering through portals and mirrors, with virtual cameras behind the portal quads and their near clips moved
+//! onto the portal planes
+
+use crate::math::{self, Matrix, Vec3};
+use crate::{Plane, ProjectionMatrix, ProjectionMatrixBuilder};
+
+impl Plane {
+    /// Returns the matrix reflecting the points across the plane, the transformation of a mirror on it
+    pub fn reflection(&self) -> Matrix {
+        let [x, y, z] = self.normal;
+        let n = [x, y, z, 0.0];
+        let mut matrix = math::identity();
+        for (i, row) in matrix.iter_mut().enumerate().take(3) {
+            for (j, cell) in row.iter_mut().enumerate().take(3) {
+                *cell -= 2.0 * n[i] * n[j];
+            }
+        }
+        matrix[3] = [-2.0 * self.distance * x, -2.0 * self.distance * y, -2.0 * self.distance * z, 1.0];
+        matrix
+    }
+}
+
+impl ProjectionMatrixBuilder {
+    /// Builds the projection matrix with the near clip replaced by the given view space plane, so nothing on its
+    /// negative side is drawn while the far clip keeps as much depth precision as possible. The depth of the points
+    /// on the plane is `0.0` in normalized device coordinates.
+    ///
+    /// # Panic
+    /// Like [`ProjectionMatrixBuilder::build`], or if the plane goes through the camera
+    pub fn oblique(&self, plane: &Plane) -> ProjectionMatrix {
+        if plane.distance.abs() < f32::EPSILON {
+            panic!("The oblique near clip can't go through the camera");
+        }
+        let mut matrix = self.build().matrix();
+        // the corner of the far clip on the positive side of the plane keeps a depth of one
+        let sign = |value: f32| if value < 0.0 { -1.0 } else { 1.0 };
+        let corner = [
+            self.far * (sign(plane.normal[0]) - matrix[2][0]) / matrix[0][0],
+            self.far * (sign(plane.normal[1]) - matrix[2][1]) / matrix[1][1],
+            self.far,
+            1.0,
+        ];
+        let coefficients = [plane.normal[0], plane.normal[1], plane.normal[2], plane.distance];
+        let w: f32 = (0..4).map(|i| corner[i] * matrix[i][3]).sum();
+        let scale = w / (0..4).map(|i| coefficients[i] * corner[i]).sum::<f32>();
+        for (row, coefficient) in matrix.iter_mut().zip(coefficients) {
+            row[2] = coefficient * scale;
+        }
+        self.with_parameters(matrix)
+    }
+
+    /// Returns the view and projection matrices rendering what is seen through the portal quad, which leads to the
+    /// world transformed by `transform`: the one of the destination portal for portals or the
+    /// [`Plane::reflection`] of the mirror plane for mirrors. Mirrors reverse the winding of the triangles. The
+    /// near clip is moved onto the portal, so nothing between the virtual camera and the portal is drawn. The
+    /// resulting view can go through further portals for recursive ones.
+    ///
+    /// Returns `None` if the camera is on the portal plane. The portals are seen from both sides.
+    ///
+    /// # Panic
+    /// Like [`ProjectionMatrixBuilder::build`], or if the transform can't be inverted
+    pub fn through_portal(&self, view: &Matrix, portal: &[Vec3; 4], transform: &Matrix)
+        -> Option<(Matrix, ProjectionMatrix)> {
+        let [a, b, c, _] = portal.map(|corner| {
+            let [x, y, z, _] = math::transform_point(corner, view);
+            [x, y, z]
+        });
+        let mut plane = Plane::from_point_normal(a, math::cross(math::sub(b, a), math::sub(c, a)));
+        // the camera, at the view space origin, must be on the negative side
+        if plane.distance > 0.0 {
+            plane = Plane { normal: math::scale(plane.normal, -1.0), distance: -plane.distance };
+        }
+        if plane.distance.abs() < f32::EPSILON || !plane.distance.is_finite() {
+            return None;
+        }
+        let inverse_transform = math::inverse(transform).expect("The portal transform must be invertible");
+        Some((math::multiply(&inverse_transform, view), self.oblique(&plane)))
+    }
+}
+
+#[test]
+fn plane_reflection() {
+    let reflection = Plane::from_point_normal([0.0, 0.0, 5.0], [0.0, 0.0, -1.0]).reflection();
+    assert_eq!([1.0, 2.0, 8.0, 1.0], math::transform_point([1.0, 2.0, 2.0], &reflection));
+    math::assert_matrix_eq(&math::identity(), &math::multiply(&reflection, &reflection));
+}
+
+#[test]
+fn oblique_near_clip() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_far(100.0);
+    let plane = Plane::from_point_normal([0.0, 0.0, 10.0], [0.0, -1.0, 1.0]);
+    let projection = builder.oblique(&plane);
+    let depth = |point: Vec3| {
+        let clip = math::transform_point(point, &projection);
+        clip[2] / clip[3]
+    };
+    assert!(depth([0.0, 0.0, 10.0]).abs() < 0.0001);
+    assert!(depth([0.0, 5.0, 15.0]).abs() < 0.0001);
+    assert!(depth([0.0, 0.0, 20.0]) > 0.0 && depth([0.0, 0.0, 20.0]) < 1.0);
+    assert!(depth([0.0, 0.0, 5.0]) < 0.0);
+    // the x and y axes are untouched
+    let (clip, oblique) = (builder.build(), projection.matrix());
+    assert!((0..4).all(|i| [0, 1, 3].iter().all(|&j| clip[i][j] == oblique[i][j])));
+}
+
+#[test]
+fn mirror() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_far(100.0);
+    let view = math::identity();
+    let mirror = [[-5.0, -5.0, 5.0], [5.0, -5.0, 5.0], [5.0, 5.0, 5.0], [-5.0, 5.0, 5.0]];
+    let reflection = Plane::from_point_normal([0.0, 0.0, 5.0], [0.0, 0.0, 1.0]).reflection();
+    let (mirrored_view, projection) = builder.through_portal(&view, &mirror, &reflection).unwrap();
+    let view_projection = math::multiply(&mirrored_view, &projection);
+
+    // the points in front of the mirror are seen behind it
+    let reflected = math::transform_point([1.0, 0.0, 2.0], &view_projection);
+    let direct = math::transform_point([1.0, 0.0, 8.0], &builder.build());
+    assert!((reflected[0] / reflected[3] - direct[0] / direct[3]).abs() < 0.0001);
+    assert!(reflected[2] > 0.0);
+    // and the points behind the mirror are clipped
+    assert!(math::transform_point([1.0, 0.0, 8.0], &view_projection)[2] < 0.0);
+
+    let behind = math::look_at([0.0, 0.0, 10.0], [0.0, 0.0, 0.0], [0.0, 1.0, 0.0]);
+    let (_, projection) = builder.through_portal(&behind, &mirror, &reflection).unwrap();
+    assert!(math::transform_point([0.0, 0.0, 8.0], &projection)[2] > 0.0);
+    let on_plane = math::look_at([0.0, 0.0, 5.0], [0.0, 0.0, 20.0], [0.0, 1.0, 0.0]);
+    assert_eq!(None, builder.through_portal(&on_plane, &mirror, &reflection));
+}
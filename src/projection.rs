@@ -0,0 +1,253 @@
+//! Common interface of the projections, to write code generic over the kind of projection
+
+use crate::clip::{self, NdcPoint};
+use crate::math::{self, Matrix, Vec3};
+use crate::{Frustum, ProjectionMatrixBuilder};
+
+/// Projection from the view space, looking down the positive z-axis, into the clip space
+pub trait Projection {
+    /// Returns the projection matrix
+    fn matrix(&self) -> Matrix;
+
+    /// Returns the position of the near clip in the z-axis
+    fn near(&self) -> f32;
+
+    /// Returns the position of the far clip in the z-axis
+    fn far(&self) -> f32;
+
+    /// Returns the world space frustum of the projection seen through the view matrix
+    fn frustum(&self, view: &Matrix) -> Frustum {
+        Frustum::from_matrix(view, &self.matrix(), self.near(), self.far())
+    }
+
+    /// Projects the view space point into normalized device coordinates, or `None` if the point is behind the camera
+    fn project(&self, point: Vec3) -> Option<NdcPoint> {
+        clip::clip_to_ndc(math::transform_point(point, &self.matrix()))
+    }
+
+    /// Returns the view space point at the given depth projected into the `x` and `y` normalized device coordinates
+    fn unproject(&self, x: f32, y: f32, depth: f32) -> Vec3;
+}
+
+/// Orthographic projection of the box between the given planes of the view space into the clip space. The depth is
+/// mapped from `0.0` in the near clip to `1.0` in the far clip.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Orthographic {
+    /// Position of the left plane in the x-axis
+    pub left: f32,
+    /// Position of the right plane in the x-axis
+    pub right: f32,
+    /// Position of the bottom plane in the y-axis
+    pub bottom: f32,
+    /// Position of the top plane in the y-axis
+    pub top: f32,
+    /// Position of the near clip in the z-axis
+    pub near: f32,
+    /// Position of the far clip in the z-axis
+    pub far: f32,
+}
+
+impl Orthographic {
+    /// Returns the orthographic projection of the box between the given planes
+    ///
+    /// # Panic
+    /// If any of the planes is not bigger than its opposite one
+    pub fn new(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Self {
+        if right <= left || top <= bottom || far <= near {
+            panic!("The right, top and far planes must be bigger than the left, bottom and near ones");
+        }
+        Self { left, right, bottom, top, near, far }
+    }
+
+    /// Returns the orthographic projection of a view of the given size centered in the z-axis
+    ///
+    /// # Panic
+    /// If the size is not positive or the far clip is not bigger than the near one
+    pub fn from_size(width: f32, height: f32, near: f32, far: f32) -> Self {
+        Self::new(-width * 0.5, width * 0.5, -height * 0.5, height * 0.5, near, far)
+    }
+}
+
+impl Projection for Orthographic {
+    fn matrix(&self) -> Matrix {
+        let (width, height, depth) = (self.right - self.left, self.top - self.bottom, self.far - self.near);
+        [
+            [2.0 / width, 0.0, 0.0, 0.0],
+            [0.0, 2.0 / height, 0.0, 0.0],
+            [0.0, 0.0, 1.0 / depth, 0.0],
+            [-(self.right + self.left) / width, -(self.top + self.bottom) / height, -self.near / depth, 1.0],
+        ]
+    }
+
+    fn near(&self) -> f32 {
+        self.near
+    }
+
+    fn far(&self) -> f32 {
+        self.far
+    }
+
+    fn unproject(&self, x: f32, y: f32, depth: f32) -> Vec3 {
+        let lerp = |a: f32, b: f32, ndc: f32| a + (b - a) * (ndc + 1.0) * 0.5;
+        [lerp(self.left, self.right, x), lerp(self.bottom, self.top, y), depth]
+    }
+}
+
+impl Projection for ProjectionMatrixBuilder {
+    fn matrix(&self) -> Matrix {
+        self.build().matrix()
+    }
+
+    fn near(&self) -> f32 {
+        self.near
+    }
+
+    fn far(&self) -> f32 {
+        self.far
+    }
+
+    fn unproject(&self, x: f32, y: f32, depth: f32) -> Vec3 {
+        // the clip space position is shifted by the safe area and divided by the depth, copied into w
+        let matrix = self.build();
+        [(x - matrix[2][0]) * depth / matrix[0][0], (y - matrix[2][1]) * depth / matrix[1][1], depth]
+    }
+}
+
+/// Projection of any kind, to switch between them at runtime without generics
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProjectionKind {
+    /// Perspective projection defined by the builder
+    Perspective(ProjectionMatrixBuilder),
+    /// Orthographic projection
+    Orthographic(Orthographic),
+    /// Projection with the given matrix and clips
+    Custom {
+        /// Projection matrix
+        matrix: Matrix,
+        /// Position of the near clip in the z-axis
+        near: f32,
+        /// Position of the far clip in the z-axis
+        far: f32,
+    },
+}
+
+impl ProjectionKind {
+    /// Returns if the projection is a perspective one
+    pub fn is_perspective(&self) -> bool {
+        matches!(self, Self::Perspective(_))
+    }
+
+    /// Returns if the projection is an orthographic one
+    pub fn is_orthographic(&self) -> bool {
+        matches!(self, Self::Orthographic(_))
+    }
+}
+
+impl Projection for ProjectionKind {
+    fn matrix(&self) -> Matrix {
+        match self {
+            Self::Perspective(builder) => builder.matrix(),
+            Self::Orthographic(orthographic) => orthographic.matrix(),
+            Self::Custom { matrix, .. } => *matrix,
+        }
+    }
+
+    fn near(&self) -> f32 {
+        match self {
+            Self::Perspective(builder) => builder.near,
+            Self::Orthographic(orthographic) => orthographic.near,
+            Self::Custom { near, .. } => *near,
+        }
+    }
+
+    fn far(&self) -> f32 {
+        match self {
+            Self::Perspective(builder) => builder.far,
+            Self::Orthographic(orthographic) => orthographic.far,
+            Self::Custom { far, .. } => *far,
+        }
+    }
+
+    fn unproject(&self, x: f32, y: f32, depth: f32) -> Vec3 {
+        match self {
+            Self::Perspective(builder) => builder.unproject(x, y, depth),
+            Self::Orthographic(orthographic) => orthographic.unproject(x, y, depth),
+            Self::Custom { matrix: m, .. } => {
+                // solves the view space x and y making the perspective division land in the coordinates
+                let row = |ndc: f32, j: usize| {
+                    [m[0][j] - ndc * m[0][3], m[1][j] - ndc * m[1][3],
+                        ndc * (m[2][3] * depth + m[3][3]) - m[2][j] * depth - m[3][j]]
+                };
+                let ([a, b, e], [c, d, f]) = (row(x, 0), row(y, 1));
+                let determinant = a * d - b * c;
+                [(e * d - b * f) / determinant, (a * f - e * c) / determinant, depth]
+            }
+        }
+    }
+}
+
+impl From<ProjectionMatrixBuilder> for ProjectionKind {
+    fn from(builder: ProjectionMatrixBuilder) -> Self {
+        Self::Perspective(builder)
+    }
+}
+
+impl From<Orthographic> for ProjectionKind {
+    fn from(orthographic: Orthographic) -> Self {
+        Self::Orthographic(orthographic)
+    }
+}
+
+#[cfg(test)]
+fn round_trip(projection: &impl Projection, point: Vec3) {
+    let ndc = projection.project(point).unwrap();
+    let unprojected = projection.unproject(ndc.x, ndc.y, point[2]);
+    assert!((0..3).all(|i| (point[i] - unprojected[i]).abs() < 0.001), "{point:?} != {unprojected:?}");
+}
+
+#[test]
+fn orthographic_projection() {
+    let orthographic = Orthographic::new(-10.0, 30.0, -5.0, 5.0, 1.0, 101.0);
+    let ndc = orthographic.project([30.0, -5.0, 51.0]).unwrap();
+    assert_eq!((1.0, -1.0, 0.5), (ndc.x, ndc.y, ndc.z));
+    round_trip(&orthographic, [3.0, 2.0, 20.0]);
+    assert_eq!(Orthographic::new(-2.0, 2.0, -1.0, 1.0, 0.0, 10.0), Orthographic::from_size(4.0, 2.0, 0.0, 10.0));
+
+    let frustum = orthographic.frustum(&math::identity());
+    assert!(frustum.contains_point([29.0, 4.0, 100.0]));
+    assert!(!frustum.contains_point([31.0, 0.0, 50.0]));
+    assert!(!frustum.contains_point([0.0, 0.0, 0.5]));
+}
+
+#[test]
+fn perspective_projection() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_far(100.0).set_safe_area(0.1, 0.0, 0.0, 0.2);
+    round_trip(&builder, [3.0, -2.0, 20.0]);
+    assert_eq!(Frustum::new(&math::identity(), &builder), builder.frustum(&math::identity()));
+    assert_eq!(None, builder.project([0.0, 0.0, -1.0]));
+}
+
+#[test]
+#[should_panic]
+fn empty_orthographic() {
+    Orthographic::new(1.0, 1.0, -1.0, 1.0, 0.0, 1.0);
+}
+
+#[test]
+fn projection_kind() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_far(100.0).set_safe_area(0.1, 0.0, 0.0, 0.2);
+    let orthographic = Orthographic::from_size(40.0, 20.0, 1.0, 100.0);
+    let kinds = [
+        ProjectionKind::from(builder),
+        ProjectionKind::from(orthographic),
+        ProjectionKind::Custom { matrix: builder.build().matrix(), near: 1.0, far: 100.0 },
+        ProjectionKind::Custom { matrix: orthographic.matrix(), near: 1.0, far: 100.0 },
+    ];
+    for kind in kinds {
+        assert_eq!((1.0, 100.0), (kind.near(), kind.far()));
+        round_trip(&kind, [3.0, -2.0, 20.0]);
+    }
+    assert!(kinds[0].is_perspective() && !kinds[0].is_orthographic());
+    assert!(kinds[1].is_orthographic() && !kinds[2].is_perspective());
+    assert_eq!(builder.frustum(&math::identity()), kinds[2].frustum(&math::identity()));
+}
@@ -0,0 +1,181 @@
+//! Arrays of overlapping projectors, blending their edges into a single seamless view for installations and
+//! simulators
+
+use crate::math::Matrix;
+use crate::viewport::crop_ndc;
+use crate::ProjectionMatrixBuilder;
+
+const DEFAULT_LAYOUT: (usize, usize) = (1, 1);
+const DEFAULT_OVERLAP: (f32, f32) = (0.0, 0.0);
+
+/// Projector of an array, with the off-axis projection of its part of the view and the blend regions shared with
+/// its neighbours
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Projector {
+    /// Column of the projector in the array, from the left
+    pub column: usize,
+    /// Row of the projector in the array, from the top
+    pub row: usize,
+    /// Projection matrix of the part of the view covered by the projector
+    pub projection: Matrix,
+    /// Left, right, top and bottom widths of the blend regions, as fractions of the projector frame, zero on the
+    /// edges without neighbours
+    pub blend: [f32; 4],
+}
+
+impl Projector {
+    /// Returns the weight of the projector light at the given position of its frame, where `(0.0, 0.0)` is the top
+    /// left corner and `(1.0, 1.0)` the bottom right one. The weight ramps linearly across the blend regions, so the
+    /// weights of the overlapping projectors add up to one.
+    pub fn blend_weight(&self, x: f32, y: f32) -> f32 {
+        let ramp = |distance: f32, width: f32| if width > 0.0 { (distance / width).clamp(0.0, 1.0) } else { 1.0 };
+        let [left, right, top, bottom] = self.blend;
+        ramp(x, left) * ramp(1.0 - x, right) * ramp(y, top) * ramp(1.0 - y, bottom)
+    }
+}
+
+/// Builder of the projections of an array of projectors in a grid, each one covering its own off-axis part of the
+/// view and overlapping its neighbours by the given percentages of its frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProjectorArrayBuilder {
+    projector: ProjectionMatrixBuilder,
+    columns: usize,
+    rows: usize,
+    overlap: (f32, f32),
+}
+
+impl ProjectorArrayBuilder {
+    /// Returns an instance of a builder
+    ///
+    /// # Default
+    /// By default, the array builder sets the following defaults:
+    /// * `projector`: the default [`ProjectionMatrixBuilder`]
+    /// * `layout`: a single projector
+    /// * `overlap`: none
+    pub fn new() -> Self {
+        Self {
+            projector: ProjectionMatrixBuilder::new(),
+            columns: DEFAULT_LAYOUT.0,
+            rows: DEFAULT_LAYOUT.1,
+            overlap: DEFAULT_OVERLAP,
+        }
+    }
+
+    /// Sets the projection of each projector, its field of view and frame size are the ones of a single projector
+    /// and the array extends them
+    pub fn set_projector(mut self, projector: ProjectionMatrixBuilder) -> Self {
+        self.projector = projector;
+        self
+    }
+
+    /// Sets the number of columns and rows of projectors
+    ///
+    /// # Panic
+    /// If there are no columns or no rows
+    pub fn set_layout(mut self, columns: usize, rows: usize) -> Self {
+        if columns == 0 || rows == 0 {
+            panic!("The projector array needs at least one column and one row");
+        }
+        self.columns = columns;
+        self.rows = rows;
+        self
+    }
+
+    /// Sets the percentages of the frame width and height of each projector overlapping its neighbours
+    ///
+    /// # Panic
+    /// If any of the percentages is not within the [0, 100) range
+    pub fn set_overlap(mut self, horizontal: f32, vertical: f32) -> Self {
+        if !(0.0..100.0).contains(&horizontal) || !(0.0..100.0).contains(&vertical) {
+            panic!("The overlap must be a percentage between 0.0 and 100.0");
+        }
+        self.overlap = (horizontal / 100.0, vertical / 100.0);
+        self
+    }
+
+    /// Builds the projectors of the array in rows from the top left one
+    ///
+    /// # Panic
+    /// Like [`ProjectionMatrixBuilder::build`]
+    pub fn build(&self) -> Vec<Projector> {
+        let (horizontal, vertical) = self.overlap;
+        // number of projector frames spanned by the whole view in each axis
+        let span = |count: usize, overlap: f32| count as f32 - (count - 1) as f32 * overlap;
+        let (columns_span, rows_span) = (span(self.columns, horizontal), span(self.rows, vertical));
+        let mut full = self.projector.build().matrix();
+        for cells in full.iter_mut() {
+            cells[0] /= columns_span;
+            cells[1] /= rows_span;
+        }
+
+        let (width, height) = (2.0 / columns_span, 2.0 / rows_span);
+        let mut projectors = Vec::with_capacity(self.columns * self.rows);
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let left = -1.0 + column as f32 * width * (1.0 - horizontal);
+                let top = 1.0 - row as f32 * height * (1.0 - vertical);
+                let blend = [
+                    if column > 0 { horizontal } else { 0.0 },
+                    if column + 1 < self.columns { horizontal } else { 0.0 },
+                    if row > 0 { vertical } else { 0.0 },
+                    if row + 1 < self.rows { vertical } else { 0.0 },
+                ];
+                let projection = crop_ndc(&full, left, left + width, top - height, top);
+                projectors.push(Projector { column, row, projection, blend });
+            }
+        }
+        projectors
+    }
+}
+
+impl Default for ProjectorArrayBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn single_projector() {
+    let projector = ProjectionMatrixBuilder::new().set_near(1.0).set_fov(60.0);
+    let projectors = ProjectorArrayBuilder::new().set_projector(projector).build();
+    assert_eq!(1, projectors.len());
+    crate::math::assert_matrix_eq(&projector.build(), &projectors[0].projection);
+    assert_eq!([0.0; 4], projectors[0].blend);
+}
+
+#[test]
+fn overlapping_projectors() {
+    let projector = ProjectionMatrixBuilder::new().set_near(1.0).set_width(800).set_height(600);
+    let projectors = ProjectorArrayBuilder::new().set_projector(projector).set_layout(3, 2).set_overlap(10.0, 20.0)
+        .build();
+    assert_eq!(6, projectors.len());
+    assert_eq!((2, 0), (projectors[2].column, projectors[2].row));
+    assert_eq!([0.1, 0.1, 0.0, 0.2], projectors[1].blend);
+    assert_eq!([0.1, 0.0, 0.2, 0.0], projectors[5].blend);
+
+    // the projectors keep the scale of a single one
+    assert!((projector.build()[0][0] - projectors[4].projection[0][0]).abs() < 0.0001);
+    // the right edge of a projector falls at 10% of the frame of its neighbour
+    let ndc_x = |projection: &Matrix, x: f32| (x * projection[0][0] + 10.0 * projection[2][0]) / 10.0;
+    let edge = 10.0 * (1.0 - projectors[0].projection[2][0]) / projectors[0].projection[0][0];
+    assert!((1.0 - ndc_x(&projectors[0].projection, edge)).abs() < 0.0001);
+    assert!((-0.8 - ndc_x(&projectors[1].projection, edge)).abs() < 0.0001);
+    assert!(projectors[1].projection[2][0].abs() < 0.0001);
+}
+
+#[test]
+fn blend_weights() {
+    let projectors = ProjectorArrayBuilder::new().set_layout(2, 1).set_overlap(20.0, 0.0).build();
+    let (left, right) = (projectors[0], projectors[1]);
+    assert_eq!(1.0, left.blend_weight(0.5, 0.5));
+    assert_eq!(0.0, left.blend_weight(1.0, 0.5));
+    for x in [0.0, 0.05, 0.1, 0.15, 0.2] {
+        assert!((1.0 - left.blend_weight(0.8 + x, 0.5) - right.blend_weight(x, 0.5)).abs() < 0.0001);
+    }
+}
+
+#[test]
+#[should_panic]
+fn full_overlap() {
+    ProjectorArrayBuilder::new().set_overlap(100.0, 0.0);
+}
@@ -0,0 +1,163 @@
+//! Quaternions to build rotations without pulling a whole math crate
+
+use std::ops::Mul;
+
+use crate::math::{self, Matrix, Vec3};
+
+/// Unit quaternion representing a rotation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quaternion {
+    /// First component of the vector part
+    pub x: f32,
+    /// Second component of the vector part
+    pub y: f32,
+    /// Third component of the vector part
+    pub z: f32,
+    /// Scalar part
+    pub w: f32,
+}
+
+impl Quaternion {
+    /// Returns the quaternion without rotation
+    pub fn identity() -> Self {
+        Self { x: 0.0, y: 0.0, z: 0.0, w: 1.0 }
+    }
+
+    /// Returns the rotation of the given angle, in degrees, around the axis. Around the z-axis the positive angles
+    /// turn the x-axis towards the y-axis, around the x-axis the y-axis towards the z-axis and around the y-axis the
+    /// z-axis towards the x-axis.
+    pub fn from_axis_angle(axis: Vec3, angle: f32) -> Self {
+        let (sin, cos) = (angle.to_radians() * 0.5).sin_cos();
+        let [x, y, z] = math::scale(math::normalize(axis), sin);
+        Self { x, y, z, w: cos }
+    }
+
+    /// Returns the rotation of the given Euler angles, in degrees: the roll around the z-axis, followed by the pitch
+    /// around the x-axis and the yaw around the y-axis
+    pub fn from_euler(yaw: f32, pitch: f32, roll: f32) -> Self {
+        Self::from_axis_angle([0.0, 1.0, 0.0], yaw)
+            * Self::from_axis_angle([1.0, 0.0, 0.0], pitch)
+            * Self::from_axis_angle([0.0, 0.0, 1.0], roll)
+    }
+
+    /// Returns the quaternion scaled to a length of one
+    pub fn normalize(&self) -> Self {
+        let length = (self.x * self.x + self.y * self.y + self.z * self.z + self.w * self.w).sqrt();
+        Self { x: self.x / length, y: self.y / length, z: self.z / length, w: self.w / length }
+    }
+
+    /// Returns the inverse rotation
+    pub fn conjugate(&self) -> Self {
+        Self { x: -self.x, y: -self.y, z: -self.z, w: self.w }
+    }
+
+    /// Returns the vector rotated by the quaternion
+    pub fn rotate(&self, vector: Vec3) -> Vec3 {
+        let axis = [self.x, self.y, self.z];
+        let t = math::scale(math::cross(axis, vector), 2.0);
+        math::add(math::add(vector, math::scale(t, self.w)), math::cross(axis, t))
+    }
+
+    /// Returns the spherical linear interpolation between both rotations, `self` with `t = 0.0` and `other` with
+    /// `t = 1.0`, following the shortest path
+    pub fn slerp(&self, other: &Self, t: f32) -> Self {
+        let mut cos = self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w;
+        let mut other = *other;
+        if cos < 0.0 {
+            cos = -cos;
+            other = Self { x: -other.x, y: -other.y, z: -other.z, w: -other.w };
+        }
+        let (from, to) = if cos > 0.9995 {
+            // too close to divide by the sine, the linear interpolation is precise enough
+            (1.0 - t, t)
+        } else {
+            let angle = cos.acos();
+            let sin = angle.sin();
+            (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+        };
+        Self {
+            x: self.x * from + other.x * to,
+            y: self.y * from + other.y * to,
+            z: self.z * from + other.z * to,
+            w: self.w * from + other.w * to,
+        }
+        .normalize()
+    }
+
+    /// Returns the rotation matrix of the quaternion, to multiply row vectors like the rest of the crate
+    pub fn to_matrix(&self) -> Matrix {
+        let Self { x, y, z, w } = *self;
+        [
+            [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y + w * z), 2.0 * (x * z - w * y), 0.0],
+            [2.0 * (x * y - w * z), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z + w * x), 0.0],
+            [2.0 * (x * z + w * y), 2.0 * (y * z - w * x), 1.0 - 2.0 * (x * x + y * y), 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]
+    }
+}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+/// Composes both rotations, the one on the right is applied first
+impl Mul for Quaternion {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self {
+            x: self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            y: self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            z: self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+            w: self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+        }
+    }
+}
+
+#[cfg(test)]
+fn assert_vec_eq(expected: Vec3, actual: Vec3) {
+    assert!((0..3).all(|i| (expected[i] - actual[i]).abs() < 0.0001), "{expected:?} != {actual:?}");
+}
+
+#[test]
+fn axis_angle_rotation() {
+    let rotation = Quaternion::from_axis_angle([0.0, 0.0, 2.0], 90.0);
+    assert_vec_eq([0.0, 1.0, 0.0], rotation.rotate([1.0, 0.0, 0.0]));
+    assert_vec_eq([1.0, 0.0, 0.0], rotation.conjugate().rotate([0.0, 1.0, 0.0]));
+    assert_vec_eq([1.0, 0.0, 0.0], Quaternion::from_axis_angle([0.0, 1.0, 0.0], 90.0).rotate([0.0, 0.0, 1.0]));
+    assert_vec_eq([0.0, 0.0, 1.0], Quaternion::from_axis_angle([1.0, 0.0, 0.0], 90.0).rotate([0.0, 1.0, 0.0]));
+}
+
+#[test]
+fn euler_rotation() {
+    let rotation = Quaternion::from_euler(90.0, 90.0, 0.0);
+    // the pitch turns the forward vector down, the yaw keeps it there
+    assert_vec_eq([0.0, -1.0, 0.0], rotation.rotate([0.0, 0.0, 1.0]));
+    assert_vec_eq([0.0, 0.0, -1.0], rotation.rotate([1.0, 0.0, 0.0]));
+    assert_eq!(Quaternion::identity(), Quaternion::from_euler(0.0, 0.0, 0.0));
+}
+
+#[test]
+fn quaternion_matrix() {
+    let rotation = Quaternion::from_euler(30.0, -45.0, 60.0).normalize();
+    let matrix = rotation.to_matrix();
+    for vector in [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.3, -2.0, 5.0]] {
+        let [x, y, z, _] = math::transform([vector[0], vector[1], vector[2], 0.0], &matrix);
+        assert_vec_eq(rotation.rotate(vector), [x, y, z]);
+    }
+}
+
+#[test]
+fn quaternion_slerp() {
+    let from = Quaternion::from_axis_angle([0.0, 1.0, 0.0], 10.0);
+    let to = Quaternion::from_axis_angle([0.0, 1.0, 0.0], 90.0);
+    let middle = from.slerp(&to, 0.5);
+    let expected = Quaternion::from_axis_angle([0.0, 1.0, 0.0], 50.0);
+    assert_vec_eq(expected.rotate([1.0, 0.0, 0.0]), middle.rotate([1.0, 0.0, 0.0]));
+    assert_vec_eq(to.rotate([0.0, 0.0, 1.0]), from.slerp(&to, 1.0).rotate([0.0, 0.0, 1.0]));
+    // the opposite sign is the same rotation, the interpolation takes the short path
+    let flipped = Quaternion { x: -to.x, y: -to.y, z: -to.z, w: -to.w };
+    assert_vec_eq(middle.rotate([1.0, 0.0, 0.0]), from.slerp(&flipped, 0.5).rotate([1.0, 0.0, 0.0]));
+}
@@ -0,0 +1,187 @@
+//! Projection of geometry into pixel buffers, for software renderers and synthetic images
+
+use crate::clip;
+use crate::math::{self, Matrix, Vec3, Vec4};
+use crate::screen::ndc_to_screen;
+use crate::{ProjectionMatrixBuilder, Viewport};
+
+/// Buffer with the view space depth of the closest point projected into each pixel
+#[derive(Clone, Debug, PartialEq)]
+pub struct DepthImage {
+    /// Width of the image in pixels
+    pub width: usize,
+    /// Height of the image in pixels
+    pub height: usize,
+    /// Depths of the pixels by rows, from the top left corner. The pixels without points are infinitely far.
+    pub depths: Vec<f32>,
+}
+
+impl DepthImage {
+    /// Returns an image of the given size with all the pixels infinitely far
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, height, depths: vec![f32::INFINITY; width * height] }
+    }
+
+    /// Returns the depth of the pixel, or `None` if no point was projected into it or it's out of the image
+    pub fn get(&self, x: usize, y: usize) -> Option<f32> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        Some(self.depths[y * self.width + x]).filter(|depth| depth.is_finite())
+    }
+}
+
+/// Triangle projected into the screen
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Triangle2D {
+    /// Position of each vertex in pixels, with the origin at the top left corner of the window, and its depth
+    pub vertices: [Vec3; 3],
+}
+
+impl ProjectionMatrixBuilder {
+    /// Projects the world space points, seen through the view matrix, into a depth image of the render resolution.
+    /// When several points land in the same pixel, the closest one is kept. Points outside of the view are discarded.
+    pub fn depth_image(&self, points: &[Vec3], view: &Matrix) -> DepthImage {
+        let (width, height) = self.render_resolution();
+        let mut image = DepthImage::new(width, height);
+        let viewport = Viewport::new(0, 0, width, height);
+        let view_projection = math::multiply(view, &self.build());
+
+        for &point in points {
+            let [x, y, _, w] = math::transform_point(point, &view_projection);
+            if w < self.near || w > self.far {
+                continue;
+            }
+            let (x, y) = ndc_to_screen(x / w, y / w, &viewport);
+            if x < 0.0 || y < 0.0 || x >= width as f32 || y >= height as f32 {
+                continue;
+            }
+            let depth = &mut image.depths[y as usize * width + x as usize];
+            *depth = depth.min(w);
+        }
+        image
+    }
+
+    /// Projects the triangles of a world space mesh, seen through the view matrix, into the viewport. The triangles
+    /// are given as the indices of their vertices and are clipped by the near plane, so the triangles crossing it
+    /// are split and the ones fully behind it discarded.
+    ///
+    /// # Panic
+    /// If any index is out of the vertices
+    pub fn project_mesh(&self, vertices: &[Vec3], indices: &[[usize; 3]], view: &Matrix, viewport: &Viewport)
+        -> Vec<Triangle2D> {
+        let view_vertices: Vec<Vec4> = vertices.iter().map(|&vertex| math::transform_point(vertex, view)).collect();
+        let projection = self.build();
+        let to_screen = |vertex: Vec4| {
+            let [x, y, _, w] = math::transform(vertex, &projection);
+            let (x, y) = ndc_to_screen(x / w, y / w, viewport);
+            [x, y, self.delinearize_depth(w)]
+        };
+
+        let mut triangles = Vec::with_capacity(indices.len());
+        for &[a, b, c] in indices {
+            let polygon = clip_near(&[view_vertices[a], view_vertices[b], view_vertices[c]], self.near);
+            let polygon: Vec<Vec3> = polygon.into_iter().map(to_screen).collect();
+            // the clipped polygon is convex, it's split in a fan of triangles
+            for i in 2..polygon.len() {
+                triangles.push(Triangle2D { vertices: [polygon[0], polygon[i - 1], polygon[i]] });
+            }
+        }
+        triangles
+    }
+}
+
+/// Returns the barycentric coordinates of the point in the screen space triangle, the weight of each vertex, or
+/// `None` if the triangle has no area. The point is inside the triangle when all the weights are positive.
+pub fn barycentric(point: (f32, f32), a: (f32, f32), b: (f32, f32), c: (f32, f32)) -> Option<Vec3> {
+    let area = (b.0 - a.0) * (c.1 - a.1) - (c.0 - a.0) * (b.1 - a.1);
+    if area.abs() < f32::EPSILON {
+        return None;
+    }
+    let weight_b = ((point.0 - a.0) * (c.1 - a.1) - (c.0 - a.0) * (point.1 - a.1)) / area;
+    let weight_c = ((b.0 - a.0) * (point.1 - a.1) - (point.0 - a.0) * (b.1 - a.1)) / area;
+    Some([1.0 - weight_b - weight_c, weight_b, weight_c])
+}
+
+/// Corrects the screen space barycentric coordinates with the clip space `w` of each vertex, interpolating with
+/// `1 / w`, so the interpolated attributes follow the perspective instead of warping
+pub fn perspective_correct(weights: Vec3, w: Vec3) -> Vec3 {
+    let corrected = [0, 1, 2].map(|i| weights[i] / w[i]);
+    let sum: f32 = corrected.iter().sum();
+    corrected.map(|weight| weight / sum)
+}
+
+/// Interpolates the attributes of the three vertices, like texture coordinates or colors, with the given weights
+pub fn interpolate<const N: usize>(weights: Vec3, attributes: &[[f32; N]; 3]) -> [f32; N] {
+    let mut interpolated = [0.0; N];
+    for (i, value) in interpolated.iter_mut().enumerate() {
+        *value = (0..3).map(|vertex| weights[vertex] * attributes[vertex][i]).sum();
+    }
+    interpolated
+}
+
+/// Clips the view space polygon keeping the part in front of the near plane
+fn clip_near(polygon: &[Vec4], near: f32) -> Vec<Vec4> {
+    clip::clip_by(polygon, |vertex| vertex[2] - near)
+}
+
+#[test]
+fn depth_image() {
+    let builder = ProjectionMatrixBuilder::new().set_width(4).set_height(4).set_near(1.0).set_far(100.0);
+    let points = [[0.1, 0.1, 10.0], [0.5, 0.5, 5.0], [-0.1, -0.1, 20.0], [0.0, 0.0, 200.0], [-120.0, 0.0, 10.0]];
+    let image = builder.depth_image(&points, &math::identity());
+    assert_eq!((4, 4), (image.width, image.height));
+    assert_eq!(Some(5.0), image.get(2, 1));
+    assert_eq!(Some(20.0), image.get(1, 2));
+    assert_eq!(None, image.get(0, 0));
+    assert_eq!(None, image.get(4, 0));
+    assert_eq!(2, image.depths.iter().filter(|depth| depth.is_finite()).count());
+}
+
+#[test]
+fn project_mesh() {
+    let builder = ProjectionMatrixBuilder::new().set_width(720).set_near(1.0);
+    let viewport = Viewport::new(0, 0, 720, 720);
+    let vertices = [[0.0, 0.0, 10.0], [5.0, 0.0, 10.0], [0.0, 5.0, 10.0], [0.0, 0.0, -10.0]];
+    let triangles = builder.project_mesh(&vertices, &[[0, 1, 2]], &math::identity(), &viewport);
+    assert_eq!(1, triangles.len());
+    let [a, b, c] = triangles[0].vertices;
+    assert_eq!([360.0, 360.0], [a[0], a[1]]);
+    assert_eq!([540.0, 360.0], [b[0], b[1]]);
+    assert_eq!([360.0, 180.0], [c[0], c[1]]);
+    assert!((builder.delinearize_depth(10.0) - a[2]).abs() < 0.0001);
+
+    // crossing the near plane with one vertex behind produces a quad
+    assert_eq!(2, builder.project_mesh(&vertices, &[[1, 2, 3]], &math::identity(), &viewport).len());
+    // fully behind the near plane
+    let behind = math::look_at([0.0, 0.0, 20.0], [0.0, 0.0, 30.0], [0.0, 1.0, 0.0]);
+    assert!(builder.project_mesh(&vertices, &[[0, 1, 2]], &behind, &viewport).is_empty());
+}
+
+#[test]
+fn near_clipping() {
+    let triangle = [[0.0, 0.0, 2.0, 1.0], [1.0, 0.0, 0.0, 1.0], [0.0, 1.0, 0.0, 1.0]];
+    let clipped = clip_near(&triangle, 1.0);
+    assert_eq!(vec![[0.0, 0.5, 1.0, 1.0], [0.0, 0.0, 2.0, 1.0], [0.5, 0.0, 1.0, 1.0]], clipped);
+}
+
+#[test]
+fn barycentric_coordinates() {
+    let (a, b, c) = ((0.0, 0.0), (4.0, 0.0), (0.0, 4.0));
+    assert_eq!(Some([1.0, 0.0, 0.0]), barycentric(a, a, b, c));
+    assert_eq!(Some([0.5, 0.25, 0.25]), barycentric((1.0, 1.0), a, b, c));
+    assert!(barycentric((3.0, 3.0), a, b, c).unwrap().iter().any(|&weight| weight < 0.0));
+    assert_eq!(None, barycentric((1.0, 1.0), a, a, c));
+}
+
+#[test]
+fn perspective_correct_interpolation() {
+    // the middle of a segment going from a depth of 1 to 3 lands at three quarters of the screen space segment
+    let weights = perspective_correct([0.25, 0.75, 0.0], [1.0, 3.0, 2.0]);
+    assert!((0.5 - weights[0]).abs() < 0.0001 && (0.5 - weights[1]).abs() < 0.0001);
+    let uv = interpolate(weights, &[[0.0, 0.0], [1.0, 2.0], [5.0, 5.0]]);
+    assert!((0.5 - uv[0]).abs() < 0.0001 && (1.0 - uv[1]).abs() < 0.0001);
+    // with the same depth the weights are kept
+    let weights = perspective_correct([0.2, 0.3, 0.5], [2.0; 3]);
+    assert!(weights.iter().zip([0.2, 0.3, 0.5]).all(|(weight, expected)| (weight - expected).abs() < 0.0001));
+}
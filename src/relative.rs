@@ -0,0 +1,117 @@
+//! Camera-relative rendering of huge worlds, keeping the positions in double precision and producing the matrices
+//! relative to the eye in single precision
+//!
+//! Single precision positions of a planet have steps of about half a meter, so the vertices jitter as the camera
+//! moves. Subtracting the eye position in double precision first keeps the small values close to the camera exact,
+//! with the view translation always at the origin.
+
+use crate::math::{self, DVec3, Matrix, Vec3};
+use crate::{ProjectionMatrixBuilder, Quaternion};
+
+/// Semi-major axis of the WGS 84 ellipsoid, the equatorial radius of the Earth in meters
+pub const WGS84_SEMI_MAJOR_AXIS: f64 = 6_378_137.0;
+/// Flattening of the WGS 84 ellipsoid
+pub const WGS84_FLATTENING: f64 = 1.0 / 298.257_223_563;
+
+/// Camera with its position in double precision, producing the matrices relative to its eye
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RelativeCamera {
+    /// Position of the camera
+    pub eye: DVec3,
+    /// Rotation of the camera, from looking along the z-axis
+    pub orientation: Quaternion,
+    /// Parameters of the projection
+    pub projection: ProjectionMatrixBuilder,
+}
+
+impl RelativeCamera {
+    /// Returns the camera at the given position and orientation
+    pub fn new(eye: DVec3, orientation: Quaternion, projection: ProjectionMatrixBuilder) -> Self {
+        Self { eye, orientation, projection }
+    }
+
+    /// Returns the position relative to the eye, subtracted in double precision
+    pub fn relative_position(&self, position: DVec3) -> Vec3 {
+        [0, 1, 2].map(|i| (position[i] - self.eye[i]) as f32)
+    }
+
+    /// Returns the translation of an object with the given origin relative to the eye, to apply after its local
+    /// rotation and scale
+    pub fn model_matrix(&self, origin: DVec3) -> Matrix {
+        let [x, y, z] = self.relative_position(origin);
+        let mut matrix = math::identity();
+        matrix[3] = [x, y, z, 1.0];
+        matrix
+    }
+
+    /// Returns the combined view and projection matrix of the camera at the origin, for the positions relative to
+    /// the eye
+    ///
+    /// # Panic
+    /// Like [`ProjectionMatrixBuilder::build`]
+    pub fn view_projection(&self) -> Matrix {
+        self.projection.build_view_projection([0.0; 3], &self.orientation)
+    }
+
+    /// Returns the combined model, view and projection matrix of an object with the given origin
+    ///
+    /// # Panic
+    /// Like [`ProjectionMatrixBuilder::build`]
+    pub fn model_view_projection(&self, origin: DVec3) -> Matrix {
+        math::multiply(&self.model_matrix(origin), &self.view_projection())
+    }
+}
+
+/// Returns the Earth-centered, Earth-fixed position of the geographic coordinates in grades over the WGS 84
+/// ellipsoid, with the altitude in meters. The x-axis goes through the prime meridian at the equator and the z-axis
+/// through the North Pole.
+pub fn geodetic_to_ecef(latitude: f64, longitude: f64, altitude: f64) -> DVec3 {
+    let (latitude, longitude) = (latitude.to_radians(), longitude.to_radians());
+    let eccentricity_squared = WGS84_FLATTENING * (2.0 - WGS84_FLATTENING);
+    let radius = WGS84_SEMI_MAJOR_AXIS / (1.0 - eccentricity_squared * latitude.sin().powi(2)).sqrt();
+    [
+        (radius + altitude) * latitude.cos() * longitude.cos(),
+        (radius + altitude) * latitude.cos() * longitude.sin(),
+        (radius * (1.0 - eccentricity_squared) + altitude) * latitude.sin(),
+    ]
+}
+
+/// Returns the east, north and up axes of the surface at the geographic coordinates in grades, in Earth-centered,
+/// Earth-fixed coordinates
+pub fn east_north_up(latitude: f64, longitude: f64) -> [Vec3; 3] {
+    let (latitude, longitude) = (latitude.to_radians(), longitude.to_radians());
+    let (sin_lat, cos_lat, sin_lon, cos_lon) = (latitude.sin(), latitude.cos(), longitude.sin(), longitude.cos());
+    [
+        [-sin_lon, cos_lon, 0.0],
+        [-sin_lat * cos_lon, -sin_lat * sin_lon, cos_lat],
+        [cos_lat * cos_lon, cos_lat * sin_lon, sin_lat],
+    ]
+    .map(|axis| axis.map(|component| component as f32))
+}
+
+#[test]
+fn relative_positions() {
+    let eye = [WGS84_SEMI_MAJOR_AXIS + 0.25, 0.0, 0.0];
+    let camera = RelativeCamera::new(eye, Quaternion::identity(), ProjectionMatrixBuilder::new().set_near(1.0));
+    let position = [eye[0] + 0.1, 2.3, 4.56];
+    let relative = camera.relative_position(position);
+    assert!((0..3).all(|i| (relative[i] as f64 - (position[i] - eye[i])).abs() < 0.000001));
+    // single precision would round the eye to a step of half a meter
+    assert_ne!(0.1, (position[0] as f32 - eye[0] as f32) as f64);
+
+    let clip = math::transform_point([0.0; 3], &camera.model_view_projection([eye[0], 0.0, 10.0]));
+    let direct = math::transform_point([0.0, 0.0, 10.0], &camera.projection.build());
+    assert!((0..4).all(|i| (clip[i] - direct[i]).abs() < 0.0001));
+}
+
+#[test]
+fn geographic_coordinates() {
+    assert_eq!([WGS84_SEMI_MAJOR_AXIS + 10.0, 0.0, 0.0], geodetic_to_ecef(0.0, 0.0, 10.0));
+    let pole = geodetic_to_ecef(90.0, 0.0, 0.0);
+    assert!(pole[0].abs() < 0.000001 && (pole[2] - 6_356_752.314_245).abs() < 0.000001);
+    let [x, y, _] = geodetic_to_ecef(0.0, 90.0, 0.0);
+    assert!(x.abs() < 0.000001 && (y - WGS84_SEMI_MAJOR_AXIS).abs() < 0.000001);
+
+    let [east, north, up] = east_north_up(0.0, 0.0);
+    assert_eq!([[0.0, 1.0, 0.0], [0.0, 0.0, 1.0], [1.0, 0.0, 0.0]], [east, north, up]);
+}
@@ -0,0 +1,94 @@
+//! Generation of random valid projections, to property-test the pipelines built over the crate with realistic
+//! inputs from any testing framework
+//!
+//! The samples are deterministic for each seed, so failing cases can be reproduced from the seed alone.
+
+use crate::{DepthMode, ProjectionMatrix, ProjectionMatrixBuilder};
+
+/// Closest near clip of the samples
+const MIN_NEAR: f32 = 0.01;
+/// Furthest near clip of the samples
+const MAX_NEAR: f32 = 10.0;
+/// Range of the ratio between the far and near clips of the samples
+const FAR_RATIO: (f32, f32) = (10.0, 100_000.0);
+/// Range of the field of view of the samples, in degrees
+const FOV: (f32, f32) = (10.0, 170.0);
+/// Biggest width and height of the frame of the samples
+const MAX_SIZE: usize = 8192;
+
+/// Generator of random projection parameters within the valid ranges of the builder
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sampler {
+    state: u64,
+}
+
+impl Sampler {
+    /// Returns the sampler generating the sequence of the given seed
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Returns a builder with random parameters: near and far clips, field of view, depth mode and frame size
+    pub fn builder(&mut self) -> ProjectionMatrixBuilder {
+        let near = self.log_uniform(MIN_NEAR, MAX_NEAR);
+        let far = near * self.log_uniform(FAR_RATIO.0, FAR_RATIO.1);
+        let fov = self.uniform(FOV.0, FOV.1);
+        let depth_mode = [DepthMode::Standard, DepthMode::Logarithmic, DepthMode::WBuffer][self.below(3)];
+        ProjectionMatrixBuilder::new()
+            .set_near(near)
+            .set_far(far)
+            .set_fov(fov)
+            .set_depth_mode(depth_mode)
+            .set_width(1 + self.below(MAX_SIZE))
+            .set_height(1 + self.below(MAX_SIZE))
+    }
+
+    /// Returns the projection matrix of a random builder
+    pub fn matrix(&mut self) -> ProjectionMatrix {
+        self.builder().build()
+    }
+
+    /// Returns the next random value, with the SplitMix64 generator
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a random value in the `[0, count)` range
+    fn below(&mut self, count: usize) -> usize {
+        (self.next() % count as u64) as usize
+    }
+
+    /// Returns a random value in the `[min, max]` range
+    fn uniform(&mut self, min: f32, max: f32) -> f32 {
+        let t = (self.next() >> 40) as f32 / (1u64 << 24) as f32;
+        min + (max - min) * t
+    }
+
+    /// Returns a random value in the `[min, max]` range, evenly spread over the orders of magnitude
+    fn log_uniform(&mut self, min: f32, max: f32) -> f32 {
+        self.uniform(min.ln(), max.ln()).exp()
+    }
+}
+
+#[test]
+fn valid_samples() {
+    let mut sampler = Sampler::new(42);
+    for _ in 0..1000 {
+        let builder = sampler.builder();
+        assert!(builder.near >= MIN_NEAR * 0.999 && builder.near <= MAX_NEAR * 1.001);
+        assert!(builder.far > builder.near);
+        assert!(builder.fov >= FOV.0 && builder.fov <= FOV.1);
+        assert!(builder.width >= 1 && builder.width <= MAX_SIZE && builder.height >= 1);
+        assert!(builder.build().iter().flatten().all(|cell| cell.is_finite()));
+    }
+}
+
+#[test]
+fn reproducible_samples() {
+    assert_eq!(Sampler::new(7).matrix(), Sampler::new(7).matrix());
+    assert_ne!(Sampler::new(7).builder(), Sampler::new(8).builder());
+}
@@ -0,0 +1,335 @@
+//! Mapping of world space positions into the screen
+
+use crate::bounds::BOX_EDGES;
+use crate::clip;
+use crate::math::{self, Matrix, Vec3};
+use crate::{Aabb, ProjectionMatrixBuilder, Viewport};
+
+/// Rectangle of the screen, in pixels with the origin at the top left corner of the window
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScreenRect {
+    /// Horizontal position of the left edge
+    pub left: f32,
+    /// Vertical position of the top edge
+    pub top: f32,
+    /// Horizontal position of the right edge
+    pub right: f32,
+    /// Vertical position of the bottom edge
+    pub bottom: f32,
+}
+
+impl ScreenRect {
+    /// Returns the rectangle between the given edges
+    pub fn new(left: f32, top: f32, right: f32, bottom: f32) -> Self {
+        Self { left, top, right, bottom }
+    }
+
+    /// Returns the width of the rectangle
+    pub fn width(&self) -> f32 {
+        self.right - self.left
+    }
+
+    /// Returns the height of the rectangle
+    pub fn height(&self) -> f32 {
+        self.bottom - self.top
+    }
+
+    /// Returns the smallest rectangle of whole pixels covering the rectangle, to be used as a scissor rectangle with
+    /// the origin at the top left corner of the window
+    pub fn to_scissor(&self) -> Viewport {
+        let (left, top) = (self.left.floor().max(0.0) as usize, self.top.floor().max(0.0) as usize);
+        let (right, bottom) = (self.right.ceil().max(0.0) as usize, self.bottom.ceil().max(0.0) as usize);
+        Viewport::new(left, top, right.saturating_sub(left), bottom.saturating_sub(top))
+    }
+
+    /// Returns the smallest rectangle containing all the points, or `None` if there are no points
+    fn bounding(points: impl IntoIterator<Item = (f32, f32)>) -> Option<Self> {
+        points.into_iter().fold(None, |rect: Option<Self>, (x, y)| {
+            Some(match rect {
+                None => Self::new(x, y, x, y),
+                Some(rect) => Self::new(rect.left.min(x), rect.top.min(y), rect.right.max(x), rect.bottom.max(y)),
+            })
+        })
+    }
+
+    /// Returns the part of the rectangle within the viewport, or `None` if they don't overlap
+    fn clamp(&self, viewport: &Viewport) -> Option<Self> {
+        let (left, top) = (viewport.x as f32, viewport.y as f32);
+        let (right, bottom) = (left + viewport.width as f32, top + viewport.height as f32);
+        let rect = Self::new(self.left.max(left), self.top.max(top), self.right.min(right), self.bottom.min(bottom));
+        (rect.left < rect.right && rect.top < rect.bottom).then_some(rect)
+    }
+}
+
+impl ProjectionMatrixBuilder {
+    /// Projects a world space point seen through the view matrix into the viewport. Returns the position of the point
+    /// in pixels, with the origin at the top left corner of the window, along with its depth, or `None` if the point
+    /// is outside the view.
+    pub fn world_to_screen(&self, point: Vec3, view: &Matrix, viewport: &Viewport) -> Option<(f32, f32, f32)> {
+        let clip = math::transform_point(point, &math::multiply(view, &self.build()));
+        let w = clip[3];
+        let ndc = clip::clip_to_ndc(clip).filter(|ndc| ndc.is_on_screen() && w >= self.near && w <= self.far)?;
+        let (screen_x, screen_y) = ndc.to_screen(viewport);
+        Some((screen_x, screen_y, self.delinearize_depth(w)))
+    }
+
+    /// Returns the rectangle of the viewport covered by the world space box seen through the view matrix, or `None`
+    /// if the box is fully behind the camera or out of the viewport. The box edges are clipped by the near clip so
+    /// boxes surrounding the camera still produce a valid rectangle.
+    pub fn aabb_screen_rect(&self, aabb: &Aabb, view: &Matrix, viewport: &Viewport) -> Option<ScreenRect> {
+        let corners = aabb.corners().map(|corner| math::transform_point(corner, view));
+        let in_front = |point: &[f32; 4]| point[2] >= self.near;
+
+        let mut points: Vec<[f32; 4]> = corners.iter().filter(|corner| in_front(corner)).copied().collect();
+        for (a, b) in BOX_EDGES {
+            let (a, b) = (corners[a], corners[b]);
+            if in_front(&a) != in_front(&b) {
+                let t = (self.near - a[2]) / (b[2] - a[2]);
+                points.push([0, 1, 2, 3].map(|i| a[i] + (b[i] - a[i]) * t));
+            }
+        }
+
+        let projection = self.build();
+        ScreenRect::bounding(points.into_iter().map(|point| {
+            let clip = math::transform(point, &projection);
+            ndc_to_screen(clip[0] / clip[3], clip[1] / clip[3], viewport)
+        }))?
+        .clamp(viewport)
+    }
+
+    /// Returns the size in pixels of the screen of a world space error, like the geometric error of a level of detail
+    /// of a mesh or terrain, seen at the given distance from the camera, the metric to select the levels of detail
+    pub fn screen_space_error(&self, geometric_error: f32, distance: f32) -> f32 {
+        geometric_error * self.pixels_per_unit() / distance
+    }
+
+    /// Returns the distance from the camera where a world space error is seen with the given size in pixels of the
+    /// screen, the switching distance of a level of detail with that error. It's the inverse of
+    /// [`ProjectionMatrixBuilder::screen_space_error`].
+    pub fn screen_space_error_distance(&self, geometric_error: f32, pixels: f32) -> f32 {
+        geometric_error * self.pixels_per_unit() / pixels
+    }
+
+    /// Returns the bias of the mip level of detail for the textures sampled at the render resolution, so they keep the
+    /// sharpness of the screen resolution, the base 2 logarithm of the render scale. It's negative when rendering
+    /// below the screen resolution and meant to be set in the samplers with the dynamic resolution changes.
+    pub fn mip_bias(&self) -> f32 {
+        let (_, render_height) = self.render_resolution();
+        (render_height as f32 / self.height as f32).log2()
+    }
+
+    /// Returns the mip level of detail selected when rendering at the render resolution a texture with the given
+    /// world space size of its texels seen at the given distance from the camera, the base 2 logarithm of the texels
+    /// covered by each pixel. It's negative for the magnified textures.
+    pub fn texture_lod(&self, texel_size: f32, distance: f32) -> f32 {
+        let (_, render_height) = self.render_resolution();
+        let render_pixels_per_unit = self.pixels_per_unit() * render_height as f32 / self.height as f32;
+        (distance / (texel_size * render_pixels_per_unit)).log2()
+    }
+
+    /// Returns the pixels of the screen height covered by a world space unit at a distance of one unit
+    fn pixels_per_unit(&self) -> f32 {
+        let [_, _, top, bottom] = self.safe_area;
+        self.fov_scale() * (1.0 - top - bottom) * self.height as f32 * 0.5
+    }
+
+    /// Returns the scissor rectangle of the pixels of the viewport affected by a light with the given world space
+    /// bounding box seen through the view matrix, or `None` if the light doesn't reach the viewport. Check
+    /// [`ProjectionMatrixBuilder::aabb_screen_rect`].
+    pub fn aabb_scissor(&self, aabb: &Aabb, view: &Matrix, viewport: &Viewport) -> Option<Viewport> {
+        self.aabb_screen_rect(aabb, view, viewport).map(|rect| rect.to_scissor())
+    }
+
+    /// Returns the scissor rectangle of the pixels of the viewport affected by a light with the given world space
+    /// bounding sphere seen through the view matrix, or `None` if the light doesn't reach the viewport. Check
+    /// [`ProjectionMatrixBuilder::sphere_screen_rect`].
+    pub fn sphere_scissor(&self, center: Vec3, radius: f32, view: &Matrix, viewport: &Viewport) -> Option<Viewport> {
+        self.sphere_screen_rect(center, radius, view, viewport).map(|rect| rect.to_scissor())
+    }
+
+    /// Returns the rectangle of the viewport covered by the world space sphere seen through the view matrix, or
+    /// `None` if the sphere is fully behind the near clip or out of the viewport. The rectangle is the exact bounds
+    /// of the projection of the sphere clipped by the near clip, covering the whole viewport when the camera is
+    /// within the sphere.
+    pub fn sphere_screen_rect(&self, center: Vec3, radius: f32, view: &Matrix, viewport: &Viewport)
+        -> Option<ScreenRect> {
+        let [x, y, z, _] = math::transform_point(center, view);
+        if z + radius < self.near {
+            return None;
+        }
+        if x * x + y * y + z * z <= radius * radius {
+            return ScreenRect::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::INFINITY, f32::INFINITY)
+                .clamp(viewport);
+        }
+        let (left, right) = self.sphere_tangents(x, z, radius);
+        let (bottom, top) = self.sphere_tangents(y, z, radius);
+        let projection = self.build();
+        let (left, top) = ndc_to_screen(left * projection[0][0] + projection[2][0],
+                                        top * projection[1][1] + projection[2][1], viewport);
+        let (right, bottom) = ndc_to_screen(right * projection[0][0] + projection[2][0],
+                                            bottom * projection[1][1] + projection[2][1], viewport);
+        ScreenRect::new(left, top, right, bottom).clamp(viewport)
+    }
+
+    /// Returns the smallest and biggest slopes, over the depth, of the points of the circle with the given center
+    /// and radius in front of the near clip, seen from the origin outside of the circle. The extremes are either the
+    /// points where the tangents from the origin touch the circle or the ones where the near clip crosses it.
+    fn sphere_tangents(&self, a: f32, z: f32, radius: f32) -> (f32, f32) {
+        let length_squared = a * a + z * z;
+        let tangent_length = (length_squared - radius * radius).sqrt();
+        // the tangent points are the center rotated by the angle of the tangents and scaled to their length
+        let (cos, sin) = (tangent_length / length_squared.sqrt(), radius / length_squared.sqrt());
+        let mut points = vec![
+            ((a * cos - z * sin) * cos, (a * sin + z * cos) * cos),
+            ((a * cos + z * sin) * cos, (-a * sin + z * cos) * cos),
+        ];
+        points.retain(|&(_, depth)| depth >= self.near);
+        let distance = self.near - z;
+        if distance.abs() < radius {
+            let half_chord = (radius * radius - distance * distance).sqrt();
+            points.extend([(a - half_chord, self.near), (a + half_chord, self.near)]);
+        }
+        points
+            .into_iter()
+            .map(|(a, depth)| a / depth)
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), slope| (min.min(slope), max.max(slope)))
+    }
+}
+
+/// Maps normalized device coordinates into pixels of the viewport, with the y-axis pointing down
+pub(crate) fn ndc_to_screen(x: f32, y: f32, viewport: &Viewport) -> (f32, f32) {
+    (
+        viewport.x as f32 + (x + 1.0) * 0.5 * viewport.width as f32,
+        viewport.y as f32 + (1.0 - y) * 0.5 * viewport.height as f32,
+    )
+}
+
+/// Maps pixels of the viewport, with the y-axis pointing down, into normalized device coordinates
+pub(crate) fn screen_to_ndc(x: f32, y: f32, viewport: &Viewport) -> (f32, f32) {
+    (
+        (x - viewport.x as f32) / viewport.width as f32 * 2.0 - 1.0,
+        1.0 - (y - viewport.y as f32) / viewport.height as f32 * 2.0,
+    )
+}
+
+#[test]
+fn world_to_screen() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_far(100.0).set_width(720);
+    let view = math::look_at([0.0, 0.0, -10.0], [0.0, 0.0, 0.0], [0.0, 1.0, 0.0]);
+    let viewport = Viewport::new(100, 0, 720, 720);
+
+    let (x, y, depth) = builder.world_to_screen([0.0, 0.0, 0.0], &view, &viewport).unwrap();
+    assert_eq!((460.0, 360.0), (x, y));
+    assert!((builder.delinearize_depth(10.0) - depth).abs() < 0.0001);
+
+    // the fov is 90 degrees, so the point is at the top right corner
+    let (x, y, _) = builder.world_to_screen([9.999, 9.999, 0.0], &view, &viewport).unwrap();
+    assert!((820.0 - x).abs() < 0.1 && y.abs() < 0.1);
+}
+
+#[test]
+fn world_to_screen_clipping() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_far(100.0);
+    let view = math::identity();
+    let viewport = Viewport::new(0, 0, 1280, 720);
+    assert_eq!(None, builder.world_to_screen([0.0, 0.0, -5.0], &view, &viewport));
+    assert_eq!(None, builder.world_to_screen([0.0, 0.0, 0.5], &view, &viewport));
+    assert_eq!(None, builder.world_to_screen([0.0, 0.0, 200.0], &view, &viewport));
+    assert_eq!(None, builder.world_to_screen([0.0, 10.0, 5.0], &view, &viewport));
+}
+
+#[test]
+fn aabb_screen_rect() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_width(720);
+    let viewport = Viewport::new(0, 0, 720, 720);
+    let view = math::identity();
+
+    let aabb = Aabb::new([-1.0, -1.0, 10.0], [0.0, 1.0, 20.0]);
+    let rect = builder.aabb_screen_rect(&aabb, &view, &viewport).unwrap();
+    let expected = [324.0, 324.0, 360.0, 396.0];
+    let actual = [rect.left, rect.top, rect.right, rect.bottom];
+    assert!(expected.iter().zip(actual).all(|(expected, actual)| (expected - actual).abs() < 0.001));
+
+    // surrounding the camera it covers the whole viewport
+    let aabb = Aabb::new([-5.0; 3], [5.0; 3]);
+    let rect = builder.aabb_screen_rect(&aabb, &view, &viewport).unwrap();
+    assert_eq!(ScreenRect::new(0.0, 0.0, 720.0, 720.0), rect);
+    assert_eq!((720.0, 720.0), (rect.width(), rect.height()));
+}
+
+#[test]
+fn aabb_screen_rect_hidden() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0);
+    let viewport = Viewport::new(0, 0, 1280, 720);
+    let view = math::identity();
+    let behind = Aabb::new([-1.0, -1.0, -10.0], [1.0, 1.0, 0.5]);
+    assert_eq!(None, builder.aabb_screen_rect(&behind, &view, &viewport));
+    let aside = Aabb::new([100.0, -1.0, 5.0], [101.0, 1.0, 6.0]);
+    assert_eq!(None, builder.aabb_screen_rect(&aside, &view, &viewport));
+}
+
+#[test]
+fn light_scissor() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_width(720);
+    let viewport = Viewport::new(0, 0, 720, 720);
+    let view = math::look_at([0.0, 0.0, -10.0], [0.0, 0.0, 0.0], [0.0, 1.0, 0.0]);
+
+    // the tangents of the sphere are at tan(asin(0.1)) of the center, from 323.8 to 396.2
+    let scissor = builder.sphere_scissor([0.0, 0.0, 0.0], 1.0, &view, &viewport).unwrap();
+    assert_eq!(Viewport::new(323, 323, 74, 74), scissor);
+    assert_eq!(Some(viewport), builder.sphere_scissor([0.0, 0.0, -10.0], 2.0, &view, &viewport));
+    assert_eq!(None, builder.sphere_scissor([0.0, 0.0, -20.0], 2.0, &view, &viewport));
+
+    // the box reaches the camera, it covers the left half of the viewport
+    let aabb = Aabb::new([-1.0, -1.0, 0.0], [0.0, 1.0, 10.0]);
+    assert_eq!(Some(Viewport::new(0, 0, 360, 720)), builder.aabb_scissor(&aabb, &math::identity(), &viewport));
+    assert_eq!(Viewport::new(1, 2, 3, 3), ScreenRect::new(1.5, 2.0, 3.2, 4.9).to_scissor());
+}
+
+#[test]
+fn sphere_screen_rect() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_width(720);
+    let viewport = Viewport::new(0, 0, 720, 720);
+    let view = math::identity();
+
+    // the edges are the tangents of the sphere from the camera
+    let rect = builder.sphere_screen_rect([1.0, 0.0, 4.0], 1.0, &view, &viewport).unwrap();
+    let slope = |angle: f32| angle.tan() * 360.0 + 360.0;
+    let (center, half) = ((1.0f32 / 4.0).atan(), (1.0f32 / 17.0f32.sqrt()).asin());
+    assert!((slope(center - half) - rect.left).abs() < 0.01 && (slope(center + half) - rect.right).abs() < 0.01);
+    assert!((rect.top + rect.bottom - 720.0).abs() < 0.01);
+
+    // a sphere crossing the near clip is bounded by the circle where they cross
+    let rect = builder.sphere_screen_rect([0.0, 0.0, 1.0], 0.5, &view, &viewport).unwrap();
+    assert!((360.0 - rect.width()).abs() < 0.01 && (360.0 - rect.height()).abs() < 0.01);
+    assert_eq!(None, builder.sphere_screen_rect([0.0, 0.0, 0.0], 0.5, &view, &viewport));
+    assert_eq!(None, builder.sphere_screen_rect([50.0, 0.0, 5.0], 1.0, &view, &viewport));
+}
+
+#[test]
+fn mip_bias() {
+    let builder = ProjectionMatrixBuilder::new().set_height(720);
+    assert_eq!(0.0, builder.mip_bias());
+    // a unit covers 36 pixels at a distance of ten units, so a texel of 1/36 units fills a pixel
+    assert!(builder.texture_lod(1.0 / 36.0, 10.0).abs() < 0.0001);
+    assert!((1.0 - builder.texture_lod(1.0 / 36.0, 20.0)).abs() < 0.0001);
+
+    let half = builder.set_render_scale(0.5);
+    assert!((-1.0 - half.mip_bias()).abs() < 0.0001);
+    let lod = half.texture_lod(1.0 / 36.0, 10.0);
+    assert!((1.0 - lod).abs() < 0.0001);
+    assert!((lod + half.mip_bias()).abs() < 0.0001);
+}
+
+#[test]
+fn screen_space_error() {
+    let builder = ProjectionMatrixBuilder::new().set_height(720);
+    // with a fov of 90 degrees, the screen height covers twice the distance
+    assert!((36.0 - builder.screen_space_error(1.0, 10.0)).abs() < 0.0001);
+    assert!((18.0 - builder.screen_space_error(1.0, 20.0)).abs() < 0.0001);
+    assert!((20.0 - builder.screen_space_error_distance(1.0, 18.0)).abs() < 0.0001);
+    let narrow = builder.set_fov(60.0);
+    assert!(narrow.screen_space_error(1.0, 10.0) > builder.screen_space_error(1.0, 10.0));
+    let distance = narrow.screen_space_error_distance(0.5, 4.0);
+    assert!((4.0 - narrow.screen_space_error(0.5, distance)).abs() < 0.0001);
+}
@@ -0,0 +1,107 @@
+//! Coverage of non-visual sensors, like radars or lidars, measured in angles and ranges from the sensor
+
+use crate::frustum::Frustum;
+use crate::math::{self, Matrix, Vec3};
+
+/// Volume covered by a sensor placed with the given view matrix, looking down its positive z-axis. The volume spans
+/// the given angular extents centered in the forward direction, between the minimum and maximum ranges.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SensorFrustum {
+    /// View matrix of the sensor
+    pub view: Matrix,
+    /// Horizontal extent, in degrees
+    pub azimuth: f32,
+    /// Vertical extent, in degrees
+    pub elevation: f32,
+    /// Closest distance detected
+    pub min_range: f32,
+    /// Furthest distance detected
+    pub max_range: f32,
+}
+
+impl SensorFrustum {
+    /// Returns the coverage of the sensor with the given view matrix, extents in degrees and ranges
+    ///
+    /// # Panic
+    /// If the azimuth is not within the (0, 360] range, the elevation within the (0, 180] range or the ranges are
+    /// negative or not increasing
+    pub fn new(view: &Matrix, azimuth: f32, elevation: f32, min_range: f32, max_range: f32) -> Self {
+        if azimuth <= 0.0 || azimuth > 360.0 || elevation <= 0.0 || elevation > 180.0 {
+            panic!("The azimuth must be between 0.0 and 360.0 and the elevation between 0.0 and 180.0");
+        }
+        if min_range < 0.0 || max_range <= min_range {
+            panic!("The ranges must be positive values with the maximum range bigger than the minimum one");
+        }
+        Self { view: *view, azimuth, elevation, min_range, max_range }
+    }
+
+    /// Returns if the world space point is covered by the sensor
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        let [x, y, z, _] = math::transform_point(point, &self.view);
+        let range = math::length([x, y, z]);
+        let azimuth = x.atan2(z).to_degrees();
+        let elevation = y.atan2(x.hypot(z)).to_degrees();
+        (self.min_range..=self.max_range).contains(&range)
+            && azimuth.abs() <= self.azimuth * 0.5
+            && elevation.abs() <= self.elevation * 0.5
+    }
+
+    /// Returns the smallest frustum enclosing the coverage, to cull with the frustum tests before the exact
+    /// [`SensorFrustum::contains_point`]. Returns `None` if any extent reaches `180.0` degrees, where the coverage
+    /// can't be bounded by a frustum.
+    pub fn frustum(&self) -> Option<Frustum> {
+        if self.azimuth >= 180.0 || self.elevation >= 180.0 {
+            return None;
+        }
+        let (half_azimuth, half_elevation) = ((self.azimuth * 0.5).to_radians(), (self.elevation * 0.5).to_radians());
+        // the elevation edges rise over the sides of the fan, the near clip is at the closest corner
+        let projection = [
+            [1.0 / half_azimuth.tan(), 0.0, 0.0, 0.0],
+            [0.0, half_azimuth.cos() / half_elevation.tan(), 0.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+            [0.0, 0.0, 0.0, 0.0],
+        ];
+        let near = self.min_range * half_azimuth.cos() * half_elevation.cos();
+        Some(Frustum::from_matrix(&self.view, &projection, near, self.max_range))
+    }
+}
+
+#[test]
+fn sensor_coverage() {
+    let view = math::look_at([0.0, 1.0, 0.0], [10.0, 1.0, 0.0], [0.0, 1.0, 0.0]);
+    let sensor = SensorFrustum::new(&view, 120.0, 20.0, 2.0, 50.0);
+    assert!(sensor.contains_point([10.0, 2.0, 0.0]));
+    // 55 degrees to the side
+    assert!(sensor.contains_point([5.736, 1.0, 8.192]));
+    assert!(!sensor.contains_point([5.0, 1.0, 10.0]));
+    assert!(!sensor.contains_point([1.0, 1.0, 0.0]));
+    assert!(!sensor.contains_point([60.0, 1.0, 0.0]));
+    assert!(!sensor.contains_point([10.0, 4.0, 0.0]));
+
+    // the frustum encloses every covered point
+    let frustum = sensor.frustum().unwrap();
+    for azimuth in [-59.9f32, -30.0, 0.0, 45.0, 59.9] {
+        for elevation in [-9.9f32, 0.0, 9.9] {
+            for range in [2.01, 20.0, 49.9] {
+                let (azimuth, elevation) = (azimuth.to_radians(), elevation.to_radians());
+                let offset = [elevation.cos() * azimuth.cos(), elevation.sin(), -elevation.cos() * azimuth.sin()];
+                let point = math::add([0.0, 1.0, 0.0], math::scale(offset, range));
+                assert!(frustum.contains_point(point), "{point:?}");
+            }
+        }
+    }
+    assert!(!frustum.contains_point([-5.0, 1.0, 0.0]));
+}
+
+#[test]
+fn unbounded_sensor() {
+    let sensor = SensorFrustum::new(&math::identity(), 360.0, 30.0, 0.0, 10.0);
+    assert_eq!(None, sensor.frustum());
+    assert!(sensor.contains_point([0.0, 0.0, -5.0]));
+}
+
+#[test]
+#[should_panic]
+fn invalid_sensor_range() {
+    SensorFrustum::new(&math::identity(), 90.0, 30.0, 10.0, 5.0);
+}
@@ -0,0 +1,126 @@
+//! Depth biases of the shadow maps, to fight the shadow acne produced by their limited resolution and precision, and
+//! planar shadows squashing the geometry onto a plane
+
+use std::f32::consts::SQRT_2;
+
+use crate::math::{Matrix, Vec3, Vec4};
+use crate::{Orthographic, Plane};
+
+/// Biggest tangent of the angle between the surface normals and the light applied by [`ShadowBias::depth_bias`],
+/// the slope bias of the surfaces almost parallel to the light grows without bound
+const MAX_SLOPE: f32 = 10.0;
+
+/// Depth biases recommended for a shadow map, in the normalized depth of its projection along with the world space
+/// offset of the normals, so every cascade gets the bias of its own resolution and depth range
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShadowBias {
+    /// Constant bias, two steps of the depth buffer
+    pub constant: f32,
+    /// Bias per unit of the tangent of the angle between the surface normal and the light, covering the depth
+    /// difference across half the diagonal of a texel
+    pub slope: f32,
+    /// World space offset along the surface normals, half the diagonal of a texel
+    pub normal_offset: f32,
+}
+
+impl ShadowBias {
+    /// Returns the total depth bias of a surface with the given cosine of the angle between its normal and the
+    /// direction to the light
+    pub fn depth_bias(&self, cos_angle: f32) -> f32 {
+        let cos = cos_angle.clamp(0.0, 1.0);
+        let tan = ((1.0 - cos * cos).sqrt() / cos).min(MAX_SLOPE);
+        self.constant + self.slope * tan
+    }
+}
+
+impl Orthographic {
+    /// Returns the world space size of the texels of a shadow map with the given resolution and this projection, the
+    /// biggest of their width and height
+    pub fn texel_size(&self, width: usize, height: usize) -> f32 {
+        ((self.right - self.left) / width as f32).max((self.top - self.bottom) / height as f32)
+    }
+
+    /// Returns the recommended biases of a shadow map with the given resolution and depth buffer bits rendered with
+    /// this projection
+    pub fn shadow_bias(&self, width: usize, height: usize, depth_bits: u32) -> ShadowBias {
+        let steps = 2.0f64.powi(depth_bits as i32) - 1.0;
+        let normal_offset = self.texel_size(width, height) * SQRT_2 * 0.5;
+        ShadowBias {
+            constant: (2.0 / steps) as f32,
+            slope: normal_offset / (self.far - self.near),
+            normal_offset,
+        }
+    }
+}
+
+impl Plane {
+    /// Returns the matrix projecting the points onto the plane from the light, the shadow they cast on it. The light
+    /// is a homogeneous point: `[x, y, z, 1.0]` for a point light at that position or `[x, y, z, 0.0]` for a
+    /// directional light shining along that direction. The result is a projective matrix, like the projections.
+    pub fn shadow_projection(&self, light: Vec4) -> Matrix {
+        let plane = [self.normal[0], self.normal[1], self.normal[2], self.distance];
+        let dot: f32 = (0..4).map(|i| plane[i] * light[i]).sum();
+        let mut matrix = [[0.0; 4]; 4];
+        for (i, row) in matrix.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                *cell = if i == j { dot } else { 0.0 } - plane[i] * light[j];
+            }
+        }
+        matrix
+    }
+
+    /// Returns the matrix projecting the points onto the plane from a point light at the given position, see
+    /// [`Plane::shadow_projection`]
+    pub fn point_shadow(&self, light: Vec3) -> Matrix {
+        self.shadow_projection([light[0], light[1], light[2], 1.0])
+    }
+
+    /// Returns the matrix projecting the points onto the plane along the direction of a directional light, see
+    /// [`Plane::shadow_projection`]
+    pub fn directional_shadow(&self, direction: Vec3) -> Matrix {
+        self.shadow_projection([direction[0], direction[1], direction[2], 0.0])
+    }
+}
+
+#[test]
+fn shadow_bias() {
+    let projection = Orthographic::from_size(100.0, 50.0, 0.0, 200.0);
+    assert_eq!(0.1, projection.texel_size(1000, 1000));
+    let bias = projection.shadow_bias(1000, 1000, 16);
+    assert_eq!(2.0 / 65535.0, bias.constant);
+    assert!((0.1 * SQRT_2 * 0.5 - bias.normal_offset).abs() < 0.00001);
+    assert!((bias.normal_offset / 200.0 - bias.slope).abs() < 0.00001);
+
+    // the wider cascades get bigger biases
+    let cascade = Orthographic::from_size(400.0, 200.0, 0.0, 200.0).shadow_bias(1000, 1000, 16);
+    assert!((4.0 * bias.slope - cascade.slope).abs() < 0.00001);
+    assert_eq!(bias.constant, cascade.constant);
+}
+
+#[test]
+fn surface_depth_bias() {
+    let bias = ShadowBias { constant: 0.001, slope: 0.01, normal_offset: 0.1 };
+    assert_eq!(0.001, bias.depth_bias(1.0));
+    assert!((0.011 - bias.depth_bias(0.5f32.sqrt())).abs() < 0.00001);
+    assert_eq!(0.001 + 0.01 * MAX_SLOPE, bias.depth_bias(0.0));
+    assert_eq!(bias.depth_bias(0.0), bias.depth_bias(-0.5));
+}
+
+#[test]
+fn planar_shadows() {
+    let ground = Plane::from_point_normal([0.0; 3], [0.0, 1.0, 0.0]);
+    let project = |point: Vec3, matrix: &Matrix| {
+        let [x, y, z, w] = crate::math::transform_point(point, matrix);
+        [x / w, y / w, z / w]
+    };
+    // a point light right above the ground doubles the distances of the points halfway
+    assert_eq!([2.0, 0.0, -2.0], project([1.0, 5.0, -1.0], &ground.point_shadow([0.0, 10.0, 0.0])));
+    let slanted = ground.directional_shadow([1.0, -1.0, 0.0]);
+    assert_eq!([5.0, 0.0, 2.0], project([3.0, 2.0, 2.0], &slanted));
+    // the points on the plane stay in place
+    assert_eq!([4.0, 0.0, 1.0], project([4.0, 0.0, 1.0], &slanted));
+
+    let wall = Plane::from_point_normal([0.0, 0.0, 5.0], [0.0, 0.0, -1.0]);
+    let [x, y, z] = project([1.0, 1.0, 2.5], &wall.point_shadow([0.0; 3]));
+    assert!((x - 2.0).abs() < 0.0001 && (y - 2.0).abs() < 0.0001 && (z - 5.0).abs() < 0.0001);
+}
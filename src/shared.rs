@@ -0,0 +1,124 @@
+//! Camera shared between threads, updated by the simulation and read by the renderers
+
+use std::sync::{Arc, PoisonError, RwLock};
+
+use crate::math::{self, Matrix};
+use crate::{ProjectionMatrix, ProjectionMatrixBuilder};
+
+/// State of a [`SharedCamera`] at some point, with all its matrices matching each other
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraSnapshot {
+    /// Parameters of the projection
+    pub projection: ProjectionMatrixBuilder,
+    /// Projection matrix built from the parameters
+    pub matrix: ProjectionMatrix,
+    /// View matrix of the camera
+    pub view: Matrix,
+    /// Combined view and projection matrix
+    pub view_projection: Matrix,
+    /// Number of updates applied to the camera, to tell if it changed since a previous snapshot
+    pub version: u64,
+}
+
+impl CameraSnapshot {
+    /// Returns the state with the given projection and view, building its matrices
+    fn new(projection: ProjectionMatrixBuilder, view: Matrix, version: u64) -> Self {
+        let matrix = projection.build();
+        Self { projection, matrix, view, view_projection: math::multiply(&view, &matrix), version }
+    }
+}
+
+/// Handle to a camera shared between threads, where a simulation thread updates the projection and the view and the
+/// render threads take snapshots of them. Cloning the handle shares the same camera.
+///
+/// The matrices are built by the updates, before replacing the state, so a snapshot always has the matrices of a
+/// single update and taking one never builds anything. The updates are applied one after the other and the snapshots
+/// see either all of an update or none of it. A panic within an update leaves the camera untouched.
+#[derive(Clone, Debug)]
+pub struct SharedCamera {
+    state: Arc<RwLock<CameraSnapshot>>,
+}
+
+impl SharedCamera {
+    /// Returns a camera with the given projection and view
+    ///
+    /// # Panic
+    /// Like [`ProjectionMatrixBuilder::build`]
+    pub fn new(projection: ProjectionMatrixBuilder, view: Matrix) -> Self {
+        Self { state: Arc::new(RwLock::new(CameraSnapshot::new(projection, view, 0))) }
+    }
+
+    /// Returns the current state of the camera
+    pub fn snapshot(&self) -> CameraSnapshot {
+        // the state is only replaced once fully built, so a poisoned lock still holds a consistent one
+        *self.state.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the number of updates applied to the camera
+    pub fn version(&self) -> u64 {
+        self.snapshot().version
+    }
+
+    /// Changes the projection and the view in a single update, like
+    /// `camera.update(|projection, view| (projection.set_fov(60.0), view))`
+    ///
+    /// # Panic
+    /// If the update panics or like [`ProjectionMatrixBuilder::build`], leaving the camera untouched
+    pub fn update(&self, update: impl FnOnce(ProjectionMatrixBuilder, Matrix) -> (ProjectionMatrixBuilder, Matrix)) {
+        let mut state = self.state.write().unwrap_or_else(PoisonError::into_inner);
+        let (projection, view) = update(state.projection, state.view);
+        *state = CameraSnapshot::new(projection, view, state.version + 1);
+    }
+
+    /// Changes the projection with the builder setters, like `camera.update_projection(|p| p.set_fov(60.0))`
+    ///
+    /// # Panic
+    /// If the update panics or like [`ProjectionMatrixBuilder::build`], leaving the camera untouched
+    pub fn update_projection(&self, update: impl FnOnce(ProjectionMatrixBuilder) -> ProjectionMatrixBuilder) {
+        self.update(|projection, view| (update(projection), view));
+    }
+
+    /// Replaces the view matrix
+    pub fn set_view(&self, view: Matrix) {
+        self.update(|projection, _| (projection, view));
+    }
+}
+
+impl Default for SharedCamera {
+    fn default() -> Self {
+        Self::new(ProjectionMatrixBuilder::new(), math::identity())
+    }
+}
+
+#[test]
+fn shared_updates() {
+    let camera = SharedCamera::default();
+    let render = camera.clone();
+    let first = render.snapshot();
+    assert_eq!(0, first.version);
+
+    let view = math::look_at([0.0, 0.0, -5.0], [0.0; 3], [0.0, 1.0, 0.0]);
+    std::thread::spawn(move || {
+        camera.update_projection(|projection| projection.set_fov(60.0));
+        camera.set_view(view);
+    })
+    .join()
+    .unwrap();
+
+    let snapshot = render.snapshot();
+    assert_eq!(2, render.version());
+    assert_eq!(ProjectionMatrixBuilder::new().set_fov(60.0).build(), snapshot.matrix);
+    assert_eq!(math::multiply(&view, &snapshot.matrix), snapshot.view_projection);
+    assert_eq!(math::identity(), first.view);
+}
+
+#[test]
+fn panicking_update() {
+    let camera = SharedCamera::default();
+    let updater = camera.clone();
+    let result = std::thread::spawn(move || updater.update_projection(|projection| projection.set_fov(0.0))).join();
+    assert!(result.is_err());
+    assert_eq!(SharedCamera::default().snapshot(), camera.snapshot());
+    camera.set_view(math::identity());
+    assert_eq!(1, camera.version());
+}
@@ -0,0 +1,54 @@
+//! View and projection of the skybox and skydome passes, drawn around the camera whatever its position
+
+use crate::math::{self, Matrix};
+use crate::{DepthMode, ProjectionMatrixBuilder};
+
+impl ProjectionMatrixBuilder {
+    /// Returns the combined view and projection matrix of a skybox, with the translation of the view removed so the
+    /// box stays centered on the camera. With `at_far` the depth of every point is the one of the far clip, so the
+    /// box can be drawn last with a less or equal depth test behind the whole scene whatever its size.
+    ///
+    /// # Panic
+    /// Like [`ProjectionMatrixBuilder::build`]
+    pub fn build_skybox(&self, view: &Matrix, at_far: bool) -> Matrix {
+        let mut rotation = *view;
+        rotation[3] = [0.0, 0.0, 0.0, 1.0];
+        let mut matrix = math::multiply(&rotation, &self.build());
+        if at_far {
+            let depth = self.delinearize_depth(self.far);
+            for row in matrix.iter_mut() {
+                // the W-buffer depth is stored without the perspective division
+                row[2] = match self.depth_mode {
+                    DepthMode::WBuffer => 0.0,
+                    DepthMode::Standard | DepthMode::Logarithmic => row[3] * depth,
+                };
+            }
+            if self.depth_mode == DepthMode::WBuffer {
+                matrix[3][2] = depth;
+            }
+        }
+        matrix
+    }
+}
+
+#[test]
+fn skybox() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_far(100.0);
+    let view = math::look_at([10.0, 5.0, -3.0], [10.0, 5.0, 0.0], [0.0, 1.0, 0.0]);
+    let skybox = builder.build_skybox(&view, false);
+    assert_eq!(builder.build_skybox(&math::identity(), false), skybox);
+    assert_eq!(builder.build().matrix(), skybox);
+
+    let ndc_depth = |matrix: &Matrix, z: f32| {
+        let clip = math::transform_point([0.3, -0.2, z], matrix);
+        clip[2] / clip[3]
+    };
+    let far = builder.build_skybox(&view, true);
+    let far_depth = builder.delinearize_depth(100.0);
+    assert!([1.0, 10.0, 1000.0].iter().all(|&z| (ndc_depth(&far, z) - far_depth).abs() < 0.0001));
+    let clip = math::transform_point([0.3, -0.2, 1.0], &far);
+    assert_eq!([skybox[0][0] * 0.3, skybox[1][1] * -0.2], [clip[0], clip[1]]);
+
+    let w_buffer = builder.set_depth_mode(DepthMode::WBuffer).build_skybox(&view, true);
+    assert_eq!(1.0, math::transform_point([0.0, 0.0, 50.0], &w_buffer)[2]);
+}
@@ -0,0 +1,106 @@
+//! Stack of matrices for hierarchical scene traversals, in the manner of the classic OpenGL matrix stack
+
+use crate::math::{self, Matrix};
+
+/// Stack of transformations, the top of the stack is the current one
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatrixStack {
+    stack: Vec<Matrix>,
+}
+
+impl MatrixStack {
+    /// Returns a stack with the identity as the current matrix
+    pub fn new() -> Self {
+        Self { stack: vec![math::identity()] }
+    }
+
+    /// Returns the current matrix
+    pub fn current(&self) -> &Matrix {
+        self.stack.last().expect("The matrix stack is never empty")
+    }
+
+    /// Pushes a copy of the current matrix, to be restored later with [`MatrixStack::pop`]
+    pub fn push(&mut self) {
+        self.stack.push(*self.current());
+    }
+
+    /// Discards the current matrix restoring the previously pushed one
+    ///
+    /// # Panic
+    /// If there's no pushed matrix to restore
+    pub fn pop(&mut self) {
+        if self.stack.len() == 1 {
+            panic!("The matrix stack can't pop more matrices than the pushed ones");
+        }
+        self.stack.pop();
+    }
+
+    /// Replaces the current matrix
+    pub fn load(&mut self, matrix: Matrix) {
+        *self.stack.last_mut().expect("The matrix stack is never empty") = matrix;
+    }
+
+    /// Replaces the current matrix with the identity
+    pub fn load_identity(&mut self) {
+        self.load(math::identity());
+    }
+
+    /// Combines the matrix with the current one. The given transformation is applied before the current one, like the
+    /// local transformation of a child in a hierarchy.
+    pub fn multiply(&mut self, matrix: &Matrix) {
+        let combined = math::multiply(matrix, self.current());
+        self.load(combined);
+    }
+
+    /// Returns the number of matrices in the stack, at least one
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+}
+
+impl Default for MatrixStack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+fn translation(x: f32, y: f32, z: f32) -> Matrix {
+    let mut matrix = math::identity();
+    matrix[3] = [x, y, z, 1.0];
+    matrix
+}
+
+#[test]
+fn hierarchy_traversal() {
+    let mut stack = MatrixStack::new();
+    stack.multiply(&translation(1.0, 0.0, 0.0));
+    stack.push();
+    stack.multiply(&translation(0.0, 2.0, 0.0));
+    assert_eq!(2, stack.depth());
+    assert_eq!([1.0, 2.0, 0.0, 1.0], math::transform_point([0.0; 3], stack.current()));
+    stack.pop();
+    assert_eq!([1.0, 0.0, 0.0, 1.0], math::transform_point([0.0; 3], stack.current()));
+
+    stack.load(translation(5.0, 5.0, 5.0));
+    assert_eq!(&translation(5.0, 5.0, 5.0), stack.current());
+    stack.load_identity();
+    assert_eq!(&math::identity(), stack.current());
+}
+
+#[test]
+fn child_transformations_first() {
+    let mut scale = math::identity();
+    scale[0][0] = 2.0;
+    let mut stack = MatrixStack::new();
+    stack.multiply(&translation(1.0, 0.0, 0.0));
+    stack.multiply(&scale);
+    // the point is scaled in the child space and then translated by the parent
+    assert_eq!([3.0, 0.0, 0.0, 1.0], math::transform_point([1.0, 0.0, 0.0], stack.current()));
+}
+
+#[test]
+#[should_panic]
+fn matrix_stack_underflow() {
+    MatrixStack::new().pop();
+}
@@ -0,0 +1,167 @@
+//! Wireframe rendering into text, to draw the projected geometry in a terminal with Braille characters or plain ASCII
+//!
+//! Each character of the canvas holds a grid of two by four dots, the ones of the Braille patterns, so the lines get
+//! four times the vertical resolution of the rows and twice the horizontal one of the columns.
+
+use std::fmt;
+
+use crate::math::{Matrix, Vec3};
+use crate::{ProjectionMatrixBuilder, Viewport};
+
+/// Dots of each character, in columns and rows
+const CELL_SIZE: (usize, usize) = (2, 4);
+/// First Braille pattern, without dots
+const BRAILLE_BLANK: u32 = 0x2800;
+/// Bits of the dots of the Braille patterns, by rows and then columns
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// Grid of characters where the lines are drawn as dots. It displays as Braille characters.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Canvas {
+    columns: usize,
+    rows: usize,
+    dots: Vec<bool>,
+}
+
+impl Canvas {
+    /// Returns an empty canvas of the given number of characters
+    pub fn new(columns: usize, rows: usize) -> Self {
+        Self { columns, rows, dots: vec![false; columns * CELL_SIZE.0 * rows * CELL_SIZE.1] }
+    }
+
+    /// Returns the size of the canvas in dots
+    pub fn dot_size(&self) -> (usize, usize) {
+        (self.columns * CELL_SIZE.0, self.rows * CELL_SIZE.1)
+    }
+
+    /// Returns if the dot, from the top left corner, is set
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        let (width, height) = self.dot_size();
+        x < width && y < height && self.dots[y * width + x]
+    }
+
+    /// Sets the dot, from the top left corner, ignoring the ones out of the canvas
+    pub fn set(&mut self, x: usize, y: usize) {
+        let (width, height) = self.dot_size();
+        if x < width && y < height {
+            self.dots[y * width + x] = true;
+        }
+    }
+
+    /// Removes all the dots
+    pub fn clear(&mut self) {
+        self.dots.fill(false);
+    }
+
+    /// Draws the line between the given positions in dots, clipping the parts out of the canvas
+    pub fn line(&mut self, from: (f32, f32), to: (f32, f32)) {
+        let (width, height) = self.dot_size();
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        // clips the line to the canvas first, so the points close to the near clip don't walk a huge line
+        let (mut start, mut end) = (0.0f32, 1.0f32);
+        let edges = [(-dx, from.0), (dx, width as f32 - from.0), (-dy, from.1), (dy, height as f32 - from.1)];
+        for (delta, distance) in edges {
+            if delta == 0.0 {
+                if distance < 0.0 {
+                    return;
+                }
+            } else if delta < 0.0 {
+                start = start.max(distance / delta);
+            } else {
+                end = end.min(distance / delta);
+            }
+        }
+        if start > end {
+            return;
+        }
+        let steps = ((end - start) * dx.abs().max(dy.abs())).ceil().max(1.0) as usize;
+        for step in 0..=steps {
+            let t = start + (end - start) * step as f32 / steps as f32;
+            let (x, y) = (from.0 + dx * t, from.1 + dy * t);
+            if x >= 0.0 && y >= 0.0 {
+                self.set(x as usize, y as usize);
+            }
+        }
+    }
+
+    /// Returns the characters of the canvas in ASCII, a `#` for each character with any dot set, by rows ending with
+    /// new lines
+    pub fn to_ascii(&self) -> String {
+        self.render(|cell| if cell == 0 { ' ' } else { '#' })
+    }
+
+    /// Returns the characters of the canvas in rows ending with new lines, mapping the dot bits of each character
+    fn render(&self, character: impl Fn(u32) -> char) -> String {
+        let mut text = String::with_capacity((self.columns + 1) * self.rows);
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let mut cell = 0;
+                for (dy, bits) in BRAILLE_DOTS.iter().enumerate() {
+                    for (dx, bit) in bits.iter().enumerate() {
+                        if self.get(column * CELL_SIZE.0 + dx, row * CELL_SIZE.1 + dy) {
+                            cell |= bit;
+                        }
+                    }
+                }
+                text.push(character(cell));
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+impl fmt::Display for Canvas {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let braille = |cell: u32| char::from_u32(BRAILLE_BLANK + cell).unwrap_or(' ');
+        f.write_str(&self.render(braille))
+    }
+}
+
+impl ProjectionMatrixBuilder {
+    /// Draws the world space segments seen through the view matrix into the canvas. The projection takes the size of
+    /// the canvas in dots, the segments are clipped by the near clip and the parts out of the canvas are dropped.
+    pub fn draw_wireframe(&self, segments: &[(Vec3, Vec3)], view: &Matrix, canvas: &mut Canvas) {
+        let (width, height) = canvas.dot_size();
+        let projection = self.set_width(width.max(1)).set_height(height.max(1));
+        let viewport = Viewport::new(0, 0, width, height);
+        for &(a, b) in segments {
+            if let Some((a, b)) = projection.project_segment(a, b, view, &viewport) {
+                canvas.line((a[0], a[1]), (b[0], b[1]));
+            }
+        }
+    }
+}
+
+#[test]
+fn canvas_characters() {
+    let mut canvas = Canvas::new(2, 1);
+    assert_eq!((4, 4), canvas.dot_size());
+    canvas.set(0, 0);
+    canvas.set(1, 3);
+    canvas.set(9, 9);
+    assert_eq!("\u{2881}\u{2800}\n", canvas.to_string());
+    assert_eq!("# \n", canvas.to_ascii());
+    canvas.clear();
+    assert!(!canvas.get(0, 0));
+
+    canvas.line((0.0, 0.0), (3.0, 3.0));
+    assert!((0..4).all(|i| canvas.get(i, i)));
+    canvas.line((-5.0, 1.0), (2.0, 1.0));
+    assert!((0..=2).all(|x| canvas.get(x, 1)) && !canvas.get(3, 1));
+}
+
+#[test]
+fn wireframe() {
+    let builder = ProjectionMatrixBuilder::new().set_near(0.1);
+    let view = crate::math::look_at([0.0, 0.0, -3.0], [0.0; 3], [0.0, 1.0, 0.0]);
+    let mut canvas = Canvas::new(20, 10);
+    // a horizontal segment through the center and another one behind the camera
+    builder.draw_wireframe(&[([-1.0, 0.0, 0.0], [1.0, 0.0, 0.0]), ([0.0, 0.0, -5.0], [1.0, 0.0, -6.0])], &view,
+                           &mut canvas);
+    let text = canvas.to_ascii();
+    let lines: Vec<&str> = text.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+    assert_eq!(1, lines.len());
+    assert!(lines[0].chars().all(|character| character == '#'));
+    assert!(!canvas.get(0, 20) && canvas.get(20, 20));
+}
@@ -0,0 +1,104 @@
+//! Interpolation of the camera between the steps of a fixed-timestep simulation, so the frames rendered between two
+//! steps show a smooth motion
+
+use crate::math::{self, Matrix, Vec3};
+use crate::{ProjectionMatrixBuilder, Quaternion};
+
+/// State of a camera in a step of the simulation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraState {
+    /// Position of the camera
+    pub position: Vec3,
+    /// Rotation of the camera, from looking along the z-axis
+    pub orientation: Quaternion,
+    /// Parameters of the projection
+    pub projection: ProjectionMatrixBuilder,
+}
+
+impl CameraState {
+    /// Returns the state between this one, with `alpha = 0.0`, and the other one, with `alpha = 1.0`. The positions
+    /// are interpolated linearly, the orientations spherically and the projections by their parameters.
+    pub fn interpolate(&self, other: &Self, alpha: f32) -> Self {
+        Self {
+            position: math::lerp(self.position, other.position, alpha),
+            orientation: self.orientation.slerp(&other.orientation, alpha),
+            projection: self.projection.interpolate(&other.projection, alpha),
+        }
+    }
+
+    /// Returns the combined view and projection matrix of the state, see
+    /// [`ProjectionMatrixBuilder::build_view_projection`]
+    pub fn view_projection(&self) -> Matrix {
+        self.projection.build_view_projection(self.position, &self.orientation)
+    }
+}
+
+/// Camera keeping the states of the last two steps of a fixed-timestep simulation, to render the frames in between
+/// with the camera interpolated by the fraction of the step elapsed since the last one
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InterpolatedCamera {
+    previous: CameraState,
+    current: CameraState,
+}
+
+impl InterpolatedCamera {
+    /// Returns a camera resting in the given state
+    pub fn new(state: CameraState) -> Self {
+        Self { previous: state, current: state }
+    }
+
+    /// Stores the state of a new step, the current one becomes the previous one
+    pub fn push(&mut self, state: CameraState) {
+        self.previous = std::mem::replace(&mut self.current, state);
+    }
+
+    /// Returns the state of the previous step
+    pub fn previous(&self) -> &CameraState {
+        &self.previous
+    }
+
+    /// Returns the state of the last step
+    pub fn current(&self) -> &CameraState {
+        &self.current
+    }
+
+    /// Returns the state at the given fraction of the step between the previous and the current one, clamped to the
+    /// [0, 1] range so late frames don't extrapolate the motion
+    pub fn state(&self, alpha: f32) -> CameraState {
+        self.previous.interpolate(&self.current, alpha.clamp(0.0, 1.0))
+    }
+
+    /// Returns the combined view and projection matrix at the given fraction of the step, usually the time
+    /// accumulated since the last step divided by the timestep
+    pub fn view_projection(&self, alpha: f32) -> Matrix {
+        self.state(alpha).view_projection()
+    }
+}
+
+#[cfg(test)]
+fn state(position: Vec3, yaw: f32, fov: f32) -> CameraState {
+    CameraState {
+        position,
+        orientation: Quaternion::from_axis_angle([0.0, 1.0, 0.0], yaw),
+        projection: ProjectionMatrixBuilder::new().set_near(1.0).set_fov(fov),
+    }
+}
+
+#[test]
+fn interpolated_steps() {
+    let mut camera = InterpolatedCamera::new(state([0.0; 3], 0.0, 60.0));
+    assert_eq!(camera.current().view_projection(), camera.view_projection(0.5));
+
+    camera.push(state([2.0, 0.0, 0.0], 90.0, 80.0));
+    assert_eq!(state([0.0; 3], 0.0, 60.0), *camera.previous());
+    let middle = camera.state(0.5);
+    assert_eq!([1.0, 0.0, 0.0], middle.position);
+    assert!((70.0 - middle.projection.fov).abs() < 0.0001);
+    let forward = middle.orientation.rotate([0.0, 0.0, 1.0]);
+    assert!((forward[0] - forward[2]).abs() < 0.0001);
+    math::assert_matrix_eq(&middle.view_projection(), &camera.view_projection(0.5));
+
+    // the late frames wait for the next step
+    assert_eq!(camera.view_projection(1.0), camera.view_projection(1.5));
+    assert_eq!(camera.previous().view_projection(), camera.view_projection(-1.0));
+}
@@ -0,0 +1,313 @@
+//! Validation of the builder parameters, to report the bad values instead of panicking
+
+use std::error::Error;
+use std::fmt;
+
+use crate::{matrix, ProjectionMatrixBuilder};
+
+/// Invalid value of a parameter of the builder
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParamError {
+    /// Field of view outside of the supported range, from [`MIN_SUPPORTED_FOV`] to [`MAX_SUPPORTED_FOV`]
+    Fov(f32),
+    /// Near clip negative, not finite or below the minimum of [`NearPolicy::Error`]
+    Near(f32),
+    /// Far clip not finite
+    Far(f32),
+    /// Far clip closer than the near clip
+    ClipOrder {
+        /// Position of the near clip
+        near: f32,
+        /// Position of the far clip
+        far: f32,
+    },
+    /// Frame width or height of zero
+    Size {
+        /// Width of the frame
+        width: usize,
+        /// Height of the frame
+        height: usize,
+    },
+    /// Scale factor not positive
+    ScaleFactor(f32),
+    /// Render scale not positive
+    RenderScale(f32),
+    /// Overscan outside of the (-100, 100) range
+    Overscan(f32),
+    /// Safe area insets leaving no visible frame
+    SafeArea([f32; 4]),
+}
+
+impl fmt::Display for ParamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fov(_) => write!(f, "The field of view must be a value between 0.1 and 179.0"),
+            Self::Near(_) => write!(f, "The near clip must be a finite value not below 0.0"),
+            Self::Far(_) => write!(f, "The far clip must be a finite value"),
+            Self::ClipOrder { .. } => write!(f, "The view limit must be bigger than the screen position, the Z-axis \
+                                                 direction is away from the screen"),
+            Self::Size { .. } => write!(f, "The frame width and height must be positive"),
+            Self::ScaleFactor(_) => write!(f, "The scale factor must be a positive value"),
+            Self::RenderScale(_) => write!(f, "The render scale must be a positive value"),
+            Self::Overscan(_) => write!(f, "The overscan must be a percentage between -100.0 and 100.0"),
+            Self::SafeArea(_) => write!(f, "The safe area insets must leave part of the frame visible"),
+        }
+    }
+}
+
+impl Error for ParamError {}
+
+/// Narrowest supported field of view, the scale of narrower ones grows too fast to keep the precision
+pub const MIN_SUPPORTED_FOV: f32 = 0.1;
+/// Widest supported field of view, the scale of the field of view drops to zero at 180 degrees and flips the view
+/// beyond it
+pub const MAX_SUPPORTED_FOV: f32 = 179.0;
+
+/// Default minimum near clip of the [`NearPolicy`]
+pub const MIN_NEAR: f32 = 0.001;
+
+/// Handling of the near clips below a minimum, a near clip of zero collapses the depth of the whole view into the
+/// same value
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NearPolicy {
+    /// Raises the near clips below the minimum to it
+    Clamp(f32),
+    /// Rejects the near clips below the minimum
+    Error(f32),
+    /// Keeps any near clip
+    Allow,
+}
+
+impl NearPolicy {
+    /// Returns the near clip resulting from the policy
+    ///
+    /// # Panic
+    /// If the policy rejects the near clip
+    pub(crate) const fn apply(self, near: f32) -> f32 {
+        match self {
+            Self::Clamp(min) => near.max(min),
+            Self::Error(min) if near < min => panic!("The near clip must not be below the minimum of the near policy"),
+            Self::Error(_) | Self::Allow => near,
+        }
+    }
+}
+
+impl Default for NearPolicy {
+    fn default() -> Self {
+        Self::Clamp(MIN_NEAR)
+    }
+}
+
+/// Suspicious but legal setup of the builder, likely to produce precision problems
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Warning {
+    /// Near clip at zero, collapsing the depth of the whole view into the same value
+    ZeroNear,
+    /// Ratio between the far and the near clips above [`MAX_DEPTH_RATIO`], wasting the depth precision
+    DepthRatio(f32),
+    /// Field of view above [`MAX_FOV`] degrees, stretching the borders of the frame
+    WideFov(f32),
+}
+
+/// Biggest ratio between the far and the near clips not reported by [`ProjectionMatrixBuilder::validate`]
+pub const MAX_DEPTH_RATIO: f32 = 100_000.0;
+/// Widest field of view not reported by [`ProjectionMatrixBuilder::validate`]
+pub const MAX_FOV: f32 = 150.0;
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ZeroNear => write!(f, "The near clip at 0.0 leaves no depth precision"),
+            Self::DepthRatio(ratio) => write!(f, "The far clip is {ratio} times the near clip, the depth will be \
+                                                  imprecise"),
+            Self::WideFov(fov) => write!(f, "The field of view of {fov} degrees will distort the frame borders"),
+        }
+    }
+}
+
+impl ProjectionMatrixBuilder {
+    /// Returns the warnings of the parameters that are valid but likely to produce precision problems, so tools can
+    /// warn the users before they appear
+    pub fn validate(&self) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+        if self.near == 0.0 {
+            warnings.push(Warning::ZeroNear);
+        } else if self.far / self.near > MAX_DEPTH_RATIO {
+            warnings.push(Warning::DepthRatio(self.far / self.near));
+        }
+        if self.fov > MAX_FOV {
+            warnings.push(Warning::WideFov(self.fov));
+        }
+        warnings
+    }
+
+    /// Sets the near clip position in the z axis, subject to the near policy, or returns the error if it's negative,
+    /// not finite, rejected by the policy or not in front of the far clip
+    pub fn try_set_near(self, near: f32) -> Result<Self, ParamError> {
+        let below_min = matches!(self.near_policy, NearPolicy::Error(min) if near < min);
+        if !near.is_finite() || near < 0.0 || below_min {
+            return Err(ParamError::Near(near));
+        }
+        if near >= self.far {
+            return Err(ParamError::ClipOrder { near, far: self.far });
+        }
+        Ok(self.set_near(near))
+    }
+
+    /// Sets the far clip position in the z axis, or returns the error if it's not finite or not beyond the near clip
+    pub fn try_set_far(self, far: f32) -> Result<Self, ParamError> {
+        if !far.is_finite() {
+            return Err(ParamError::Far(far));
+        }
+        if far <= self.near {
+            return Err(ParamError::ClipOrder { near: self.near, far });
+        }
+        Ok(self.set_far(far))
+    }
+
+    /// Sets the field of view in grades, or returns the error if it's not within the supported [0.1, 179] range
+    pub fn try_set_fov(mut self, fov: f32) -> Result<Self, ParamError> {
+        if !(MIN_SUPPORTED_FOV..=MAX_SUPPORTED_FOV).contains(&fov) {
+            return Err(ParamError::Fov(fov));
+        }
+        self.fov = fov;
+        self.fov_scale = matrix::fov_scale(fov);
+        Ok(self)
+    }
+
+    /// Sets the width of the screen, or returns the error if it's zero
+    pub fn try_set_width(self, width: usize) -> Result<Self, ParamError> {
+        if width == 0 {
+            return Err(ParamError::Size { width, height: self.height });
+        }
+        Ok(self.set_width(width))
+    }
+
+    /// Sets the height of the screen, or returns the error if it's zero
+    pub fn try_set_height(self, height: usize) -> Result<Self, ParamError> {
+        if height == 0 {
+            return Err(ParamError::Size { width: self.width, height });
+        }
+        Ok(self.set_height(height))
+    }
+
+    /// Sets the ratio between physical and logical pixels keeping the logical size of the screen, or returns the
+    /// error if it's not positive
+    pub fn try_set_scale_factor(mut self, scale_factor: f32) -> Result<Self, ParamError> {
+        if scale_factor <= 0.0 || scale_factor.is_nan() {
+            return Err(ParamError::ScaleFactor(scale_factor));
+        }
+        let (width, height) = self.logical_size();
+        self.width = (width * scale_factor).round() as usize;
+        self.height = (height * scale_factor).round() as usize;
+        self.scale_factor = scale_factor;
+        Ok(self)
+    }
+
+    /// Sets the scale of the internal render resolution relative to the screen size, or returns the error if it's not
+    /// positive
+    pub fn try_set_render_scale(mut self, render_scale: f32) -> Result<Self, ParamError> {
+        if render_scale <= 0.0 || render_scale.is_nan() {
+            return Err(ParamError::RenderScale(render_scale));
+        }
+        self.render_scale = render_scale;
+        Ok(self)
+    }
+
+    /// Sets the percentage of the frame cropped by the screen overscan, or returns the error if it's not within the
+    /// (-100, 100) range
+    pub fn try_set_overscan(self, percent: f32) -> Result<Self, ParamError> {
+        if !(percent > -100.0 && percent < 100.0) {
+            return Err(ParamError::Overscan(percent));
+        }
+        let inset = percent / 200.0;
+        self.try_set_safe_area(inset, inset, inset, inset)
+    }
+
+    /// Sets the insets of the safe area of the frame, or returns the error if the insets of any axis leave no safe
+    /// area
+    pub fn try_set_safe_area(self, left: f32, right: f32, top: f32, bottom: f32) -> Result<Self, ParamError> {
+        if !(left + right < 1.0 && top + bottom < 1.0) {
+            return Err(ParamError::SafeArea([left, right, top, bottom]));
+        }
+        Ok(self.set_safe_area(left, right, top, bottom))
+    }
+}
+
+#[test]
+fn valid_parameters() {
+    let builder = ProjectionMatrixBuilder::new()
+        .try_set_far(100.0)
+        .and_then(|builder| builder.try_set_near(1.0))
+        .and_then(|builder| builder.try_set_fov(60.0))
+        .and_then(|builder| builder.try_set_width(1920))
+        .and_then(|builder| builder.try_set_height(1080))
+        .and_then(|builder| builder.try_set_scale_factor(2.0))
+        .and_then(|builder| builder.try_set_render_scale(0.5))
+        .and_then(|builder| builder.try_set_overscan(5.0))
+        .unwrap();
+    let expected = ProjectionMatrixBuilder::new().set_far(100.0).set_near(1.0).set_fov(60.0).set_width(1920)
+        .set_height(1080).set_scale_factor(2.0).set_render_scale(0.5).set_overscan(5.0);
+    assert_eq!(expected, builder);
+}
+
+#[test]
+fn invalid_parameters() {
+    let builder = ProjectionMatrixBuilder::new().set_far(100.0);
+    assert_eq!(Err(ParamError::Fov(360.0)), builder.try_set_fov(360.0));
+    assert!(matches!(builder.try_set_fov(f32::NAN), Err(ParamError::Fov(_))));
+    assert_eq!(Err(ParamError::Near(-1.0)), builder.try_set_near(-1.0));
+    assert!(matches!(builder.try_set_near(f32::NAN), Err(ParamError::Near(_))));
+    assert_eq!(Err(ParamError::ClipOrder { near: 200.0, far: 100.0 }), builder.try_set_near(200.0));
+    assert_eq!(Err(ParamError::ClipOrder { near: 100.0, far: 100.0 }), builder.try_set_near(100.0));
+    assert_eq!(Err(ParamError::ClipOrder { near: 0.1, far: 0.1 }), builder.try_set_far(0.1));
+    assert_eq!(Err(ParamError::Far(f32::INFINITY)), builder.try_set_far(f32::INFINITY));
+    assert_eq!(Err(ParamError::Size { width: 0, height: 720 }), builder.try_set_width(0));
+    assert_eq!(Err(ParamError::ScaleFactor(0.0)), builder.try_set_scale_factor(0.0));
+    assert_eq!(Err(ParamError::RenderScale(-1.0)), builder.try_set_render_scale(-1.0));
+    assert_eq!(Err(ParamError::Overscan(100.0)), builder.try_set_overscan(100.0));
+    assert_eq!(Err(ParamError::SafeArea([0.5, 0.5, 0.0, 0.0])), builder.try_set_safe_area(0.5, 0.5, 0.0, 0.0));
+    assert_eq!("The render scale must be a positive value", ParamError::RenderScale(0.0).to_string());
+}
+
+#[test]
+fn validation_warnings() {
+    let builder = ProjectionMatrixBuilder::new().set_far(1000.0).set_near(0.1);
+    assert!(builder.validate().is_empty());
+    assert_eq!(vec![Warning::ZeroNear], builder.set_near_policy(NearPolicy::Allow).set_near(0.0).validate());
+    let warnings = builder.set_near(0.001).validate();
+    assert!(matches!(warnings[..], [Warning::DepthRatio(ratio)] if (ratio - 1_000_000.0).abs() < 1.0));
+    assert_eq!(vec![Warning::WideFov(160.0)], builder.set_fov(160.0).validate());
+    assert_eq!("The field of view of 160 degrees will distort the frame borders", Warning::WideFov(160.0).to_string());
+}
+
+#[test]
+fn near_policy() {
+    let builder = ProjectionMatrixBuilder::new();
+    assert_eq!(0.1, builder.build().near());
+    assert_eq!(MIN_NEAR, builder.set_near(0.0).build().near());
+    assert_eq!(0.0, builder.set_near_policy(NearPolicy::Allow).set_near(0.0).build().near());
+    assert_eq!(0.5, builder.set_near(0.0).set_near_policy(NearPolicy::Clamp(0.5)).build().near());
+    let builder = builder.set_near_policy(NearPolicy::Error(0.01));
+    assert_eq!(Err(ParamError::Near(0.001)), builder.try_set_near(0.001));
+    assert_eq!(0.01, builder.try_set_near(0.01).unwrap().build().near());
+}
+
+#[test]
+#[should_panic]
+fn near_policy_error() {
+    ProjectionMatrixBuilder::new().set_near_policy(NearPolicy::Error(0.01)).set_near(0.0);
+}
+
+#[test]
+fn supported_fov_envelope() {
+    let builder = ProjectionMatrixBuilder::new();
+    for fov in [MIN_SUPPORTED_FOV, 1.0, 90.0, 170.0, MAX_SUPPORTED_FOV] {
+        let matrix = builder.try_set_fov(fov).unwrap().build();
+        assert!(matrix[1][1].is_finite() && matrix[1][1] > 0.0, "{fov}: {}", matrix[1][1]);
+        assert!((fov - (1.0 / matrix[1][1]).atan().to_degrees() * 2.0).abs() < 0.01, "{fov}");
+    }
+    for fov in [0.0, 0.05, 179.5, 180.0, 270.0] {
+        assert_eq!(Err(ParamError::Fov(fov)), builder.try_set_fov(fov));
+    }
+}
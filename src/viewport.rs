@@ -0,0 +1,349 @@
+//! Viewports and strategies to present the projected content into windows of any size
+
+use crate::{Matrix, ProjectionMatrix, ProjectionMatrixBuilder};
+
+/// Rectangle of the window where the projection is presented, in pixels
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Viewport {
+    /// Horizontal position of the left edge
+    pub x: usize,
+    /// Vertical position of the top edge
+    pub y: usize,
+    /// Width of the rectangle
+    pub width: usize,
+    /// Height of the rectangle
+    pub height: usize,
+}
+
+impl Viewport {
+    /// Returns a new viewport with the given position and size
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self { x, y, width, height }
+    }
+
+    /// Ratio between the width and the height of the viewport
+    pub fn aspect_ratio(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+}
+
+/// Strategy to present content of a fixed aspect ratio into a window with a different one
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FitMode {
+    /// Fills the whole window deforming the content
+    Stretch,
+    /// Fills the whole window keeping the horizontal extent of the content
+    FitWidth,
+    /// Fills the whole window keeping the vertical extent of the content
+    FitHeight,
+    /// Shows all the content adding black bars to the sides of the window
+    Contain,
+    /// Fills the whole window cropping the content that does not fit
+    Cover,
+}
+
+/// Arrangement of the player viewports in a split-screen
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SplitLayout {
+    /// Viewports side by side, from left to right
+    Horizontal,
+    /// Viewports stacked, from top to bottom
+    Vertical,
+    /// Viewports in rows of the same number of columns, the smallest square grid able to hold all the players
+    Grid,
+}
+
+/// Region of a view split for foveated rendering, rendered at its own resolution and presented into its viewport
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FoveatedRegion {
+    /// Projection matrix of the part of the view covered by the region
+    pub projection: Matrix,
+    /// Rectangle of the frame covered by the region
+    pub viewport: Viewport,
+    /// Resolution where the region is rendered before being presented into the viewport
+    pub resolution: (usize, usize),
+}
+
+impl ProjectionMatrixBuilder {
+    /// Builds the projection matrix and the viewport to present the content described by the builder into a window
+    /// of the given size. The aspect ratio of the content is the one defined by the builder width and height.
+    ///
+    /// The fields of view adjusted by [`FitMode::FitWidth`] and [`FitMode::Cover`] are clamped to the supported
+    /// [0.1, 179] range, so extreme window aspect ratios crop or stretch the content beyond it.
+    ///
+    /// Windows without area, like the minimized ones, get the projection of the builder and an empty viewport, so
+    /// nothing is presented until they are restored.
+    pub fn fit(&self, mode: FitMode, window_width: usize, window_height: usize) -> (ProjectionMatrix, Viewport) {
+        let window = Viewport::new(0, 0, window_width, window_height);
+        if window_width == 0 || window_height == 0 {
+            return (self.build(), Viewport::default());
+        }
+        let content_aspect = self.aspect_ratio();
+        let window_aspect = window.aspect_ratio();
+        let resized = self.set_width(window_width).set_height(window_height);
+        // keeping the horizontal extent means keeping the x-axis scale, aspect_ratio * fov_scale
+        let width_scale = self.fov_scale() * content_aspect / window_aspect;
+
+        match mode {
+            FitMode::Stretch => (self.build(), window),
+            FitMode::FitWidth => (resized.with_fov_scale(width_scale).build(), window),
+            FitMode::FitHeight => (resized.build(), window),
+            FitMode::Contain => (self.build(), contained(window, content_aspect)),
+            FitMode::Cover => {
+                let scale = width_scale.max(self.fov_scale());
+                (resized.with_fov_scale(scale).build(), window)
+            }
+        }
+    }
+
+    /// Splits a window of the given size between the specified number of players, returning the projection matrix
+    /// and viewport of each player in order. Each matrix is built with the aspect ratio of its own viewport.
+    ///
+    /// # Panic
+    /// If the number of players is zero
+    pub fn split_screen(&self, players: usize, layout: SplitLayout, window_width: usize, window_height: usize)
+        -> Vec<(ProjectionMatrix, Viewport)> {
+        if players == 0 {
+            panic!("The split-screen needs at least one player");
+        }
+        let (columns, rows) = match layout {
+            SplitLayout::Horizontal => (players, 1),
+            SplitLayout::Vertical => (1, players),
+            SplitLayout::Grid => {
+                let columns = (players as f32).sqrt().ceil() as usize;
+                (columns, players.div_ceil(columns))
+            }
+        };
+        let width = window_width / columns;
+        let height = window_height / rows;
+
+        (0..players)
+            .map(|player| {
+                let viewport = Viewport::new(player % columns * width, player / columns * height, width, height);
+                (self.set_width(width).set_height(height).build(), viewport)
+            })
+            .collect()
+    }
+
+    /// Builds the projection matrix and viewport of an inset view, like a rear-view mirror, placed over the frame
+    /// defined by the builder. The inset rectangle is given in normalized coordinates, where `(0.0, 0.0)` is the top
+    /// left corner of the frame and `(1.0, 1.0)` the bottom right one.
+    ///
+    /// # Panic
+    /// If the inset rectangle is empty or does not fit within the frame
+    pub fn inset(&self, x: f32, y: f32, width: f32, height: f32) -> (ProjectionMatrix, Viewport) {
+        if x < 0.0 || y < 0.0 || width <= 0.0 || height <= 0.0 || x + width > 1.0 || y + height > 1.0 {
+            panic!("The inset must be a non-empty rectangle within the (0.0, 0.0) and (1.0, 1.0) corners");
+        }
+        let viewport = Viewport::new(
+            (x * self.width as f32).round() as usize,
+            (y * self.height as f32).round() as usize,
+            ((width * self.width as f32).round() as usize).max(1),
+            ((height * self.height as f32).round() as usize).max(1),
+        );
+        (self.set_width(viewport.width).set_height(viewport.height).build(), viewport)
+    }
+
+    /// Splits the view into a central region, the fovea, rendered at full resolution and the regions of the periphery
+    /// around it, rendered at the resolution scaled by `periphery_scale`. The fovea rectangle is given in normalized
+    /// coordinates, like in [`ProjectionMatrixBuilder::inset`], and the regions are returned in rows from the top
+    /// left corner of the frame, skipping the empty ones when the fovea touches the edges.
+    ///
+    /// # Panic
+    /// If the fovea rectangle is empty or does not fit within the frame, or the scale is not within the (0, 1] range
+    pub fn foveated(&self, x: f32, y: f32, width: f32, height: f32, periphery_scale: f32) -> Vec<FoveatedRegion> {
+        if x < 0.0 || y < 0.0 || width <= 0.0 || height <= 0.0 || x + width > 1.0 || y + height > 1.0 {
+            panic!("The fovea must be a non-empty rectangle within the (0.0, 0.0) and (1.0, 1.0) corners");
+        }
+        if periphery_scale <= 0.0 || periphery_scale > 1.0 {
+            panic!("The periphery scale must be a value between 0.0 and 1.0");
+        }
+        let edges = |start: f32, size: f32, frame: usize| {
+            let pixel = |fraction: f32| (fraction * frame as f32).round() as usize;
+            [0, pixel(start), pixel(start + size), frame]
+        };
+        let (columns, rows) = (edges(x, width, self.width), edges(y, height, self.height));
+        let full = self.build().matrix();
+
+        let mut regions = Vec::with_capacity(9);
+        for row in 0..3 {
+            for column in 0..3 {
+                let viewport = Viewport::new(columns[column], rows[row], columns[column + 1] - columns[column],
+                                             rows[row + 1] - rows[row]);
+                if viewport.width == 0 || viewport.height == 0 {
+                    continue;
+                }
+                let to_ndc = |pixel: usize, frame: usize| pixel as f32 / frame as f32 * 2.0 - 1.0;
+                let (left, right) = (to_ndc(viewport.x, self.width), to_ndc(viewport.x + viewport.width, self.width));
+                let (top, bottom) =
+                    (-to_ndc(viewport.y, self.height), -to_ndc(viewport.y + viewport.height, self.height));
+
+                let projection = crop_ndc(&full, left, right, bottom, top);
+                let scale = if row == 1 && column == 1 { 1.0 } else { periphery_scale };
+                let resize = |size: usize| ((size as f32 * scale).round() as usize).max(1);
+                let resolution = (resize(viewport.width), resize(viewport.height));
+                regions.push(FoveatedRegion { projection, viewport, resolution });
+            }
+        }
+        regions
+    }
+}
+
+/// Returns the projection matrix of the given rectangle of the normalized device coordinates of the projection,
+/// stretching its range to cover the whole normalized device coordinates
+pub(crate) fn crop_ndc(projection: &Matrix, left: f32, right: f32, bottom: f32, top: f32) -> Matrix {
+    let mut cropped = *projection;
+    for cells in cropped.iter_mut() {
+        cells[0] = (cells[0] - (left + right) * 0.5 * cells[3]) * 2.0 / (right - left);
+        cells[1] = (cells[1] - (bottom + top) * 0.5 * cells[3]) * 2.0 / (top - bottom);
+    }
+    cropped
+}
+
+/// Returns the biggest centered rectangle of the given aspect ratio fitting into the window
+fn contained(window: Viewport, aspect_ratio: f32) -> Viewport {
+    if window.aspect_ratio() > aspect_ratio {
+        let width = (window.height as f32 * aspect_ratio).round() as usize;
+        Viewport::new((window.width - width) / 2, 0, width, window.height)
+    } else {
+        let height = (window.width as f32 / aspect_ratio).round() as usize;
+        Viewport::new(0, (window.height - height) / 2, window.width, height)
+    }
+}
+
+#[test]
+fn contain_pillarbox() {
+    let (_, viewport) = ProjectionMatrixBuilder::new()
+        .set_width(1600)
+        .set_height(1200)
+        .fit(FitMode::Contain, 1920, 1080);
+    assert_eq!(Viewport::new(240, 0, 1440, 1080), viewport);
+}
+
+#[test]
+fn contain_letterbox() {
+    let (matrix, viewport) = ProjectionMatrixBuilder::new().fit(FitMode::Contain, 1280, 1024);
+    assert_eq!(Viewport::new(0, 152, 1280, 720), viewport);
+    assert_eq!(ProjectionMatrixBuilder::new().build(), matrix);
+}
+
+#[test]
+fn fit_keeps_extents() {
+    let builder = ProjectionMatrixBuilder::new();
+    let content = builder.build();
+
+    let (matrix, viewport) = builder.fit(FitMode::FitWidth, 1024, 1024);
+    assert_eq!(Viewport::new(0, 0, 1024, 1024), viewport);
+    assert!((content[0][0] - matrix[0][0]).abs() < 0.0001);
+
+    let (matrix, _) = builder.fit(FitMode::FitHeight, 1024, 1024);
+    assert!((content[1][1] - matrix[1][1]).abs() < 0.0001);
+}
+
+#[test]
+fn fit_minimized_window() {
+    let builder = ProjectionMatrixBuilder::new();
+    for mode in [FitMode::Stretch, FitMode::FitWidth, FitMode::FitHeight, FitMode::Contain, FitMode::Cover] {
+        assert_eq!((builder.build(), Viewport::default()), builder.fit(mode, 0, 0));
+        assert_eq!((builder.build(), Viewport::default()), builder.fit(mode, 1280, 0));
+    }
+}
+
+#[test]
+fn fit_fov_envelope() {
+    let builder = ProjectionMatrixBuilder::new();
+    let (matrix, _) = builder.fit(FitMode::FitWidth, 100_000, 1);
+    assert_eq!(crate::MAX_SUPPORTED_FOV, matrix.fov());
+    let (matrix, _) = builder.fit(FitMode::Cover, 1, 100_000);
+    assert_eq!(crate::MIN_SUPPORTED_FOV, matrix.fov());
+    let (matrix, _) = builder.fit(FitMode::FitWidth, 1, 100_000);
+    assert_eq!(crate::MIN_SUPPORTED_FOV, matrix.fov());
+}
+
+#[test]
+fn cover_crops_content() {
+    let builder = ProjectionMatrixBuilder::new();
+    let content = builder.build();
+    for (width, height) in [(1024, 1024), (2560, 1080)] {
+        let (matrix, _) = builder.fit(FitMode::Cover, width, height);
+        assert!(matrix[0][0] >= content[0][0] - 0.0001);
+        assert!(matrix[1][1] >= content[1][1] - 0.0001);
+    }
+}
+
+#[test]
+fn split_screen_grid() {
+    let screens = ProjectionMatrixBuilder::new().split_screen(3, SplitLayout::Grid, 1280, 720);
+    let viewports: Vec<Viewport> = screens.iter().map(|(_, viewport)| *viewport).collect();
+    assert_eq!(vec![
+        Viewport::new(0, 0, 640, 360),
+        Viewport::new(640, 0, 640, 360),
+        Viewport::new(0, 360, 640, 360),
+    ], viewports);
+}
+
+#[test]
+fn split_screen_aspect() {
+    let fov_scale = ProjectionMatrixBuilder::new().build()[1][1];
+    let screens = ProjectionMatrixBuilder::new().split_screen(2, SplitLayout::Vertical, 1280, 720);
+    for (matrix, viewport) in screens {
+        assert_eq!(Viewport::new(0, viewport.y, 1280, 360), viewport);
+        assert!((1280.0 / 360.0 * fov_scale - matrix[0][0]).abs() < 0.0001);
+    }
+}
+
+#[test]
+#[should_panic]
+fn split_screen_without_players() {
+    ProjectionMatrixBuilder::new().split_screen(0, SplitLayout::Horizontal, 1280, 720);
+}
+
+#[test]
+fn inset_viewport() {
+    let (matrix, viewport) = ProjectionMatrixBuilder::new().inset(0.375, 0.05, 0.25, 0.2);
+    assert_eq!(Viewport::new(480, 36, 320, 144), viewport);
+    let expected = ProjectionMatrixBuilder::new().set_width(320).set_height(144).build();
+    assert_eq!(expected, matrix);
+}
+
+#[test]
+#[should_panic]
+fn inset_out_of_frame() {
+    ProjectionMatrixBuilder::new().inset(0.8, 0.8, 0.3, 0.1);
+}
+
+#[test]
+fn foveated_regions() {
+    use crate::math;
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_width(1000).set_height(800);
+    let regions = builder.foveated(0.25, 0.25, 0.5, 0.5, 0.25);
+    assert_eq!(9, regions.len());
+    assert_eq!(Viewport::new(250, 200, 500, 400), regions[4].viewport);
+    assert_eq!((500, 400), regions[4].resolution);
+    assert_eq!(Viewport::new(0, 0, 250, 200), regions[0].viewport);
+    assert_eq!((63, 50), regions[0].resolution);
+
+    // every point lands on the same pixel through the full view and its region
+    let (full, frame) = (builder.build(), Viewport::new(0, 0, 1000, 800));
+    let to_screen = |point: [f32; 3], projection: &Matrix, viewport: &Viewport| {
+        let clip = math::transform_point(point, projection);
+        crate::screen::ndc_to_screen(clip[0] / clip[3], clip[1] / clip[3], viewport)
+    };
+    for point in [[0.0, 0.0, 10.0], [-8.0, 6.0, 10.0], [3.0, -4.0, 5.0]] {
+        let (x, y) = to_screen(point, &full, &frame);
+        let region = regions.iter().find(|region| {
+            let viewport = region.viewport;
+            (viewport.x as f32..(viewport.x + viewport.width) as f32).contains(&x)
+                && (viewport.y as f32..(viewport.y + viewport.height) as f32).contains(&y)
+        }).unwrap();
+        let (region_x, region_y) = to_screen(point, &region.projection, &region.viewport);
+        assert!((x - region_x).abs() < 0.01 && (y - region_y).abs() < 0.01);
+    }
+}
+
+#[test]
+fn foveated_edges() {
+    let regions = ProjectionMatrixBuilder::new().foveated(0.0, 0.0, 0.5, 1.0, 0.5);
+    assert_eq!(2, regions.len());
+    assert_eq!(Viewport::new(640, 0, 640, 720), regions[1].viewport);
+}
@@ -0,0 +1,344 @@
+//! Tools of the computer vision, to validate and exchange cameras with calibration and reconstruction code
+//!
+//! The image points are in pixels of the viewport, with the origin at its top left corner like the rest of the
+//! crate.
+
+use crate::clip;
+use crate::math::{self, Matrix, Matrix3, Vec3};
+use crate::screen::screen_to_ndc;
+use crate::{ProjectionMatrixBuilder, Viewport};
+
+/// Projection matrix of the computer vision, three by four and multiplying column vectors on its right side. It maps
+/// world space points in homogeneous coordinates into homogeneous pixel coordinates.
+pub type CameraMatrix = [[f32; 4]; 3];
+
+/// Camera matrix split into the intrinsics of the camera and its pose, with the camera matrix being
+/// `intrinsics * [rotation | translation]`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraDecomposition {
+    /// Upper triangular matrix with the focal lengths, skew and principal point in pixels
+    pub intrinsics: Matrix3,
+    /// Rotation from the world space into the camera space of the computer vision, with the y-axis pointing down
+    pub rotation: Matrix3,
+    /// Translation applied after the rotation
+    pub translation: Vec3,
+}
+
+impl CameraDecomposition {
+    /// Returns the view matrix of the camera, in the convention of the crate
+    pub fn view(&self) -> Matrix {
+        let [right, down, forward] = self.rotation;
+        let [x, y, z] = self.translation;
+        // the view space y-axis points up instead
+        let mut view = math::identity();
+        for i in 0..3 {
+            view[i][..3].copy_from_slice(&[right[i], -down[i], forward[i]]);
+        }
+        view[3] = [x, -y, z, 1.0];
+        view
+    }
+
+    /// Returns the builder of the projection with the intrinsics of the camera presented into the viewport, and the
+    /// given clips. The skew can't be represented by the builder and is ignored.
+    pub fn to_builder(&self, viewport: &Viewport, near: f32, far: f32) -> ProjectionMatrixBuilder {
+        let (half_width, half_height) = (viewport.width as f32 * 0.5, viewport.height as f32 * 0.5);
+        let (center_x, center_y) = (viewport.x as f32 + half_width, viewport.y as f32 + half_height);
+        let [[focal_x, _, principal_x], [_, focal_y, principal_y], _] = self.intrinsics;
+        ProjectionMatrixBuilder::new()
+            .set_width(viewport.width)
+            .set_height(viewport.height)
+            .set_near(near)
+            .set_far(far)
+            .with_clip_terms(focal_x / half_width, (principal_x - center_x) / half_width, focal_y / half_height,
+                             (center_y - principal_y) / half_height)
+    }
+}
+
+/// Differences between the projection of some points and the image positions where they were observed
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReprojectionError {
+    /// Distance in pixels between the projection of each point and its observation, infinite for the points behind
+    /// the camera
+    pub errors: Vec<f32>,
+    /// Root mean square of the distances
+    pub rms: f32,
+}
+
+/// Returns the reprojection error of the world space points, projected with the combined view and projection matrix
+/// into the viewport, against their observed image positions
+///
+/// # Panic
+/// If the number of points and observations don't match
+pub fn reprojection_error(view_projection: &Matrix, viewport: &Viewport, points: &[Vec3], observations: &[(f32, f32)])
+    -> ReprojectionError {
+    if points.len() != observations.len() {
+        panic!("Each point must have one observation");
+    }
+    let errors: Vec<f32> = points
+        .iter()
+        .zip(observations)
+        .map(|(&point, &(observed_x, observed_y))| {
+            match clip::clip_to_ndc(math::transform_point(point, view_projection)) {
+                Some(ndc) => {
+                    let (x, y) = ndc.to_screen(viewport);
+                    (x - observed_x).hypot(y - observed_y)
+                }
+                None => f32::INFINITY,
+            }
+        })
+        .collect();
+    let squares: f32 = errors.iter().map(|error| error * error).sum();
+    let rms = if errors.is_empty() { 0.0 } else { (squares / errors.len() as f32).sqrt() };
+    ReprojectionError { errors, rms }
+}
+
+/// Returns the world space point observed in the given pixels of the viewport by two cameras with the given combined
+/// view and projection matrices, using the linear triangulation. Returns `None` if the cameras can't locate the
+/// point, like when they are in the same place.
+pub fn triangulate(first: &Matrix, second: &Matrix, viewport: &Viewport, first_pixel: (f32, f32),
+                   second_pixel: (f32, f32)) -> Option<Vec3> {
+    let mut equations = Vec::with_capacity(4);
+    for (view_projection, (x, y)) in [(first, first_pixel), (second, second_pixel)] {
+        let (x, y) = screen_to_ndc(x, y, viewport);
+        let column = |j: usize| [0, 1, 2, 3].map(|i| view_projection[i][j] as f64);
+        let (x_column, y_column, w_column) = (column(0), column(1), column(3));
+        // the projected coordinates are the ones observed after the perspective division
+        equations.push([0, 1, 2, 3].map(|i| x as f64 * w_column[i] - x_column[i]));
+        equations.push([0, 1, 2, 3].map(|i| y as f64 * w_column[i] - y_column[i]));
+    }
+
+    // least squares solution of the equations with the point in homogeneous coordinates
+    let mut normal = [[0.0f64; 3]; 3];
+    let mut constants = [0.0f64; 3];
+    for equation in &equations {
+        for i in 0..3 {
+            for j in 0..3 {
+                normal[i][j] += equation[i] * equation[j];
+            }
+            constants[i] -= equation[i] * equation[3];
+        }
+    }
+    solve3(&normal, constants).map(|point| point.map(|coordinate| coordinate as f32))
+}
+
+/// Returns the camera matrix projecting into the pixels of the viewport like the combined view and projection matrix
+pub fn to_camera_matrix(view_projection: &Matrix, viewport: &Viewport) -> CameraMatrix {
+    let (half_width, half_height) = (viewport.width as f32 * 0.5, viewport.height as f32 * 0.5);
+    let (center_x, center_y) = (viewport.x as f32 + half_width, viewport.y as f32 + half_height);
+    let mut camera = [[0.0; 4]; 3];
+    for (k, row) in view_projection.iter().enumerate() {
+        camera[0][k] = half_width * row[0] + center_x * row[3];
+        camera[1][k] = -half_height * row[1] + center_y * row[3];
+        camera[2][k] = row[3];
+    }
+    camera
+}
+
+/// Returns the combined view and projection matrix projecting into the viewport like the camera matrix, with the
+/// depth of the clips and depth mode of the given builder, so the depth is the same one as the one of its matrices.
+/// The camera matrix is normalized first, as it is only known up to a scale, so `w` is the view space depth in the
+/// convention of the crate.
+///
+/// # Panic
+/// If the camera matrix is degenerate
+pub fn from_camera_matrix(camera: &CameraMatrix, viewport: &Viewport, projection: &ProjectionMatrixBuilder)
+    -> Matrix {
+    let depth_row = [camera[2][0], camera[2][1], camera[2][2]];
+    let length = math::length(depth_row);
+    if length < f32::EPSILON {
+        panic!("The camera matrix must have a depth direction");
+    }
+    // the y-axis of the pixels points down, flipping the sign of the determinant in front of the camera
+    let rotation = [0, 1, 2].map(|i| [camera[i][0], camera[i][1], camera[i][2]]);
+    let determinant = math::dot(rotation[0], math::cross(rotation[1], rotation[2]));
+    let scale = if determinant > 0.0 { -1.0 / length } else { 1.0 / length };
+
+    let (half_width, half_height) = (viewport.width as f32 * 0.5, viewport.height as f32 * 0.5);
+    let (center_x, center_y) = (viewport.x as f32 + half_width, viewport.y as f32 + half_height);
+    let (depth_scale, depth_offset) = projection.depth_terms();
+    let mut view_projection = [[0.0; 4]; 4];
+    for (k, row) in view_projection.iter_mut().enumerate() {
+        let w = camera[2][k] * scale;
+        row[0] = (camera[0][k] * scale - center_x * w) / half_width;
+        row[1] = -(camera[1][k] * scale - center_y * w) / half_height;
+        row[2] = depth_scale * w;
+        row[3] = w;
+    }
+    view_projection[3][2] += depth_offset;
+    view_projection
+}
+
+/// Splits the camera matrix into the intrinsics and pose of the camera, with the RQ decomposition. The camera matrix
+/// is normalized first, as it is only known up to a scale. Returns `None` if the camera matrix is degenerate.
+pub fn decompose_camera_matrix(camera: &CameraMatrix) -> Option<CameraDecomposition> {
+    let rows = camera.map(|row| [row[0], row[1], row[2]]);
+    // the y-axis of the pixels points down, the rotation is a reflection of the proper ones of the crate
+    let sign = if math::dot(rows[0], math::cross(rows[1], rows[2])) > 0.0 { -1.0 } else { 1.0 };
+    let rows = rows.map(|row| math::scale(row, sign));
+
+    // Gram-Schmidt orthonormalization from the last row
+    let mut intrinsics = [[0.0; 3]; 3];
+    let mut rotation = [[0.0; 3]; 3];
+    for i in (0..3).rev() {
+        let mut row = rows[i];
+        for j in i + 1..3 {
+            intrinsics[i][j] = math::dot(rows[i], rotation[j]);
+            row = math::sub(row, math::scale(rotation[j], intrinsics[i][j]));
+        }
+        intrinsics[i][i] = math::length(row);
+        if intrinsics[i][i] < f32::EPSILON {
+            return None;
+        }
+        rotation[i] = math::scale(row, 1.0 / intrinsics[i][i]);
+    }
+
+    // the translation solves the upper triangular intrinsics with the last column
+    let column = [0, 1, 2].map(|i| camera[i][3] * sign);
+    let mut translation = [0.0; 3];
+    for i in (0..3).rev() {
+        let known: f32 = (i + 1..3).map(|j| intrinsics[i][j] * translation[j]).sum();
+        translation[i] = (column[i] - known) / intrinsics[i][i];
+    }
+    let scale = intrinsics[2][2];
+    Some(CameraDecomposition { intrinsics: intrinsics.map(|row| row.map(|cell| cell / scale)), rotation, translation })
+}
+
+/// Returns the homography mapping the coordinates over a world space plane into the pixels of the viewport, seen
+/// through the combined view and projection matrix. The plane coordinates `(u, v)` are the point
+/// `origin + u * u_axis + v * v_axis`, so the axes define the orientation and scale of the coordinates.
+pub fn plane_homography(view_projection: &Matrix, viewport: &Viewport, origin: Vec3, u_axis: Vec3, v_axis: Vec3)
+    -> Matrix3 {
+    let camera = to_camera_matrix(view_projection, viewport);
+    camera.map(|row| {
+        let direction = |axis: Vec3| row[0] * axis[0] + row[1] * axis[1] + row[2] * axis[2];
+        [direction(u_axis), direction(v_axis), direction(origin) + row[3]]
+    })
+}
+
+/// Returns the pixel where the homography maps the plane coordinates, or `None` if they are mapped to the infinity
+pub fn apply_homography(homography: &Matrix3, u: f32, v: f32) -> Option<(f32, f32)> {
+    let [x, y, w] = homography.map(|row| row[0] * u + row[1] * v + row[2]);
+    (w.abs() > f32::EPSILON).then(|| (x / w, y / w))
+}
+
+/// Returns the solution of the three by three linear system, or `None` if it's singular
+fn solve3(matrix: &[[f64; 3]; 3], constants: [f64; 3]) -> Option<[f64; 3]> {
+    let determinant = |m: &[[f64; 3]; 3]| {
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1]) - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    };
+    let scale = matrix.iter().flatten().fold(0.0f64, |max, cell| max.max(cell.abs()));
+    let divisor = determinant(matrix);
+    if divisor.abs() <= f64::EPSILON * scale * scale * scale {
+        return None;
+    }
+    // Cramer's rule
+    Some([0, 1, 2].map(|j| {
+        let mut replaced = *matrix;
+        for (row, constant) in replaced.iter_mut().zip(constants) {
+            row[j] = constant;
+        }
+        determinant(&replaced) / divisor
+    }))
+}
+
+#[test]
+fn reprojection() {
+    let builder = crate::ProjectionMatrixBuilder::new().set_near(1.0).set_width(720);
+    let view = math::look_at([0.0, 0.0, -10.0], [0.0; 3], [0.0, 1.0, 0.0]);
+    let view_projection = math::multiply(&view, &builder.build());
+    let viewport = Viewport::new(0, 0, 720, 720);
+
+    let points = [[0.0; 3], [5.0, 5.0, 0.0], [0.0, 0.0, -20.0]];
+    let error = reprojection_error(&view_projection, &viewport, &points[..2], &[(363.0, 364.0), (540.0, 180.0)]);
+    assert_eq!(vec![5.0, 0.0], error.errors);
+    assert!((12.5f32.sqrt() - error.rms).abs() < 0.0001);
+
+    let error = reprojection_error(&view_projection, &viewport, &points, &[(360.0, 360.0); 3]);
+    assert!(error.errors[2].is_infinite() && error.rms.is_infinite());
+    assert_eq!(0.0, reprojection_error(&view_projection, &viewport, &[], &[]).rms);
+}
+
+#[test]
+#[should_panic]
+fn reprojection_without_observations() {
+    reprojection_error(&math::identity(), &Viewport::new(0, 0, 1, 1), &[[0.0; 3]], &[]);
+}
+
+#[test]
+fn two_view_triangulation() {
+    let builder = crate::ProjectionMatrixBuilder::new().set_near(0.1).set_width(720);
+    let viewport = Viewport::new(0, 0, 720, 720);
+    let projection = builder.build();
+    let left = math::multiply(&math::look_at([-1.0, 0.0, -10.0], [0.0; 3], [0.0, 1.0, 0.0]), &projection);
+    let right = math::multiply(&math::look_at([2.0, 1.0, -9.0], [0.0; 3], [0.0, 1.0, 0.0]), &projection);
+
+    let point = [0.5, -1.5, 2.0];
+    let pixel = |view_projection: &Matrix| {
+        clip::clip_to_ndc(math::transform_point(point, view_projection)).unwrap().to_screen(&viewport)
+    };
+    let triangulated = triangulate(&left, &right, &viewport, pixel(&left), pixel(&right)).unwrap();
+    assert!((0..3).all(|i| (point[i] - triangulated[i]).abs() < 0.001), "{point:?} != {triangulated:?}");
+    assert_eq!(None, triangulate(&left, &left, &viewport, pixel(&left), pixel(&left)));
+}
+
+#[test]
+fn camera_matrix_conversion() {
+    let builder = crate::ProjectionMatrixBuilder::new().set_near(1.0).set_far(100.0);
+    let viewport = Viewport::new(10, 20, 1280, 720);
+    let view_projection = math::multiply(&math::look_at([1.0, 2.0, -8.0], [0.0; 3], [0.0, 1.0, 0.0]), &builder.build());
+    let camera = to_camera_matrix(&view_projection, &viewport);
+
+    let point = [2.0, -1.0, 3.0];
+    let (x, y) = clip::clip_to_ndc(math::transform_point(point, &view_projection)).unwrap().to_screen(&viewport);
+    let homogeneous = camera.map(|row| row[0] * point[0] + row[1] * point[1] + row[2] * point[2] + row[3]);
+    assert!((x - homogeneous[0] / homogeneous[2]).abs() < 0.001 && (y - homogeneous[1] / homogeneous[2]).abs() < 0.001);
+
+    // any scale of the camera matrix gives back the same matrix, with the depth of the builder
+    let scaled = camera.map(|row| row.map(|cell| cell * -3.0));
+    let converted = from_camera_matrix(&scaled, &viewport, &builder);
+    for j in 0..4 {
+        // the depth column of the crate is thousands of times bigger than the others
+        let tolerance = 0.0001 * (0..4).map(|i| view_projection[i][j].abs()).fold(1.0, f32::max);
+        assert!((0..4).all(|i| (view_projection[i][j] - converted[i][j]).abs() < tolerance), "{converted:?}");
+    }
+    let ndc_depth = |matrix: &Matrix| {
+        let clip = math::transform_point([0.0; 3], matrix);
+        clip[2] / clip[3]
+    };
+    let view_z = math::length([1.0, 2.0, -8.0]);
+    assert!((builder.delinearize_depth(view_z) - ndc_depth(&converted)).abs() < 0.001);
+    let linear = builder.linearize_depth(ndc_depth(&converted));
+    assert!((builder.linearize_depth(ndc_depth(&view_projection)) - linear).abs() < 0.0001);
+}
+
+#[test]
+fn camera_matrix_decomposition() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_far(100.0).set_safe_area(0.1, 0.0, 0.0, 0.05);
+    let viewport = Viewport::new(0, 0, 1280, 720);
+    let view = math::look_at([1.0, 2.0, -8.0], [0.0; 3], [0.0, 1.0, 0.0]);
+    let camera = to_camera_matrix(&math::multiply(&view, &builder.build()), &viewport);
+    let decomposition = decompose_camera_matrix(&camera.map(|row| row.map(|cell| cell * -0.5))).unwrap();
+
+    let [[_, skew, _], [zero, _, _], [_, _, one]] = decomposition.intrinsics;
+    assert!(skew.abs() < 0.001 && zero == 0.0 && one == 1.0);
+    math::assert_matrix_eq(&view, &decomposition.view());
+    math::assert_matrix_eq(&builder.build(), &decomposition.to_builder(&viewport, 1.0, 100.0).build());
+    assert_eq!(None, decompose_camera_matrix(&[[0.0; 4]; 3]));
+}
+
+#[test]
+fn ground_plane_homography() {
+    let builder = ProjectionMatrixBuilder::new().set_near(1.0).set_far(100.0);
+    let viewport = Viewport::new(0, 0, 1280, 720);
+    let view_projection =
+        math::multiply(&math::look_at([0.0, 5.0, -10.0], [0.0; 3], [0.0, 1.0, 0.0]), &builder.build());
+    let homography = plane_homography(&view_projection, &viewport, [1.0, 0.0, 1.0], [2.0, 0.0, 0.0], [0.0, 0.0, 2.0]);
+
+    for (u, v) in [(0.0, 0.0), (1.5, -2.0), (-3.0, 4.0)] {
+        let point = [1.0 + 2.0 * u, 0.0, 1.0 + 2.0 * v];
+        let (x, y) = clip::clip_to_ndc(math::transform_point(point, &view_projection)).unwrap().to_screen(&viewport);
+        let (mapped_x, mapped_y) = apply_homography(&homography, u, v).unwrap();
+        assert!((x - mapped_x).abs() < 0.01 && (y - mapped_y).abs() < 0.01);
+    }
+}